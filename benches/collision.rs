@@ -0,0 +1,64 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use snake_rust::elements::{Direction, FoodKind, GridPosition, Snake};
+use snake_rust::grid::{CellKind, Occupancy};
+
+const BOARD: (i16, i16) = (200, 200);
+const LENGTHS: [usize; 4] = [10, 100, 1_000, 10_000];
+
+/// Feeds a snake along a serpentine route until it is `length` cells long.
+/// The route is a closed loop over the whole board, so the snake can keep
+/// following it for as long as the benchmark runs without hitting itself.
+fn long_snake(length: usize) -> (Snake, Occupancy) {
+    let mut grid = Occupancy::new(BOARD);
+    let mut snake = Snake::new(GridPosition::new(2, 1));
+    for pos in snake.positions() {
+        grid.set(pos, CellKind::Snake);
+    }
+
+    while snake.length() < length {
+        snake.steer(next_dir(snake.head_pos()));
+        let food = GridPosition::new_from_move_in(snake.head_pos(), snake.next_dir.unwrap(), BOARD);
+        grid.place_food(food, FoodKind::Mouse);
+        snake.update(&mut grid);
+        grid.remove_food(food);
+    }
+    (snake, grid)
+}
+
+fn next_dir(head: GridPosition) -> Direction {
+    let (x, y) = head.coords();
+    if y % 2 == 1 {
+        if x + 2 < BOARD.0 { Direction::Right } else { Direction::Down }
+    } else if x > 1 {
+        Direction::Left
+    } else {
+        Direction::Down
+    }
+}
+
+fn collision(c: &mut Criterion) {
+    let mut group = c.benchmark_group("eats_self");
+    for length in LENGTHS.iter() {
+        let (snake, grid) = long_snake(*length);
+        group.bench_with_input(BenchmarkId::from_parameter(length), length, |b, _| {
+            b.iter(|| black_box(&snake).eats_self(black_box(&grid)))
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("update");
+    for length in LENGTHS.iter() {
+        let (mut snake, mut grid) = long_snake(*length);
+        group.bench_with_input(BenchmarkId::from_parameter(length), length, |b, _| {
+            b.iter(|| {
+                snake.steer(next_dir(snake.head_pos()));
+                snake.update(&mut grid);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, collision);
+criterion_main!(benches);
@@ -0,0 +1,113 @@
+//! A bot in a program of its own, speaking the protocol `--bot-cmd` uses:
+//! one line of JSON a tick on standard input, one move a line on standard
+//! output. It heads for the nearest mouse that is not poisoned and never
+//! moves onto a wall or a snake if it can help it.
+//!
+//! ```bash
+//! cargo build --example bot
+//! cargo run -- --bot-cmd target/debug/examples/bot
+//! cargo run --bin snake_arena -- greedy --bot-cmd target/debug/examples/bot
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, Write};
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Snake {
+    segments: Vec<(i16, i16)>,
+    dir: String,
+}
+
+#[derive(Deserialize)]
+struct Food {
+    x: i16,
+    y: i16,
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct State {
+    width: i16,
+    height: i16,
+    walls: Vec<(i16, i16)>,
+    holes: Vec<(i16, i16)>,
+    you: Snake,
+    snakes: Vec<Snake>,
+    food: Vec<Food>,
+}
+
+const MOVES: [(&str, (i16, i16)); 4] = [("up", (0, -1)), ("down", (0, 1)), ("left", (-1, 0)), ("right", (1, 0))];
+
+fn opposite(dir: &str) -> &str {
+    match dir {
+        "up" => "down",
+        "down" => "up",
+        "left" => "right",
+        _ => "left",
+    }
+}
+
+/// Where a move from `from` lands, across the board through a hole in the
+/// border.
+fn step(state: &State, from: (i16, i16), by: (i16, i16)) -> (i16, i16) {
+    let x = (from.0 + by.0).rem_euclid(state.width);
+    let mut y = from.1 + by.1;
+    // Row 0 is the score bar, so the rows wrap between 1 and the bottom.
+    if y < 1 {
+        y = state.height - 1;
+    } else if y >= state.height {
+        y = 1;
+    }
+    (x, y)
+}
+
+fn choose(state: &State) -> &'static str {
+    let mut blocked: HashSet<(i16, i16)> = state.walls.iter().cloned().collect();
+    for snake in state.snakes.iter().chain(Some(&state.you)) {
+        blocked.extend(snake.segments.iter().cloned());
+    }
+    let head = state.you.segments[0];
+    let back = opposite(&state.you.dir);
+
+    let safe: Vec<(&'static str, (i16, i16))> = MOVES.iter()
+        .filter(|(name, _)| *name != back)
+        .map(|(name, by)| (*name, step(state, head, *by)))
+        .filter(|(_, to)| !blocked.contains(to))
+        .filter(|(_, to)| state.holes.contains(to) || (to.0 - head.0).abs() + (to.1 - head.1).abs() == 1)
+        .collect();
+
+    let distance = |a: (i16, i16), b: (i16, i16)| (a.0 - b.0).abs() + (a.1 - b.1).abs();
+    let target = state.food.iter()
+                           .filter(|food| food.kind != "poisoned")
+                           .map(|food| (food.x, food.y))
+                           .min_by_key(|food| distance(head, *food));
+    let best = match target {
+        Some(target) => safe.iter().min_by_key(|(_, to)| distance(*to, target)),
+        None => safe.first(),
+    };
+    best.map(|(name, _)| *name).unwrap_or("up")
+}
+
+fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        let answer = match serde_json::from_str::<State>(&line) {
+            Ok(state) => choose(&state),
+            Err(e) => {
+                eprintln!("bad state: {}", e);
+                "none"
+            }
+        };
+        if writeln!(out, "{}", answer).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+}
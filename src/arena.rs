@@ -0,0 +1,378 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts::*;
+use crate::elements::{GridPosition, Snake, Wall};
+use crate::grid::{CellKind, Occupancy};
+use crate::topology::Topology;
+
+/// Draws the walls of a fresh arena. What comes out is only a draft: `build`
+/// clears the start, checks it and seals off what the snake could never reach.
+pub trait ArenaGenerator {
+    /// Wall cells of the arena.
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition>;
+
+    /// The cells that must stay open and connected when `start` has to.
+    fn keep(&self, start: HashSet<GridPosition>, _size: (i16, i16)) -> HashSet<GridPosition> {
+        start
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ArenaKind {
+    /// The border with random holes in the sides, as it always was.
+    #[default]
+    Plain,
+    Obstacles,
+    Maze,
+    Caves,
+    /// Mirrored both ways, so two snakes starting on either side get the
+    /// same board.
+    Symmetric,
+}
+
+impl ArenaKind {
+    pub const ALL: [ArenaKind; 5] = [ArenaKind::Plain, ArenaKind::Obstacles, ArenaKind::Maze,
+                                     ArenaKind::Caves, ArenaKind::Symmetric];
+
+    /// The arena after this one on the start screen.
+    pub fn next(&self) -> Self {
+        let i = ArenaKind::ALL.iter().position(|kind| kind == self).unwrap();
+        ArenaKind::ALL[(i + 1) % ArenaKind::ALL.len()]
+    }
+
+    pub fn generator(&self) -> Box<dyn ArenaGenerator> {
+        match *self {
+            ArenaKind::Plain => Box::new(Plain),
+            ArenaKind::Obstacles => Box::new(Obstacles),
+            ArenaKind::Maze => Box::new(Maze),
+            ArenaKind::Caves => Box::new(Caves),
+            ArenaKind::Symmetric => Box::new(Symmetric),
+        }
+    }
+
+    /// Walls for a game starting with `snake` on a board joined by
+    /// `topology`. The snake and the cells right in front of it are kept open,
+    /// every open cell can be reached from the head and at least
+    /// `ARENA_MIN_FREE` of the board is floor. A draft that falls short is
+    /// thrown away and drawn again; after `ARENA_TRIES` of them the plain
+    /// border is used instead.
+    pub fn build(&self, rng: &mut Pcg32, size: (i16, i16), snake: &Snake, topology: Topology) -> Wall {
+        self.build_around(rng, size, std::slice::from_ref(snake), topology)
+    }
+
+    /// Like `build`, with room kept for every one of `snakes`.
+    pub fn build_around(&self, rng: &mut Pcg32, size: (i16, i16), snakes: &[Snake], topology: Topology) -> Wall {
+        if *self == ArenaKind::Plain {
+            return topology.shape(Wall::bordered(rng, size), size);
+        }
+
+        let mut start: HashSet<GridPosition> = HashSet::new();
+        for snake in snakes {
+            start.extend(snake.positions());
+            let mut ahead = Some(snake.head_pos());
+            for _ in 0..ARENA_START_LANE {
+                ahead = ahead.and_then(|pos| topology.step(pos, snake.dir, size));
+                start.extend(ahead);
+            }
+        }
+
+        let generator = self.generator();
+        let keep = generator.keep(start, size);
+        for _ in 0..ARENA_TRIES {
+            let draft = topology.shape(Wall::from_positions(generator.carve(rng, size)), size);
+            if let Some(walls) = seal(draft.positions().collect(), size, &keep, topology) {
+                return walls;
+            }
+        }
+        topology.shape(Wall::bordered(rng, size), size)
+    }
+}
+
+impl fmt::Display for ArenaKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ArenaKind::Plain => write!(f, "plain"),
+            ArenaKind::Obstacles => write!(f, "obstacles"),
+            ArenaKind::Maze => write!(f, "maze"),
+            ArenaKind::Caves => write!(f, "caves"),
+            ArenaKind::Symmetric => write!(f, "symmetric"),
+        }
+    }
+}
+
+impl FromStr for ArenaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "plain" => Ok(ArenaKind::Plain),
+            "obstacles" => Ok(ArenaKind::Obstacles),
+            "maze" => Ok(ArenaKind::Maze),
+            "caves" => Ok(ArenaKind::Caves),
+            "symmetric" => Ok(ArenaKind::Symmetric),
+            _ => Err(format!("unknown arena: {}", s)),
+        }
+    }
+}
+
+/// Walls on `cells` with every pocket the head cannot reach filled in, or
+/// `None` when a kept cell is cut off or too little floor is left.
+fn seal(mut cells: HashSet<GridPosition>, size: (i16, i16), keep: &HashSet<GridPosition>,
+        topology: Topology) -> Option<Wall> {
+    for pos in keep.iter() {
+        cells.remove(pos);
+    }
+    let mut grid = Occupancy::new(size).with_topology(topology);
+    for pos in cells.iter() {
+        grid.set(*pos, CellKind::Wall);
+    }
+
+    let start = keep.iter().min_by_key(|pos| pos.coords())?;
+    let open = grid.reachable(*start);
+    if keep.iter().any(|pos| !open.contains(pos)) {
+        return None;
+    }
+
+    let mut free = 0;
+    for y in 1..size.1 {
+        for x in 0..size.0 {
+            let pos = GridPosition::new(x, y);
+            if cells.contains(&pos) {
+                continue;
+            }
+            if open.contains(&pos) {
+                free += 1;
+            } else {
+                cells.insert(pos);
+            }
+        }
+    }
+    if (free as f32) < ARENA_MIN_FREE * (size.0 as f32 * (size.1 - 1) as f32) {
+        return None;
+    }
+
+    let mut cells: Vec<GridPosition> = cells.into_iter().collect();
+    cells.sort_by_key(|pos| (pos.coords().1, pos.coords().0));
+    Some(Wall::from_positions(cells))
+}
+
+/// The solid border around the play area, below the score bar.
+fn border(size: (i16, i16)) -> HashSet<GridPosition> {
+    let mut cells = HashSet::new();
+    for x in 0..size.0 {
+        cells.insert(GridPosition::new(x, 1));
+        cells.insert(GridPosition::new(x, size.1 - 1));
+    }
+    for y in 2..size.1 - 1 {
+        cells.insert(GridPosition::new(0, y));
+        cells.insert(GridPosition::new(size.0 - 1, y));
+    }
+    cells
+}
+
+/// Whether `pos` is inside the border.
+fn inside(pos: GridPosition, size: (i16, i16)) -> bool {
+    let (x, y) = pos.coords();
+    x > 0 && x < size.0 - 1 && y > 1 && y < size.1 - 1
+}
+
+pub struct Plain;
+
+impl ArenaGenerator for Plain {
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition> {
+        Wall::bordered(rng, size).positions().collect()
+    }
+}
+
+/// The plain border with blocks of one to three cells a side scattered inside.
+pub struct Obstacles;
+
+impl Obstacles {
+    /// The smallest board the biggest block fits on, clear of the border.
+    /// Smaller boards only get the plain border.
+    pub const MIN_SIZE: (i16, i16) = (8, 9);
+}
+
+impl ArenaGenerator for Obstacles {
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition> {
+        if size.0 < Obstacles::MIN_SIZE.0 || size.1 < Obstacles::MIN_SIZE.1 {
+            return Plain.carve(rng, size);
+        }
+        let mut cells: HashSet<GridPosition> = Wall::bordered(rng, size).positions().collect();
+        for _ in 0..rng.gen_range(ARENA_OBSTACLES / 2, ARENA_OBSTACLES + 1) {
+            let (width, height) = (rng.gen_range(1, 4), rng.gen_range(1, 4));
+            let x = rng.gen_range(2, size.0 - 2 - width);
+            let y = rng.gen_range(3, size.1 - 2 - height);
+            for dx in 0..width {
+                for dy in 0..height {
+                    cells.insert(GridPosition::new(x + dx, y + dy));
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// Corridors one cell wide dug by a recursive backtracker between the cells
+/// on every other row and column, with a few walls knocked out afterwards so
+/// there are loops to escape through.
+pub struct Maze;
+
+impl ArenaGenerator for Maze {
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition> {
+        let mut cells = HashSet::new();
+        for x in 0..size.0 {
+            for y in 1..size.1 {
+                cells.insert(GridPosition::new(x, y));
+            }
+        }
+
+        // Rooms sit on odd columns and even rows, so the walls between them
+        // line up with the border.
+        let rooms = ((size.0 - 1) / 2, (size.1 - 2) / 2);
+        let room = |(i, j): (i16, i16)| GridPosition::new(1 + 2 * i, 2 + 2 * j);
+        let mut visited = HashSet::new();
+        let mut stack = vec![(0, 0)];
+        visited.insert((0, 0));
+        cells.remove(&room((0, 0)));
+        while let Some(&(i, j)) = stack.last() {
+            let next: Vec<(i16, i16)> = [(i + 1, j), (i - 1, j), (i, j + 1), (i, j - 1)].iter()
+                .cloned()
+                .filter(|(a, b)| *a >= 0 && *b >= 0 && *a < rooms.0 && *b < rooms.1)
+                .filter(|r| !visited.contains(r))
+                .collect();
+            if next.is_empty() {
+                stack.pop();
+                continue;
+            }
+            let (a, b) = next[rng.gen_range(0, next.len())];
+            cells.remove(&room((a, b)));
+            cells.remove(&GridPosition::new(1 + i + a, 2 + j + b));
+            visited.insert((a, b));
+            stack.push((a, b));
+        }
+
+        // Walls are visited in a fixed order so the same seed knocks out the
+        // same ones.
+        let mut walls: Vec<GridPosition> = cells.iter().cloned().filter(|pos| inside(*pos, size)).collect();
+        walls.sort_by_key(|pos| pos.coords());
+        for pos in walls {
+            if rng.gen::<f32>() < ARENA_MAZE_LOOPS {
+                cells.remove(&pos);
+            }
+        }
+        cells
+    }
+}
+
+/// Caves grown by a cellular automaton: random rock, then a few rounds where
+/// a cell turns to rock with five or more rocky neighbours and to floor with
+/// fewer than four.
+pub struct Caves;
+
+impl ArenaGenerator for Caves {
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition> {
+        let mut cells = border(size);
+        for x in 1..size.0 - 1 {
+            for y in 2..size.1 - 1 {
+                if rng.gen::<f32>() < ARENA_CAVE_ROCK {
+                    cells.insert(GridPosition::new(x, y));
+                }
+            }
+        }
+
+        for _ in 0..ARENA_CAVE_ROUNDS {
+            let mut next = border(size);
+            for x in 1..size.0 - 1 {
+                for y in 2..size.1 - 1 {
+                    let mut rock = 0;
+                    for dx in -1..=1 {
+                        for dy in -1..=1 {
+                            let pos = GridPosition::new(x + dx, y + dy);
+                            if (dx, dy) != (0, 0) && (!inside(pos, size) || cells.contains(&pos)) {
+                                rock += 1;
+                            }
+                        }
+                    }
+                    let pos = GridPosition::new(x, y);
+                    if rock >= 5 || (rock == 4 && cells.contains(&pos)) {
+                        next.insert(pos);
+                    }
+                }
+            }
+            cells = next;
+        }
+        cells
+    }
+}
+
+/// Obstacles drawn in one quarter and mirrored into the other three, with
+/// holes in the border that mirror too.
+pub struct Symmetric;
+
+impl Symmetric {
+    /// The smallest board the biggest block fits on in one quarter, clear of
+    /// the border. Smaller boards only get the plain border.
+    pub const MIN_SIZE: (i16, i16) = (12, 10);
+
+    /// `pos` and its mirror images across the middle column and the middle
+    /// row of the play area. The score bar is not mirrored.
+    fn mirrors(pos: GridPosition, size: (i16, i16)) -> Vec<GridPosition> {
+        let (x, y) = pos.coords();
+        let mx = size.0 - 1 - x;
+        if y == 0 {
+            return vec![pos, GridPosition::new(mx, y)];
+        }
+        let my = size.1 - y;
+        vec![pos, GridPosition::new(mx, y), GridPosition::new(x, my), GridPosition::new(mx, my)]
+    }
+}
+
+impl ArenaGenerator for Symmetric {
+    fn carve(&self, rng: &mut Pcg32, size: (i16, i16)) -> HashSet<GridPosition> {
+        if size.0 < Symmetric::MIN_SIZE.0 || size.1 < Symmetric::MIN_SIZE.1 {
+            return Plain.carve(rng, size);
+        }
+        let mut quarter = HashSet::new();
+        for y in 2..=size.1 / 2 {
+            if rng.gen::<f32>() < ARENA_HOLES {
+                quarter.insert(GridPosition::new(0, y));
+            }
+        }
+        let mut blocks = HashSet::new();
+        for _ in 0..rng.gen_range(ARENA_OBSTACLES / 4, ARENA_OBSTACLES / 2 + 1) {
+            let (width, height) = (rng.gen_range(1, 4), rng.gen_range(1, 3));
+            let x = rng.gen_range(2, size.0 / 2 - width);
+            let y = rng.gen_range(3, size.1 / 2 - height + 1);
+            for dx in 0..width {
+                for dy in 0..height {
+                    blocks.insert(GridPosition::new(x + dx, y + dy));
+                }
+            }
+        }
+
+        let mut cells = border(size);
+        for hole in quarter.iter() {
+            for pos in Symmetric::mirrors(*hole, size) {
+                cells.remove(&pos);
+            }
+        }
+        for block in blocks.iter() {
+            cells.extend(Symmetric::mirrors(*block, size));
+        }
+        cells
+    }
+
+    /// Whatever has to stay open stays open in every quarter, so a second
+    /// snake can start across from the first.
+    fn keep(&self, start: HashSet<GridPosition>, size: (i16, i16)) -> HashSet<GridPosition> {
+        start.iter().flat_map(|pos| Symmetric::mirrors(*pos, size)).collect()
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use ggez::{audio,
+           graphics,
+           Context,
+           GameResult};
+
+use crate::audio::{Sound, PLAYLIST};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sprite {
+    Floor,
+    Wall,
+    Mouse,
+    GoldenMouse,
+    PoisonedMouse,
+    BonusMouse,
+    SlowTime,
+    Ghost,
+    WallBreaker,
+    Scissors,
+    Teleporter,
+    OneWay,
+    Conveyor,
+    Key,
+    Switch,
+    Door,
+    Gate,
+    Head,
+    Body,
+    Tail,
+    Blood,
+    HoleWall,
+}
+
+impl Sprite {
+    fn path(&self) -> &'static str {
+        match *self {
+            Sprite::Floor => "/floor.png",
+            Sprite::Wall => "/wall.png",
+            Sprite::Mouse => "/mouse.png",
+            Sprite::GoldenMouse => "/golden_mouse.png",
+            Sprite::PoisonedMouse => "/poisoned_mouse.png",
+            Sprite::BonusMouse => "/bonus_mouse.png",
+            Sprite::SlowTime => "/slow_time.png",
+            Sprite::Ghost => "/ghost.png",
+            Sprite::WallBreaker => "/wall_breaker.png",
+            Sprite::Scissors => "/scissors.png",
+            Sprite::Teleporter => "/teleporter.png",
+            Sprite::OneWay => "/one_way.png",
+            Sprite::Conveyor => "/conveyor.png",
+            Sprite::Key => "/key.png",
+            Sprite::Switch => "/switch.png",
+            Sprite::Door => "/door.png",
+            Sprite::Gate => "/gate.png",
+            Sprite::Head => "/shead.png",
+            Sprite::Body => "/sbody.png",
+            Sprite::Tail => "/send.png",
+            Sprite::Blood => "/blood.png",
+            Sprite::HoleWall => "/holewall.png",
+        }
+    }
+
+    const ALL: [Sprite; 22] = [Sprite::Floor, Sprite::Wall, Sprite::Mouse,
+                               Sprite::GoldenMouse, Sprite::PoisonedMouse, Sprite::BonusMouse,
+                               Sprite::SlowTime, Sprite::Ghost, Sprite::WallBreaker, Sprite::Scissors,
+                               Sprite::Teleporter, Sprite::OneWay, Sprite::Conveyor,
+                               Sprite::Key, Sprite::Switch, Sprite::Door, Sprite::Gate,
+                               Sprite::Head, Sprite::Body, Sprite::Tail,
+                               Sprite::Blood, Sprite::HoleWall];
+}
+
+const FONT_PATH: &str = "/Terminus.ttf";
+
+/// Texts cached before the cache is dropped and rebuilt. The score changes
+/// every few seconds, so without a bound the cache would grow all game long.
+const MAX_CACHED_TEXTS: usize = 64;
+
+/// Everything loaded from the resource directory, read once at startup.
+///
+/// Images and fonts are cheap handles in ggez, so the game elements only
+/// keep positions and ask the cache for what to draw.
+pub struct Assets {
+    pub font: graphics::Font,
+    images: HashMap<Sprite, graphics::Image>,
+    sounds: HashMap<&'static str, audio::SoundData>,
+    texts: HashMap<(String, u32, u32), graphics::Text>,
+}
+
+impl Assets {
+    pub fn new(ctx: &mut Context) -> GameResult<Assets> {
+        let font = graphics::Font::new(ctx, FONT_PATH)?;
+
+        let mut images = HashMap::new();
+        for sprite in Sprite::ALL.iter() {
+            images.insert(*sprite, graphics::Image::new(ctx, sprite.path())?);
+        }
+
+        let mut sounds = HashMap::new();
+        let paths = Sound::ALL.iter().map(|s| s.path()).chain(PLAYLIST.iter().cloned());
+        for path in paths {
+            match audio::SoundData::new(ctx, path) {
+                Ok(data) => { sounds.insert(path, data); },
+                Err(e) => eprintln!("Could not load sound {}: {}", path, e),
+            }
+        }
+
+        Ok(Assets {
+            font,
+            images,
+            sounds,
+            texts: HashMap::new(),
+        })
+    }
+
+    pub fn image(&self, sprite: Sprite) -> &graphics::Image {
+        &self.images[&sprite]
+    }
+
+    pub fn sound(&self, path: &str) -> Option<&audio::SoundData> {
+        self.sounds.get(path)
+    }
+
+    /// A text in the game font, laid out once and reused on later frames.
+    pub fn text(&mut self, content: &str, scale: graphics::Scale) -> &graphics::Text {
+        let key = (content.to_string(), scale.x.to_bits(), scale.y.to_bits());
+        if self.texts.len() >= MAX_CACHED_TEXTS && !self.texts.contains_key(&key) {
+            self.texts.clear();
+        }
+
+        let font = self.font;
+        self.texts.entry(key)
+                  .or_insert_with(|| graphics::Text::new(content)
+                                        .set_font(font, scale)
+                                        .to_owned())
+    }
+}
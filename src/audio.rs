@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use ggez::audio::{self, SoundSource};
+use ggez::Context;
+
+use crate::assets::Assets;
+use crate::settings::Settings;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    Eat,
+    WallDeath,
+    SelfDeath,
+    Wrap,
+    Start,
+    SpeedUp,
+}
+
+impl Sound {
+    pub fn path(&self) -> &'static str {
+        match *self {
+            Sound::Eat => "/eat.wav",
+            Sound::WallDeath => "/death_wall.wav",
+            Sound::SelfDeath => "/death_self.wav",
+            Sound::Wrap => "/wrap.wav",
+            Sound::Start => "/start.wav",
+            Sound::SpeedUp => "/speedup.wav",
+        }
+    }
+
+    pub const ALL: [Sound; 6] = [Sound::Eat, Sound::WallDeath, Sound::SelfDeath,
+                                 Sound::Wrap, Sound::Start, Sound::SpeedUp];
+}
+
+pub const PLAYLIST: [&str; 2] = ["/music1.wav", "/music2.wav"];
+
+/// Sound effects and background music.
+///
+/// Every failure (no audio device, missing file) is logged once and the
+/// affected sound is simply left out, so the game runs the same without audio.
+pub struct Audio {
+    sounds: HashMap<Sound, audio::Source>,
+    playlist: Vec<audio::Source>,
+    track: usize,
+    music_started: bool,
+}
+
+impl Audio {
+    pub fn new(ctx: &mut Context, assets: &Assets, enabled: bool) -> Audio {
+        let mut sounds = HashMap::new();
+        let mut playlist = Vec::new();
+
+        if enabled {
+            for sound in Sound::ALL.iter() {
+                if let Some(data) = assets.sound(sound.path()) {
+                    match audio::Source::from_data(ctx, data.clone()) {
+                        Ok(source) => { sounds.insert(*sound, source); },
+                        Err(e) => eprintln!("Could not create sound {}: {}", sound.path(), e),
+                    }
+                }
+            }
+            for path in PLAYLIST.iter() {
+                if let Some(data) = assets.sound(path) {
+                    match audio::Source::from_data(ctx, data.clone()) {
+                        Ok(source) => playlist.push(source),
+                        Err(e) => eprintln!("Could not create music {}: {}", path, e),
+                    }
+                }
+            }
+        }
+
+        Audio {
+            sounds,
+            playlist,
+            track: 0,
+            music_started: false,
+        }
+    }
+
+    pub fn play(&mut self, sound: Sound, settings: &Settings) {
+        if settings.muted {
+            return;
+        }
+        if let Some(source) = self.sounds.get_mut(&sound) {
+            source.set_volume(settings.sfx_gain());
+            if let Err(e) = source.play_detached() {
+                eprintln!("Could not play sound {}: {}", sound.path(), e);
+            }
+        }
+    }
+
+    /// Keeps the playlist going: starts the current track if nothing plays
+    /// and moves on to the next one when it ends, wrapping around at the end.
+    pub fn update_music(&mut self, settings: &Settings) {
+        if self.playlist.is_empty() {
+            return;
+        }
+
+        if self.music_started && !self.playlist[self.track].playing() {
+            self.track = (self.track + 1) % self.playlist.len();
+            self.music_started = false;
+        }
+
+        let source = &mut self.playlist[self.track];
+        source.set_volume(settings.music_gain());
+        if !self.music_started {
+            match source.play() {
+                Ok(()) => self.music_started = true,
+                Err(e) => {
+                    eprintln!("Could not play music: {}", e);
+                    self.playlist.clear();
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::process;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use rand_pcg::Pcg32;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bots::{Bot, View};
+use crate::elements::{Direction, FoodKind, GridPosition, Snake};
+use crate::external::parse_move;
+use crate::grid::CellKind;
+use crate::topology::Topology;
+
+/// A cell as Battlesnake has it: `y` counts up from the bottom row.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Coord {
+    pub x: i16,
+    pub y: i16,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RulesetSettings {
+    pub food_spawn_chance: u32,
+    pub minimum_food: u32,
+    pub hazard_damage_per_turn: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Ruleset {
+    pub name: String,
+    pub version: String,
+    pub settings: RulesetSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Game {
+    pub id: String,
+    pub ruleset: Ruleset,
+    pub map: String,
+    pub timeout: u64,
+    pub source: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Customizations {
+    pub color: String,
+    pub head: String,
+    pub tail: String,
+}
+
+/// A snake on the board. There is no hunger here, so `health` stays full.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Battlesnake {
+    pub id: String,
+    pub name: String,
+    pub health: u32,
+    pub body: Vec<Coord>,
+    pub latency: String,
+    pub head: Coord,
+    pub length: usize,
+    pub shout: String,
+    pub squad: String,
+    pub customizations: Customizations,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BoardState {
+    pub height: i16,
+    pub width: i16,
+    pub food: Vec<Coord>,
+    pub hazards: Vec<Coord>,
+    pub snakes: Vec<Battlesnake>,
+}
+
+/// The body of every `/start`, `/move` and `/end` request.
+///
+/// The board is the playing field under the score bar, turned upside down
+/// so the bottom row is `y = 0`. Walls and poisoned mice are hazards that
+/// take all of a snake's health, the way Battlesnake's maze maps build
+/// walls, and every other mouse is food. The ruleset says how the edges are
+/// joined and `map` names the topology.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRequest {
+    pub game: Game,
+    pub turn: u64,
+    pub board: BoardState,
+    pub you: Battlesnake,
+}
+
+impl Coord {
+    /// Where `pos` is on a board `height` rows high, score bar and all.
+    pub fn new(pos: GridPosition, height: i16) -> Self {
+        let (x, y) = pos.coords();
+        Coord { x, y: height - 1 - y }
+    }
+}
+
+/// The rules a board with `topology` is played by. Battlesnake has none for
+/// edges that wrap mirrored, so those get a name of their own instead of
+/// passing for `wrapped`.
+fn ruleset(topology: Topology) -> &'static str {
+    match topology {
+        Topology::Bounded => "standard",
+        Topology::Portals | Topology::Torus => "wrapped",
+        Topology::Klein | Topology::Projective => "wrapped_mirrored",
+    }
+}
+
+fn battlesnake(snake: &Snake, height: i16) -> Battlesnake {
+    let id = match snake.rival {
+        Some(side) => format!("snake-{}", side),
+        None => "player".to_string(),
+    };
+    let body: Vec<Coord> = snake.positions().map(|pos| Coord::new(pos, height)).collect();
+    Battlesnake {
+        name: id.clone(),
+        id,
+        health: 100,
+        head: body[0],
+        length: body.len(),
+        body,
+        latency: "0".to_string(),
+        shout: String::new(),
+        squad: String::new(),
+        customizations: Customizations {
+            color: "#888888".to_string(),
+            head: "default".to_string(),
+            tail: "default".to_string(),
+        },
+    }
+}
+
+impl GameRequest {
+    pub fn new(view: &View, id: &str, timeout: Duration) -> Self {
+        let (width, height) = view.grid.size();
+        let mut hazards = Vec::new();
+        for y in 1..height {
+            for x in 0..width {
+                let pos = GridPosition::new(x, y);
+                if view.grid.get(pos) == CellKind::Wall {
+                    hazards.push(Coord::new(pos, height));
+                }
+            }
+        }
+        let (poisoned, food): (Vec<_>, Vec<_>) = view.foods.iter().partition(|food| food.kind == FoodKind::Poisoned);
+        hazards.extend(poisoned.iter().map(|food| Coord::new(food.pos, height)));
+
+        let you = battlesnake(view.snake, height);
+        let mut snakes = vec![you.clone()];
+        snakes.extend(view.others.iter().map(|snake| battlesnake(snake, height)));
+        let topology = view.grid.topology();
+        GameRequest {
+            game: Game {
+                id: id.to_string(),
+                ruleset: Ruleset {
+                    name: ruleset(topology).to_string(),
+                    version: "v1.0.0".to_string(),
+                    settings: RulesetSettings { food_spawn_chance: 0, minimum_food: 0, hazard_damage_per_turn: 100 },
+                },
+                map: topology.to_string(),
+                timeout: timeout.as_millis() as u64,
+                source: "custom".to_string(),
+            },
+            turn: view.tick,
+            board: BoardState {
+                height: height - 1,
+                width,
+                food: food.iter().map(|food| Coord::new(food.pos, height)).collect(),
+                hazards,
+                snakes,
+            },
+            you,
+        }
+    }
+}
+
+/// A bot's address, `http://host:port/path`. There is no TLS, so it is
+/// meant for servers on this machine or the local network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Url {
+    pub host: String,
+    pub port: u16,
+    /// Everything after the port, without a slash at the end.
+    pub path: String,
+}
+
+impl fmt::Display for Url {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "http://{}:{}{}", self.host, self.port, self.path)
+    }
+}
+
+impl FromStr for Url {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rest = s.strip_prefix("http://").ok_or(format!("not an http:// URL: {}", s))?;
+        let (authority, path) = match rest.find('/') {
+            Some(i) => rest.split_at(i),
+            None => (rest, ""),
+        };
+        let (host, port) = match authority.rfind(':') {
+            Some(i) => {
+                let port = authority[i + 1..].parse().map_err(|_| format!("bad port in {}", s))?;
+                (&authority[..i], port)
+            }
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("no host in {}", s));
+        }
+        Ok(Url { host: host.to_string(), port, path: path.trim_end_matches('/').to_string() })
+    }
+}
+
+/// Posts `body` as JSON to `path` under `url` and returns the body of the
+/// answer, giving up once `timeout` has passed. It speaks HTTP/1.0 so the
+/// answer comes in one piece and ends when the server closes the
+/// connection.
+pub fn post(url: &Url, path: &str, body: &str, timeout: Duration) -> io::Result<String> {
+    let deadline = Instant::now() + timeout;
+    let left = || {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left == Duration::from_secs(0) {
+            Err(io::Error::new(io::ErrorKind::TimedOut, "no answer in time"))
+        } else {
+            Ok(left)
+        }
+    };
+    let addr = (url.host.as_str(), url.port).to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address for the host"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, left()?)?;
+    stream.set_write_timeout(Some(left()?))?;
+    write!(stream,
+           "POST {}{} HTTP/1.0\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+           url.path, path, url.host, url.port, body.len(), body)?;
+    stream.flush()?;
+
+    let mut answer = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        stream.set_read_timeout(Some(left()?))?;
+        match stream.read(&mut buffer)? {
+            0 => break,
+            n => answer.extend_from_slice(&buffer[..n]),
+        }
+    }
+    let answer = String::from_utf8_lossy(&answer);
+    let (head, body) = answer.split_at(answer.find("\r\n\r\n").map_or(answer.len(), |i| i + 4));
+    let status = head.split_whitespace().nth(1).unwrap_or("");
+    if !status.starts_with('2') {
+        return Err(io::Error::other(format!("the server answered {}", status)));
+    }
+    Ok(body.to_string())
+}
+
+/// A Battlesnake bot behind an HTTP server. The game sends it `/start`
+/// before the first move, `/move` every tick and `/end` when the game is
+/// over, each with a `GameRequest`, and plays the move in the `{"move":
+/// "up"}` it answers. No answer in time, or one that is not a move, keeps
+/// the snake going straight.
+pub struct BattlesnakeBot {
+    url: Url,
+    timeout: Duration,
+    game: String,
+    games: u64,
+    /// Whether this game has already said it could not reach the server.
+    warned: bool,
+}
+
+impl BattlesnakeBot {
+    pub fn new(url: Url, timeout: Duration) -> Self {
+        BattlesnakeBot { url, timeout, game: String::new(), games: 0, warned: false }
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    fn send(&mut self, path: &str, view: &View) -> Option<String> {
+        let request = GameRequest::new(view, &self.game, self.timeout);
+        let body = serde_json::to_string(&request).ok()?;
+        match post(&self.url, path, &body, self.timeout) {
+            Ok(answer) => Some(answer),
+            Err(e) => {
+                if !self.warned {
+                    eprintln!("{}{}: {}", self.url, path, e);
+                    self.warned = true;
+                }
+                None
+            }
+        }
+    }
+}
+
+impl Bot for BattlesnakeBot {
+    fn choose(&mut self, view: &View, _rng: &mut Pcg32) -> Direction {
+        if self.game.is_empty() {
+            self.start(view);
+        }
+        self.send("/move", view)
+            .and_then(|answer| parse_move(&answer))
+            .unwrap_or(view.snake.dir)
+    }
+
+    fn start(&mut self, view: &View) {
+        self.games += 1;
+        self.game = format!("snake-rust-{}-{}", process::id(), self.games);
+        self.warned = false;
+        self.send("/start", view);
+    }
+
+    fn end(&mut self, view: &View) {
+        self.send("/end", view);
+        self.game.clear();
+    }
+}
@@ -0,0 +1,180 @@
+//! Plays bots against each other in headless games and rates them, so bots
+//! can be compared on the same games every time.
+//!
+//! ```text
+//! snake_arena greedy lookahead random [--format round-robin|swiss] [--rounds N]
+//!             [--seed N] [--size WxH] [--speed N] [--seconds N] [--arena KIND]
+//!             [--edges TOPOLOGY] [--replays DIR]
+//!             [--bot-cmd "python mybot.py"]... [--bot-url http://localhost:8000]...
+//!             [--bot-timeout MS]
+//! ```
+//!
+//! A bot given by `--bot-cmd` runs as a program of its own, started afresh
+//! for every game. One given by `--bot-url` is a Battlesnake server that is
+//! already running.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use snake_rust::battlesnake::{BattlesnakeBot, Url};
+use snake_rust::bots::{Bot, BotTier};
+use snake_rust::consts::{BOT_MOVE_TIMEOUT_MS, DUEL_MIN_SIZE};
+use snake_rust::duel::{DuelConfig, Record};
+use snake_rust::external::ExternalBot;
+use snake_rust::tournament::{Format, Tournament};
+
+const USAGE: &str = "usage: snake_arena BOT BOT... [--format round-robin|swiss] [--rounds N] [--seed N] \
+                     [--size WxH] [--speed N] [--seconds N] [--arena KIND] [--edges TOPOLOGY] [--replays DIR] \
+                     [--bot-cmd COMMAND]... [--bot-url URL]... [--bot-timeout MS]";
+
+#[derive(Clone, PartialEq)]
+enum Entrant {
+    Tier(BotTier),
+    Command(String),
+    Url(Url),
+}
+
+impl Entrant {
+    fn name(&self) -> String {
+        match self {
+            Entrant::Tier(tier) => tier.to_string(),
+            Entrant::Command(command) => command.clone(),
+            Entrant::Url(url) => url.to_string(),
+        }
+    }
+
+    fn bot(&self, timeout: Duration) -> Box<dyn Bot> {
+        match self {
+            Entrant::Tier(tier) => tier.bot(),
+            Entrant::Command(command) => match ExternalBot::spawn(command, timeout) {
+                Ok(bot) => Box::new(bot),
+                Err(e) => {
+                    eprintln!("{}: {}", command, e);
+                    process::exit(1);
+                }
+            },
+            Entrant::Url(url) => Box::new(BattlesnakeBot::new(url.clone(), timeout)),
+        }
+    }
+}
+
+struct Options {
+    bots: Vec<Entrant>,
+    timeout: Duration,
+    rounds: Option<u32>,
+    tournament: Tournament,
+    replays: Option<String>,
+}
+
+fn value<T: std::str::FromStr>(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<T, String> {
+    let value = args.next().ok_or(format!("missing value for {}", flag))?;
+    value.parse().map_err(|_| format!("bad value for {}: {}", flag, value))
+}
+
+fn parse_size(text: &str) -> Result<(i16, i16), String> {
+    let mut parts = text.splitn(2, 'x').map(|n| n.parse::<i16>());
+    match (parts.next(), parts.next()) {
+        (Some(Ok(width)), Some(Ok(height))) if width >= DUEL_MIN_SIZE.0 && height >= DUEL_MIN_SIZE.1 => Ok((width, height)),
+        _ => Err(format!("bad value for --size: {} (at least {}x{})", text, DUEL_MIN_SIZE.0, DUEL_MIN_SIZE.1)),
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut options = Options {
+        bots: Vec::new(),
+        timeout: Duration::from_millis(BOT_MOVE_TIMEOUT_MS),
+        rounds: None,
+        tournament: Tournament { format: Format::RoundRobin, rounds: 1, seed: 0, config: DuelConfig::default() },
+        replays: None,
+    };
+    let config = &mut options.tournament.config;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => options.tournament.format = args.next().ok_or("missing value for --format")?.parse()?,
+            "--rounds" => options.rounds = Some(value(&mut args, "--rounds")?),
+            "--seed" => options.tournament.seed = value(&mut args, "--seed")?,
+            "--size" => config.size = parse_size(&args.next().ok_or("missing value for --size")?)?,
+            "--speed" => config.speed = value::<u32>(&mut args, "--speed")?.max(1),
+            "--seconds" => config.seconds = value::<u32>(&mut args, "--seconds")?.max(1),
+            "--arena" => config.arena = args.next().ok_or("missing value for --arena")?.parse()?,
+            "--edges" => config.topology = args.next().ok_or("missing value for --edges")?.parse()?,
+            "--replays" => options.replays = Some(args.next().ok_or("missing value for --replays")?),
+            "--bot-cmd" => options.bots.push(Entrant::Command(args.next().ok_or("missing value for --bot-cmd")?)),
+            "--bot-url" => options.bots.push(Entrant::Url(args.next().ok_or("missing value for --bot-url")?.parse()?)),
+            "--bot-timeout" => options.timeout = Duration::from_millis(value(&mut args, "--bot-timeout")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ => options.bots.push(Entrant::Tier(arg.parse()?)),
+        }
+    }
+    if options.bots.len() < 2 {
+        return Err(USAGE.to_string());
+    }
+    // A Swiss tournament needs about as many rounds as it takes to halve
+    // the field down to one.
+    options.tournament.rounds = options.rounds.unwrap_or(match options.tournament.format {
+        Format::RoundRobin => 1,
+        Format::Swiss => (options.bots.len() as f64).log2().ceil() as u32,
+    });
+    Ok(options)
+}
+
+/// Names to tell the bots apart by, numbered where the same one is entered
+/// more than once.
+fn names(bots: &[Entrant]) -> Vec<String> {
+    bots.iter()
+        .enumerate()
+        .map(|(i, bot)| {
+            let name = bot.name();
+            match bots.iter().filter(|other| *other == bot).count() {
+                1 => name,
+                _ => format!("{}#{}", name, bots[..=i].iter().filter(|other| *other == bot).count()),
+            }
+        })
+        .collect()
+}
+
+/// `name` with everything but letters, digits and `#` turned into dashes,
+/// for bots named after the command that runs them.
+fn file_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == '#' { c } else { '-' }).collect()
+}
+
+fn run(options: Options) -> Result<(), String> {
+    if let Some(dir) = &options.replays {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir, e))?;
+    }
+    let names = names(&options.bots);
+    let mut games = 0;
+    let mut error = None;
+    let on_game = |record: &Record| {
+        games += 1;
+        let result = match record.winner {
+            Some(side) => format!("{} won", record.names[side]),
+            None => "draw".to_string(),
+        };
+        println!("game {}: {} vs {} on seed {}: {} after {} ticks",
+                 games, record.names[0], record.names[1], record.seed, result, record.ticks);
+        if let Some(dir) = &options.replays {
+            let path = Path::new(dir).join(format!("{:03}-{}-vs-{}.txt", games, file_name(&record.names[0]), file_name(&record.names[1])));
+            if let Err(e) = fs::write(&path, record.to_string()) {
+                error.get_or_insert(format!("{}: {}", path.display(), e));
+            }
+        }
+    };
+    let standings = options.tournament.run(&names, |i| options.bots[i].bot(options.timeout), on_game);
+    println!();
+    print!("{}", standings);
+    error.map_or(Ok(()), Err)
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
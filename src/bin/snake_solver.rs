@@ -0,0 +1,95 @@
+//! Finds the fewest moves a puzzle can be solved in, so puzzles can be
+//! checked before they ship and given their par.
+//!
+//! ```text
+//! snake_solver resources/puzzles/01.txt [--max-states N] [--replay PATH] [--show]
+//! ```
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+use snake_rust::elements::Direction;
+use snake_rust::puzzle::Puzzle;
+use snake_rust::solver::{self, MAX_STATES};
+
+const USAGE: &str = "usage: snake_solver PUZZLE [--max-states N] [--replay PATH] [--show]";
+
+struct Options {
+    path: String,
+    max_states: usize,
+    replay: Option<String>,
+    show: bool,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = env::args().skip(1);
+    let mut options = Options { path: String::new(), max_states: MAX_STATES, replay: None, show: false };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--max-states" => {
+                let value = args.next().ok_or("missing value for --max-states")?;
+                options.max_states = value.parse().map_err(|_| format!("bad value for --max-states: {}", value))?;
+            }
+            "--replay" => options.replay = Some(args.next().ok_or("missing value for --replay")?),
+            "--show" => options.show = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option: {}", arg)),
+            _ if options.path.is_empty() => options.path = arg,
+            _ => return Err(format!("more than one puzzle: {}", arg)),
+        }
+    }
+    if options.path.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    Ok(options)
+}
+
+fn letter(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right | Direction::None => 'R',
+    }
+}
+
+/// The puzzle number of a file named like the shipped ones, `07.txt`.
+fn number(path: &str) -> Option<usize> {
+    let stem = Path::new(path).file_stem()?.to_str()?;
+    stem.parse::<usize>().ok().filter(|n| *n > 0).map(|n| n - 1)
+}
+
+fn run(options: Options) -> Result<(), String> {
+    let text = fs::read_to_string(&options.path).map_err(|e| format!("{}: {}", options.path, e))?;
+    let puzzle: Puzzle = text.parse().map_err(|e| format!("{}: {}", options.path, e))?;
+
+    let moves = solver::solve(&puzzle.board, puzzle.moves, options.max_states).map_err(|e| e.to_string())?;
+    println!("{}: solved in {} moves (limit {}, par {})", puzzle.name, moves.len(), puzzle.moves, puzzle.par);
+    println!("{}", moves.iter().map(|dir| letter(*dir)).collect::<String>());
+    if moves.len() as u32 != puzzle.par {
+        println!("note: par should be {}", moves.len());
+    }
+
+    if options.show {
+        for (i, board) in solver::play(&puzzle.board, &moves).iter().enumerate() {
+            println!("move {}: {}", i + 1, letter(moves[i]));
+            println!("{}", board);
+        }
+    }
+
+    let replay = solver::replay(&moves, number(&options.path));
+    match options.replay {
+        Some(path) => fs::write(&path, replay.to_string()).map_err(|e| format!("{}: {}", path, e))?,
+        None => print!("{}", replay),
+    }
+    Ok(())
+}
+
+fn main() {
+    let result = parse_args().and_then(run);
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use ggez::{filesystem, Context, GameResult};
+
+use crate::elements::{Direction, Door, DoorKind, Food, FoodKind, GridPosition, Segment, Snake, Wall};
+use crate::grid::Occupancy;
+use crate::tiles::{Tile, Tiles};
+
+pub const BOARD_PATH: &str = "/board.txt";
+
+/// Everything on the board in a form that can be pasted into a test or a bug
+/// report. One character per cell, one line per row, the score bar included:
+///
+/// ```text
+/// #  wall            .  floor           :  hole in the border
+/// *  mouse           $  golden mouse    !  poisoned mouse    +  bonus mouse
+/// ^ v < >  head, the way it is heading
+/// u d l r  body      U D L R  tail
+/// 0 - 9    teleporter, linked to the other one with the same digit
+/// n e s w  one-way arrow         N E S W  conveyor
+/// k  key             K  locked door     o  switch
+/// G  gate, shut until a switch is held  g  gate, open until a switch is held
+/// ```
+///
+/// Body and tail letters point to the next piece towards the head, so the
+/// snake can be followed from the tail even where it touches itself. Food
+/// hidden under the snake or a wall is given on an extra line such as
+/// `mouse 4 7`, a hidden tile on one such as `tile 3 4 7` and a hidden door
+/// on one such as `door g 4 7`. Keys, switches and doors are in group 0
+/// unless a line such as `group 2 4 7` puts the piece on that cell in
+/// another: a key opens the doors of its group and a switch works its gates.
+#[derive(Clone, Debug)]
+pub struct Board {
+    pub size: (i16, i16),
+    pub walls: Wall,
+    pub snake: Snake,
+    pub foods: Vec<Food>,
+    pub tiles: Tiles,
+}
+
+impl Board {
+    pub fn new(size: (i16, i16), walls: Wall, snake: Snake, foods: Vec<Food>) -> Self {
+        Board { size, walls, snake, foods, tiles: Tiles::default() }
+    }
+
+    pub fn grid(&self) -> Occupancy {
+        Occupancy::build(self.size, &self.walls, &self.snake, &self.foods).with_tiles(self.tiles.clone())
+    }
+
+    /// Prints the board and writes it to the user directory.
+    pub fn dump(&self, ctx: &mut Context) -> GameResult {
+        println!("{}", self);
+        let mut file = filesystem::create(ctx, BOARD_PATH)?;
+        write!(file, "{}", self)?;
+        Ok(())
+    }
+
+    /// Open cells on the border of the play area, below the score bar.
+    fn is_hole(&self, pos: GridPosition) -> bool {
+        let (x, y) = pos.coords();
+        x == 0 || x == self.size.0 - 1 || y == 1 || y == self.size.1 - 1
+    }
+}
+
+fn head_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => '^',
+        Direction::Down => 'v',
+        Direction::Left => '<',
+        Direction::Right | Direction::None => '>',
+    }
+}
+
+fn body_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'u',
+        Direction::Down => 'd',
+        Direction::Left => 'l',
+        Direction::Right | Direction::None => 'r',
+    }
+}
+
+fn food_char(kind: FoodKind) -> char {
+    match kind {
+        FoodKind::Mouse => '*',
+        FoodKind::Golden => '$',
+        FoodKind::Poisoned => '!',
+        FoodKind::Bonus => '+',
+    }
+}
+
+fn door_char(kind: DoorKind) -> char {
+    match kind {
+        DoorKind::Locked => 'K',
+        DoorKind::Gate { rests_open: false } => 'G',
+        DoorKind::Gate { rests_open: true } => 'g',
+    }
+}
+
+fn char_door(c: char) -> Option<DoorKind> {
+    match c {
+        'K' => Some(DoorKind::Locked),
+        'G' => Some(DoorKind::Gate { rests_open: false }),
+        'g' => Some(DoorKind::Gate { rests_open: true }),
+        _ => None,
+    }
+}
+
+fn char_food(c: char) -> Option<FoodKind> {
+    FoodKind::ALL.iter().cloned().find(|kind| food_char(*kind) == c)
+}
+
+fn char_dir(c: char) -> Option<Direction> {
+    match c.to_ascii_lowercase() {
+        '^' | 'u' => Some(Direction::Up),
+        'v' | 'd' => Some(Direction::Down),
+        '<' | 'l' => Some(Direction::Left),
+        '>' | 'r' => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut cells: HashMap<GridPosition, char> = HashMap::new();
+        for pos in self.walls.positions() {
+            cells.insert(pos, '#');
+        }
+        for door in self.walls.doors() {
+            cells.insert(door.pos, door_char(door.kind));
+        }
+
+        let segments: Vec<&Segment> = self.snake.segments().collect();
+        let last = segments.len() - 1;
+        for (i, seg) in segments.iter().enumerate() {
+            let c = match i {
+                0 => head_char(self.snake.dir),
+                _ if i == last => body_char(segments[i - 1].dir).to_ascii_uppercase(),
+                _ => body_char(segments[i - 1].dir),
+            };
+            cells.insert(seg.pos, c);
+        }
+
+        let hidden_doors: Vec<&Door> = self.walls.doors().filter(|door| self.snake.positions().any(|p| p == door.pos)).collect();
+        let hidden: Vec<&Food> = self.foods.iter().filter(|food| cells.contains_key(&food.pos)).collect();
+        for food in self.foods.iter() {
+            cells.entry(food.pos).or_insert_with(|| food_char(food.kind));
+        }
+        let hidden_tiles: Vec<&(GridPosition, Tile)> = self.tiles.iter().filter(|(pos, _)| cells.contains_key(pos)).collect();
+        for (pos, tile) in self.tiles.iter() {
+            cells.entry(*pos).or_insert_with(|| tile.to_char());
+        }
+
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                let pos = GridPosition::new(x, y);
+                let c = match cells.get(&pos) {
+                    Some(&c) => c,
+                    None if y > 0 && self.is_hole(pos) => ':',
+                    None => '.',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        for food in hidden {
+            let (x, y) = food.pos.coords();
+            writeln!(f, "{} {} {}", food.kind, x, y)?;
+        }
+        for (pos, tile) in hidden_tiles {
+            let (x, y) = pos.coords();
+            writeln!(f, "tile {} {} {}", tile.to_char(), x, y)?;
+        }
+        for door in hidden_doors {
+            let (x, y) = door.pos.coords();
+            writeln!(f, "door {} {} {}", door_char(door.kind), x, y)?;
+        }
+        let tile_groups = self.tiles.iter().filter_map(|(pos, tile)| tile.group().map(|group| (*pos, group)));
+        let door_groups = self.walls.doors().map(|door| (door.pos, door.group));
+        for (pos, group) in tile_groups.chain(door_groups).filter(|(_, group)| *group != 0) {
+            let (x, y) = pos.coords();
+            writeln!(f, "group {} {} {}", group, x, y)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Board {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(|l| l.trim_end()).filter(|l| !l.is_empty()).collect();
+        let (extra, rows): (Vec<&str>, Vec<&str>) = lines.into_iter().partition(|l| l.contains(' '));
+        if rows.is_empty() {
+            return Err("empty board".to_string());
+        }
+        let width = rows[0].chars().count();
+        if rows.iter().any(|r| r.chars().count() != width) {
+            return Err("board rows differ in length".to_string());
+        }
+        let size = (width as i16, rows.len() as i16);
+
+        let mut walls = Vec::new();
+        let mut foods = Vec::new();
+        let mut tiles = Vec::new();
+        let mut doors = Vec::new();
+        let mut groups = HashMap::new();
+        let mut head = None;
+        let mut tail = None;
+        let mut body = HashMap::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let pos = GridPosition::new(x as i16, y as i16);
+                match c {
+                    '#' => walls.push(pos),
+                    '.' | ':' => {},
+                    '*' | '$' | '!' | '+' => foods.push(Food::of_kind(pos, char_food(c).unwrap())),
+                    '^' | 'v' | '<' | '>' if head.is_none() => head = Some((pos, char_dir(c).unwrap())),
+                    '^' | 'v' | '<' | '>' => return Err("more than one head".to_string()),
+                    'U' | 'D' | 'L' | 'R' if tail.is_none() => tail = Some((pos, char_dir(c).unwrap())),
+                    'U' | 'D' | 'L' | 'R' => return Err("more than one tail".to_string()),
+                    'u' | 'd' | 'l' | 'r' => { body.insert(pos, char_dir(c).unwrap()); },
+                    'K' | 'G' | 'g' => doors.push(Door::new(pos, 0, char_door(c).unwrap())),
+                    _ => match Tile::from_char(c) {
+                        Some(tile) => tiles.push((pos, tile)),
+                        None => return Err(format!("unknown cell '{}' at {} {}", c, x, y)),
+                    },
+                }
+            }
+        }
+
+        for line in extra {
+            let mut parts = line.split_whitespace();
+            let first = parts.next().unwrap_or("");
+            if first == "tile" || first == "door" || first == "group" {
+                let what = parts.next().ok_or(format!("bad {} line: {}", first, line))?;
+                let coords: Vec<i16> = parts.map(|n| n.parse::<i16>().map_err(|e| e.to_string()))
+                                            .collect::<Result<_, _>>()?;
+                let pos = match coords.as_slice() {
+                    &[x, y] => GridPosition::new(x, y),
+                    _ => return Err(format!("bad {} line: {}", first, line)),
+                };
+                let c = what.chars().next().unwrap_or(' ');
+                match first {
+                    "tile" => tiles.push((pos, Tile::from_char(c).ok_or(format!("bad tile line: {}", line))?)),
+                    "door" => doors.push(Door::new(pos, 0, char_door(c).ok_or(format!("bad door line: {}", line))?)),
+                    _ => { groups.insert(pos, what.parse::<u8>().map_err(|_| format!("bad group line: {}", line))?); },
+                }
+                continue;
+            }
+            let kind = first.parse::<FoodKind>()?;
+            let coords: Vec<i16> = parts.map(|n| n.parse::<i16>().map_err(|e| e.to_string()))
+                                        .collect::<Result<_, _>>()?;
+            match coords.as_slice() {
+                &[x, y] => foods.push(Food::of_kind(GridPosition::new(x, y), kind)),
+                _ => return Err(format!("bad food line: {}", line)),
+            }
+        }
+
+        let group = |pos: &GridPosition| groups.get(pos).cloned().unwrap_or(0);
+        let tiles = Tiles::new(tiles.into_iter().map(|(pos, tile)| (pos, tile.in_group(group(&pos)))).collect())?;
+        let mut walls = Wall::from_positions(walls);
+        for door in doors {
+            walls.add_door(Door::new(door.pos, group(&door.pos), door.kind));
+        }
+        let (head_pos, dir) = head.ok_or("missing head")?;
+        let (tail_pos, tail_dir) = tail.ok_or("missing tail")?;
+
+        // Walk from the tail to the head. Every piece entered its cell the way
+        // the piece behind it points, through a teleporter if there is one.
+        let mut segments = vec![Segment::new(tail_pos, tail_dir)];
+        let mut pos = tail_pos;
+        let mut towards_head = tail_dir;
+        loop {
+            let next = GridPosition::new_from_move_in(pos, towards_head, size);
+            pos = match tiles.get(next) {
+                Some(Tile::Teleporter(_)) => tiles.partner(next).unwrap_or(next),
+                _ => next,
+            };
+            if pos == head_pos {
+                segments.push(Segment::new(pos, towards_head));
+                break;
+            }
+            let next = body.remove(&pos).ok_or(format!("snake breaks off at {:?}", pos.coords()))?;
+            segments.push(Segment::new(pos, towards_head));
+            towards_head = next;
+        }
+        if !body.is_empty() {
+            return Err("body pieces not joined to the snake".to_string());
+        }
+        segments.reverse();
+
+        Ok(Board {
+            size,
+            walls,
+            snake: Snake::from_segments(&segments, dir),
+            foods,
+            tiles,
+        })
+    }
+}
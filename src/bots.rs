@@ -0,0 +1,300 @@
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::str::FromStr;
+
+use rand::Rng;
+use rand_pcg::Pcg32;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::consts::*;
+use crate::elements::{Direction, Food, GridPosition, Snake};
+use crate::grid::{CellKind, Occupancy};
+use crate::tiles::Tile;
+
+/// What a bot is shown before it picks its move.
+pub struct View<'a> {
+    /// The snake the bot steers.
+    pub snake: &'a Snake,
+    /// Every other snake on the board, the player's among them.
+    pub others: Vec<&'a Snake>,
+    pub grid: &'a Occupancy,
+    pub foods: &'a [Food],
+    pub tick: u64,
+}
+
+/// Steers a snake that is not the player's.
+pub trait Bot {
+    /// The way to go next. The snake keeps going straight if the move is
+    /// not one it can make.
+    fn choose(&mut self, view: &View, rng: &mut Pcg32) -> Direction;
+
+    /// Told about the board before the first move of a game.
+    fn start(&mut self, _view: &View) {}
+
+    /// Told about the board once the game is over.
+    fn end(&mut self, _view: &View) {}
+}
+
+/// How well a rival plays, from moving at random to thinking a move ahead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BotTier {
+    /// Any move that does not kill it straight away.
+    RandomSafe,
+    /// The shortest way to the nearest mouse.
+    #[default]
+    Greedy,
+    /// Greedy, but keeps out of dead ends and away from other heads.
+    LookAhead,
+}
+
+impl BotTier {
+    pub const ALL: [BotTier; 3] = [BotTier::RandomSafe, BotTier::Greedy, BotTier::LookAhead];
+
+    /// The tier after this one on the start screen.
+    pub fn next(&self) -> Self {
+        let i = BotTier::ALL.iter().position(|tier| tier == self).unwrap();
+        BotTier::ALL[(i + 1) % BotTier::ALL.len()]
+    }
+
+    pub fn bot(&self) -> Box<dyn Bot> {
+        match *self {
+            BotTier::RandomSafe => Box::new(RandomSafe),
+            BotTier::Greedy => Box::new(Greedy),
+            BotTier::LookAhead => Box::new(LookAhead),
+        }
+    }
+}
+
+impl fmt::Display for BotTier {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BotTier::RandomSafe => write!(f, "random"),
+            BotTier::Greedy => write!(f, "greedy"),
+            BotTier::LookAhead => write!(f, "lookahead"),
+        }
+    }
+}
+
+impl FromStr for BotTier {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(BotTier::RandomSafe),
+            "greedy" => Ok(BotTier::Greedy),
+            "lookahead" => Ok(BotTier::LookAhead),
+            _ => Err(format!("unknown bot tier: {}", s)),
+        }
+    }
+}
+
+/// A computer-controlled snake sharing the board with the player. Its bot
+/// is not saved: a rival that is loaded or cloned gets a fresh one of its
+/// tier.
+#[derive(Serialize)]
+pub struct Rival {
+    pub snake: Snake,
+    pub tier: BotTier,
+    #[serde(skip)]
+    pub bot: Box<dyn Bot>,
+}
+
+impl Rival {
+    pub fn new(snake: Snake, tier: BotTier) -> Self {
+        Rival { snake, tier, bot: tier.bot() }
+    }
+}
+
+impl Clone for Rival {
+    fn clone(&self) -> Self {
+        Rival::new(self.snake.clone(), self.tier)
+    }
+}
+
+impl fmt::Debug for Rival {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Rival").field("snake", &self.snake).field("tier", &self.tier).finish()
+    }
+}
+
+impl<'de> Deserialize<'de> for Rival {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Saved {
+            snake: Snake,
+            tier: BotTier,
+        }
+        let saved = Saved::deserialize(deserializer)?;
+        Ok(Rival::new(saved.snake, saved.tier))
+    }
+}
+
+/// Cells a snake can move onto without dying.
+fn open(cell: CellKind) -> bool {
+    matches!(cell, CellKind::Empty | CellKind::Food(_) | CellKind::PowerUp(_))
+}
+
+/// The moves the snake can make this tick that do not run it into a wall
+/// or a snake. On a conveyor the belt decides, so that is the only one.
+pub fn safe_moves(snake: &Snake, grid: &Occupancy) -> Vec<Direction> {
+    let head = snake.head_pos();
+    let moves = match grid.tile(head) {
+        Some(Tile::Conveyor(way)) => vec![way],
+        _ => Direction::MOVES.iter().cloned().filter(|dir| *dir != snake.dir.inverse()).collect(),
+    };
+    moves.into_iter()
+         .filter(|dir| grid.step(head, *dir).map_or(false, |pos| open(grid.get(pos))))
+         .collect()
+}
+
+/// How many moves it takes to reach the nearest food from `from`, or `None`
+/// if there is none it can get to.
+fn food_distance(grid: &Occupancy, from: GridPosition) -> Option<usize> {
+    if let CellKind::Food(_) = grid.get(from) {
+        return Some(0);
+    }
+    let mut seen: HashSet<GridPosition> = HashSet::new();
+    seen.insert(from);
+    let mut todo = VecDeque::new();
+    todo.push_back((from, 0));
+    while let Some((pos, distance)) = todo.pop_front() {
+        for next in Direction::MOVES.iter().filter_map(|dir| grid.step(pos, *dir)) {
+            let cell = grid.get(next);
+            if !open(cell) || !seen.insert(next) {
+                continue;
+            }
+            if let CellKind::Food(_) = cell {
+                return Some(distance + 1);
+            }
+            todo.push_back((next, distance + 1));
+        }
+    }
+    None
+}
+
+/// Free cells that can be reached from `from`, counting no further than
+/// `limit`.
+fn room(grid: &Occupancy, from: GridPosition, limit: usize) -> usize {
+    let mut seen: HashSet<GridPosition> = HashSet::new();
+    seen.insert(from);
+    let mut todo = vec![from];
+    while let Some(pos) = todo.pop() {
+        if seen.len() >= limit {
+            break;
+        }
+        for next in Direction::MOVES.iter().filter_map(|dir| grid.step(pos, *dir)) {
+            if open(grid.get(next)) && seen.insert(next) {
+                todo.push(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+/// The move among `moves` that gets closest to food, the first one on a tie.
+fn nearest_food(snake: &Snake, grid: &Occupancy, moves: &[Direction]) -> Option<Direction> {
+    let head = snake.head_pos();
+    moves.iter()
+         .filter_map(|dir| Some((*dir, food_distance(grid, grid.step(head, *dir)?)?)))
+         .min_by_key(|(_, distance)| *distance)
+         .map(|(dir, _)| dir)
+}
+
+fn pick(moves: &[Direction], rng: &mut Pcg32) -> Option<Direction> {
+    if moves.is_empty() {
+        None
+    } else {
+        Some(moves[rng.gen_range(0, moves.len())])
+    }
+}
+
+pub struct RandomSafe;
+
+impl Bot for RandomSafe {
+    fn choose(&mut self, view: &View, rng: &mut Pcg32) -> Direction {
+        pick(&safe_moves(view.snake, view.grid), rng).unwrap_or(view.snake.dir)
+    }
+}
+
+pub struct Greedy;
+
+impl Bot for Greedy {
+    fn choose(&mut self, view: &View, rng: &mut Pcg32) -> Direction {
+        let moves = safe_moves(view.snake, view.grid);
+        nearest_food(view.snake, view.grid, &moves)
+            .or_else(|| pick(&moves, rng))
+            .unwrap_or(view.snake.dir)
+    }
+}
+
+pub struct LookAhead;
+
+impl Bot for LookAhead {
+    fn choose(&mut self, view: &View, rng: &mut Pcg32) -> Direction {
+        let (snake, grid) = (view.snake, view.grid);
+        let head = snake.head_pos();
+        let moves = safe_moves(snake, grid);
+
+        // Cells another head could move onto this tick as well.
+        let contested: HashSet<GridPosition> = view.others.iter()
+            .flat_map(|other| Direction::MOVES.iter().filter_map(move |dir| grid.step(other.head_pos(), *dir)))
+            .collect();
+        let length = snake.length();
+        let roomy: Vec<Direction> = moves.iter().cloned()
+            .filter(|dir| {
+                let pos = grid.step(head, *dir).unwrap();
+                !contested.contains(&pos) && room(grid, pos, length) >= length
+            })
+            .collect();
+
+        if let Some(dir) = nearest_food(snake, grid, &roomy).or_else(|| pick(&roomy, rng)) {
+            return dir;
+        }
+        // Boxed in either way: take the move with the most room left.
+        moves.iter()
+             .cloned()
+             .max_by_key(|dir| room(grid, grid.step(head, *dir).unwrap(), grid.size().0 as usize * grid.size().1 as usize))
+             .unwrap_or(snake.dir)
+    }
+}
+
+/// Whether a fresh snake fits at `pos` with a clear run ahead of it.
+fn fits(grid: &Occupancy, snake: &Snake) -> bool {
+    if !snake.positions().all(|pos| grid.get(pos) == CellKind::Empty && grid.tile(pos).is_none()) {
+        return false;
+    }
+    let mut ahead = Some(snake.head_pos());
+    for _ in 0..ARENA_START_LANE {
+        ahead = ahead.and_then(|pos| grid.step(pos, snake.dir));
+        match ahead {
+            Some(pos) if grid.get(pos) == CellKind::Empty => {},
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Up to `count` rivals of `tier`, put on `grid` where they fit: rows a
+/// quarter of the way from the top and the bottom first, then the middle,
+/// each starting from the centre column.
+pub fn spawn(count: u8, tier: BotTier, grid: &mut Occupancy) -> Vec<Rival> {
+    let (width, height) = grid.size();
+    let starts = [(width / 2, height / 4), (width / 2, height - 1 - height / 4), (width / 2 + 2, height / 2)];
+    let mut rivals = Vec::new();
+    for (i, (x0, y0)) in starts.iter().cycle().take(count.min(MAX_RIVALS) as usize).enumerate() {
+        let rows = (0..height).flat_map(|dy| vec![y0 + dy, y0 - dy]).filter(|y| *y >= 0 && *y < height);
+        let found = rows.flat_map(|y| (*x0..width).chain(1..*x0).map(move |x| (x, y)))
+            .map(|(x, y)| {
+                let mut snake = Snake::new(GridPosition::new(x, y));
+                snake.rival = Some(i as u8);
+                snake
+            })
+            .find(|snake| fits(grid, snake));
+        if let Some(snake) = found {
+            grid.place_snake(&snake);
+            rivals.push(Rival::new(snake, tier));
+        }
+    }
+    rivals
+}
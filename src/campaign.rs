@@ -0,0 +1,363 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use ggez::{filesystem, Context, GameResult};
+
+use rand_pcg::Pcg32;
+
+use crate::arena::ArenaKind;
+use crate::board::Board;
+use crate::consts::GRID_SIZE;
+use crate::elements::{Ate, Food, Snake, Wall};
+use crate::modes::{Classic, GameMode};
+use crate::tiles::Tiles;
+use crate::topology::Topology;
+
+pub const LEVELS_DIR: &str = "/levels";
+pub const PROGRESS_PATH: &str = "/progress.txt";
+
+/// What has to be reached to clear a level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    Points(u32),
+    Length(usize),
+}
+
+impl Target {
+    pub fn reached(&self, points: u32, length: usize) -> bool {
+        match *self {
+            Target::Points(target) => points >= target,
+            Target::Length(target) => length >= target,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Target::Points(points) => write!(f, "points {}", points),
+            Target::Length(length) => write!(f, "length {}", length),
+        }
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.split_whitespace();
+        let kind = parts.next().unwrap_or("");
+        let value = parts.next().ok_or(format!("missing value for target {}", kind))?;
+        let bad = |_| format!("bad target: {}", s);
+        match kind {
+            "points" => value.parse().map(Target::Points).map_err(bad),
+            "length" => value.parse().map(Target::Length).map_err(bad),
+            _ => Err(format!("unknown target: {}", kind)),
+        }
+    }
+}
+
+/// One campaign level: a board to start from, with its walls, the snake and
+/// any mice placed by hand, and what it takes to clear it. Levels are kept as
+/// text, a few `key value` lines followed by the board as `Board` writes it:
+///
+/// ```text
+/// name Two Rooms
+/// target points 12
+/// time 110
+/// stars 55 85
+/// ```
+///
+/// `time` is the limit in seconds. Clearing the level within the first
+/// `stars` number of seconds earns three stars, within the second two, and
+/// otherwise one.
+#[derive(Clone, Debug)]
+pub struct Level {
+    pub name: String,
+    pub target: Target,
+    pub time: u32,
+    pub stars: (u32, u32),
+    pub board: Board,
+}
+
+impl Level {
+    /// Stars for clearing the level in `seconds`.
+    pub fn stars(&self, seconds: f32) -> u8 {
+        if seconds <= self.stars.0 as f32 {
+            3
+        } else if seconds <= self.stars.1 as f32 {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The target and the time limit, the way the start screen shows them.
+    pub fn goal(&self) -> String {
+        match self.target {
+            Target::Points(points) => format!("{} POINTS IN {}s", points, self.time),
+            Target::Length(length) => format!("LENGTH {} IN {}s", length, self.time),
+        }
+    }
+}
+
+impl GameMode for Level {
+    fn walls(&self, _rng: &mut Pcg32, _arena: ArenaKind, _topology: Topology) -> Wall {
+        self.board.walls.clone()
+    }
+
+    fn snake(&self) -> Snake {
+        self.board.snake.clone()
+    }
+
+    fn tiles(&self) -> Tiles {
+        self.board.tiles.clone()
+    }
+
+    fn foods(&self) -> Vec<Food> {
+        self.board.foods.clone()
+    }
+
+    fn is_over(&self, ate: Option<Ate>, elapsed: f32) -> bool {
+        Classic.is_over(ate, elapsed) || elapsed >= self.time as f32
+    }
+
+    fn is_won(&self, points: u32, length: usize) -> bool {
+        self.target.reached(points, length)
+    }
+
+    fn hud(&self, elapsed: f32) -> Option<String> {
+        let left = (self.time as f32 - elapsed).max(0.0).ceil();
+        Some(format!("GOAL {}   TIME {:.0}s", self.target.to_string().to_uppercase(), left))
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "target {}", self.target)?;
+        writeln!(f, "time {}", self.time)?;
+        writeln!(f, "stars {} {}", self.stars.0, self.stars.1)?;
+        write!(f, "{}", self.board)
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut target = None;
+        let mut time = None;
+        let mut stars = None;
+        let (header, rows) = split_header(s);
+        for (key, value) in header {
+            let number = |value: &str| value.parse::<u32>().map_err(|_| format!("bad value for {}: {}", key, value));
+            match key {
+                "name" => name = Some(value.to_string()),
+                "target" => target = Some(value.parse::<Target>()?),
+                "time" => time = Some(number(value)?),
+                "stars" => {
+                    let limits: Vec<u32> = value.split_whitespace().map(number).collect::<Result<_, _>>()?;
+                    match limits.as_slice() {
+                        &[three, two] if three <= two => stars = Some((three, two)),
+                        _ => return Err(format!("bad stars line: stars {}", value)),
+                    }
+                }
+                _ => return Err(format!("unknown key: {}", key)),
+            }
+        }
+
+        let board: Board = rows.parse()?;
+        if board.size != GRID_SIZE {
+            return Err(format!("level is {}x{}, not {}x{}", board.size.0, board.size.1, GRID_SIZE.0, GRID_SIZE.1));
+        }
+        let time = time.ok_or("missing time")?;
+        Ok(Level {
+            name: name.ok_or("missing name")?,
+            target: target.ok_or("missing target")?,
+            stars: stars.unwrap_or((time / 2, time)),
+            time,
+            board,
+        })
+    }
+}
+
+/// Splits a level or puzzle into its header of `key value` lines and the
+/// board below it. The header ends at the first board row, the first line
+/// with no space.
+pub fn split_header(s: &str) -> (Vec<(&str, &str)>, String) {
+    let lines: Vec<&str> = s.lines().map(|line| line.trim()).collect();
+    let start = lines.iter().position(|line| !line.is_empty() && !line.contains(' ')).unwrap_or(lines.len());
+    let header = lines[..start].iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut parts = line.splitn(2, ' ');
+            (parts.next().unwrap_or(""), parts.next().unwrap_or("").trim())
+        })
+        .collect();
+    (header, lines[start..].join("\n"))
+}
+
+/// The files in `dir` numbered from `01.txt` up, each parsed as a `T`.
+/// Loading stops at the first number that is missing or does not parse.
+pub fn load_numbered<T: FromStr<Err = String>>(ctx: &mut Context, dir: &str) -> Vec<T> {
+    let mut items = Vec::new();
+    loop {
+        let path = format!("{}/{:02}.txt", dir, items.len() + 1);
+        if !filesystem::exists(ctx, &path) {
+            break;
+        }
+        let mut text = String::new();
+        let read = filesystem::open(ctx, &path)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.into()));
+        if let Err(e) = read {
+            eprintln!("Could not read {}: {}", path, e);
+            break;
+        }
+        match text.parse::<T>() {
+            Ok(item) => items.push(item),
+            Err(e) => {
+                eprintln!("Could not parse {}: {}", path, e);
+                break;
+            }
+        }
+    }
+    items
+}
+
+/// The levels in `LEVELS_DIR`, numbered from `01.txt` up.
+#[derive(Clone, Debug, Default)]
+pub struct Campaign {
+    levels: Vec<Level>,
+}
+
+impl Campaign {
+    pub fn new(levels: Vec<Level>) -> Self {
+        Campaign { levels }
+    }
+
+    pub fn load(ctx: &mut Context) -> Campaign {
+        Campaign { levels: load_numbered(ctx, LEVELS_DIR) }
+    }
+
+    pub fn levels(&self) -> &[Level] {
+        &self.levels
+    }
+}
+
+/// The best clear of a level.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clear {
+    pub stars: u8,
+    pub points: u32,
+    pub seconds: f32,
+}
+
+impl Clear {
+    /// More stars first, then more points, then a quicker clear.
+    fn beats(&self, other: &Clear) -> bool {
+        (self.stars, self.points) > (other.stars, other.points)
+            || ((self.stars, self.points) == (other.stars, other.points) && self.seconds < other.seconds)
+    }
+}
+
+/// How far the player got in the campaign, kept in the user directory as one
+/// `level stars points seconds` line per cleared level, counted from 1. The
+/// first level is always open and clearing one opens the next.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    cleared: BTreeMap<usize, Clear>,
+}
+
+impl Progress {
+    pub fn load(ctx: &mut Context) -> Progress {
+        if !filesystem::exists(ctx, PROGRESS_PATH) {
+            return Progress::default();
+        }
+
+        let mut text = String::new();
+        let read = filesystem::open(ctx, PROGRESS_PATH)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.into()));
+        if let Err(e) = read {
+            eprintln!("Could not read campaign progress: {}", e);
+            return Progress::default();
+        }
+
+        text.parse().unwrap_or_else(|e| {
+            eprintln!("Could not parse campaign progress: {}", e);
+            Progress::default()
+        })
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::create(ctx, PROGRESS_PATH)?;
+        write!(file, "{}", self)?;
+        Ok(())
+    }
+
+    /// The best clear of the level at `index`, counted from 0.
+    pub fn best(&self, index: usize) -> Option<Clear> {
+        self.cleared.get(&(index + 1)).copied()
+    }
+
+    pub fn is_unlocked(&self, index: usize) -> bool {
+        index == 0 || self.best(index - 1).is_some()
+    }
+
+    /// Stars earned over the whole campaign.
+    pub fn stars(&self) -> u32 {
+        self.cleared.values().map(|clear| clear.stars as u32).sum()
+    }
+
+    /// Keeps `clear` if it is the best one of its level so far and tells
+    /// whether it was.
+    pub fn record(&mut self, index: usize, clear: Clear) -> bool {
+        match self.cleared.get(&(index + 1)) {
+            Some(best) if !clear.beats(best) => false,
+            _ => {
+                self.cleared.insert(index + 1, clear);
+                true
+            }
+        }
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (level, clear) in self.cleared.iter() {
+            writeln!(f, "{} {} {} {}", level, clear.stars, clear.points, clear.seconds)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Progress {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut progress = Progress::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let bad = || format!("bad progress line: {}", line);
+            match parts.as_slice() {
+                &[level, stars, points, seconds] => {
+                    let level = level.parse::<usize>().map_err(|_| bad())?;
+                    let clear = Clear {
+                        stars: stars.parse().map_err(|_| bad())?,
+                        points: points.parse().map_err(|_| bad())?,
+                        seconds: seconds.parse().map_err(|_| bad())?,
+                    };
+                    if level == 0 {
+                        return Err(bad());
+                    }
+                    progress.cleared.insert(level, clear);
+                }
+                _ => return Err(bad()),
+            }
+        }
+        Ok(progress)
+    }
+}
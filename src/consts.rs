@@ -6,5 +6,56 @@ pub const SCREEN_SIZE: (f32, f32) = (
     GRID_SIZE.1 as f32 * GRID_CELL_SIZE.1 as f32,
 );
 
-pub const UPDATES_PER_SECOND: f32 = 10.0;
-pub const MILLIS_PER_UPDATE: u64 = (1.0 / UPDATES_PER_SECOND * 1000.0) as u64;
+pub const UPDATES_PER_SECOND: u32 = 10;
+pub const MAX_UPDATES_PER_SECOND: u32 = 20;
+pub const SPEEDUP_STEP: u32 = 1;
+pub const SPEEDUP_EVERY: u32 = 5;
+
+pub const FOOD_ITEMS: usize = 3;
+pub const BONUS_TICKS: u32 = 60;
+pub const BURST_SPEEDUP: u32 = 6;
+pub const BURST_TICKS: u32 = 40;
+
+pub const POWERUP_EVERY: u64 = 150;
+pub const POWERUP_TICKS: u32 = 100;
+pub const MAX_POWERUPS: usize = 2;
+pub const SLOW_TIME_TICKS: u32 = 60;
+pub const GHOST_TICKS: u32 = 50;
+
+pub const FLEE_RADIUS: i16 = 5;
+pub const MAX_MICE: usize = 12;
+
+pub const TIME_ATTACK_SECONDS: f32 = 120.0;
+pub const SURVIVAL_RING_TICKS: u64 = 150;
+pub const SURVIVAL_RINGS: i16 = 6;
+pub const HIGH_SCORES: usize = 5;
+pub const LEVELS_PER_COLUMN: usize = 12;
+
+pub const ARENA_TRIES: u32 = 20;
+pub const ARENA_START_LANE: usize = 3;
+pub const ARENA_MIN_FREE: f32 = 0.4;
+pub const ARENA_OBSTACLES: u32 = 12;
+pub const ARENA_HOLES: f32 = 0.3;
+pub const ARENA_MAZE_LOOPS: f32 = 0.1;
+pub const ARENA_CAVE_ROCK: f32 = 0.42;
+pub const ARENA_CAVE_ROUNDS: u32 = 4;
+
+pub const MAX_RIVALS: u8 = 3;
+/// Tints for the rivals' head and body sprites, red, blue and gold.
+pub const RIVAL_TINTS: [(f32, f32, f32); 3] = [(1.0, 0.45, 0.45), (0.5, 0.7, 1.0), (1.0, 0.85, 0.35)];
+
+pub const BOT_MOVE_TIMEOUT_MS: u64 = 200;
+pub const DUEL_SECONDS: u32 = 120;
+/// The smallest board every arena can be built on. Symmetric arenas are the
+/// ones that need it all.
+pub const DUEL_MIN_SIZE: (i16, i16) = (12, 10);
+pub const ELO_START: f64 = 1500.0;
+pub const ELO_K: f64 = 32.0;
+pub const TRUESKILL_MU: f64 = 25.0;
+pub const TRUESKILL_SIGMA: f64 = TRUESKILL_MU / 3.0;
+pub const TRUESKILL_DRAW_PROBABILITY: f64 = 0.1;
+
+pub const VOLUME_STEP: f32 = 0.1;
+
+pub const SHAKE_STRENGTH: f32 = 8.0;
+pub const SHAKE_SECONDS: f32 = 0.35;
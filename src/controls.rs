@@ -0,0 +1,100 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ggez::event::KeyCode;
+
+use serde::{Deserialize, Serialize};
+
+use crate::elements::{Direction, Turn};
+
+/// How key presses are turned into snake moves.
+///
+/// `Absolute` maps the four arrows onto the four directions, `Relative` only
+/// turns left or right of the current heading, so it can be played with two
+/// buttons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    Absolute,
+    Relative,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Input {
+    Absolute(Direction),
+    Relative(Turn),
+}
+
+impl ControlScheme {
+
+    pub fn toggle(&self) -> Self {
+        match *self {
+            ControlScheme::Absolute => ControlScheme::Relative,
+            ControlScheme::Relative => ControlScheme::Absolute,
+        }
+    }
+
+    pub fn input_from_keycode(&self, key: KeyCode) -> Option<Input> {
+        match *self {
+            ControlScheme::Absolute => Direction::from_keycode(key).map(Input::Absolute),
+            ControlScheme::Relative => match key {
+                KeyCode::Left | KeyCode::A => Some(Input::Relative(Turn::Left)),
+                KeyCode::Right | KeyCode::D => Some(Input::Relative(Turn::Right)),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ControlScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ControlScheme::Absolute => write!(f, "absolute"),
+            ControlScheme::Relative => write!(f, "relative"),
+        }
+    }
+}
+
+impl FromStr for ControlScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "absolute" => Ok(ControlScheme::Absolute),
+            "relative" => Ok(ControlScheme::Relative),
+            _ => Err(format!("unknown control scheme: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Input::Absolute(Direction::Up) => write!(f, "up"),
+            Input::Absolute(Direction::Down) => write!(f, "down"),
+            Input::Absolute(Direction::Left) => write!(f, "left"),
+            Input::Absolute(Direction::Right) => write!(f, "right"),
+            Input::Absolute(Direction::None) => write!(f, "none"),
+            Input::Relative(Turn::Left) => write!(f, "turn-left"),
+            Input::Relative(Turn::Right) => write!(f, "turn-right"),
+            Input::Relative(Turn::Straight) => write!(f, "straight"),
+        }
+    }
+}
+
+impl FromStr for Input {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "up" => Ok(Input::Absolute(Direction::Up)),
+            "down" => Ok(Input::Absolute(Direction::Down)),
+            "left" => Ok(Input::Absolute(Direction::Left)),
+            "right" => Ok(Input::Absolute(Direction::Right)),
+            "none" => Ok(Input::Absolute(Direction::None)),
+            "turn-left" => Ok(Input::Relative(Turn::Left)),
+            "turn-right" => Ok(Input::Relative(Turn::Right)),
+            "straight" => Ok(Input::Relative(Turn::Straight)),
+            _ => Err(format!("unknown input: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use crate::arena::ArenaKind;
+use crate::bots::{Bot, View};
+use crate::consts::*;
+use crate::controls::Input;
+use crate::elements::{Ate, Direction, GridPosition, Segment, Snake, Wall};
+use crate::food::FoodManager;
+use crate::grid::{CellKind, Occupancy};
+use crate::topology::Topology;
+
+/// How a headless game between bots is set up.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DuelConfig {
+    pub size: (i16, i16),
+    /// Ticks a second the game would run at on screen. With `seconds` it
+    /// sets how many ticks a game lasts at most.
+    pub speed: u32,
+    pub seconds: u32,
+    pub arena: ArenaKind,
+    pub topology: Topology,
+}
+
+impl Default for DuelConfig {
+    fn default() -> Self {
+        DuelConfig {
+            size: GRID_SIZE,
+            speed: UPDATES_PER_SECOND,
+            seconds: DUEL_SECONDS,
+            arena: ArenaKind::Plain,
+            topology: Topology::Portals,
+        }
+    }
+}
+
+impl DuelConfig {
+    pub fn max_ticks(&self) -> u64 {
+        self.speed as u64 * self.seconds as u64
+    }
+}
+
+/// The two snakes of a duel, the second one the first turned half way
+/// round the middle of the board so neither side starts better off.
+fn starts(size: (i16, i16)) -> Vec<Snake> {
+    let mut first = Snake::new(GridPosition::new(size.0 / 4, size.1 / 2));
+    first.rival = Some(0);
+    let mirror = |pos: GridPosition| {
+        let (x, y) = pos.coords();
+        GridPosition::new(size.0 - 1 - x, size.1 - y)
+    };
+    let segments: Vec<Segment> = first.segments()
+                                      .map(|seg| Segment::new(mirror(seg.pos), Direction::Left))
+                                      .collect();
+    let mut second = Snake::from_segments(&segments, Direction::Left);
+    second.rival = Some(1);
+    vec![first, second]
+}
+
+/// A game between two bots with nobody at the keyboard. There are no
+/// power-ups, keys or modes: the snakes eat, grow and crash by the usual
+/// rules until one is left or the time is up.
+pub struct Duel {
+    pub config: DuelConfig,
+    pub snakes: Vec<Snake>,
+    pub alive: Vec<bool>,
+    pub walls: Wall,
+    pub grid: Occupancy,
+    pub foods: FoodManager,
+    pub tick: u64,
+    rng: Pcg32,
+}
+
+impl Duel {
+    pub fn new(config: DuelConfig, seed: u64) -> Duel {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let snakes = starts(config.size);
+        let walls = config.arena.build_around(&mut rng, config.size, &snakes, config.topology);
+        let mut grid = Occupancy::new(config.size).with_topology(config.topology);
+        for pos in walls.positions() {
+            grid.set(pos, CellKind::Wall);
+        }
+        for snake in snakes.iter() {
+            grid.place_snake(snake);
+        }
+        let mut foods = FoodManager::new(FOOD_ITEMS, None);
+        foods.fill(&mut rng, &mut grid);
+        Duel {
+            config,
+            alive: vec![true; snakes.len()],
+            snakes,
+            walls,
+            grid,
+            foods,
+            tick: 0,
+            rng,
+        }
+    }
+
+    /// What the snake on `side` sees before it picks its move.
+    pub fn view(&self, side: usize) -> View<'_> {
+        let others = self.snakes.iter()
+                                .enumerate()
+                                .filter(|(i, _)| *i != side && self.alive[*i])
+                                .map(|(_, snake)| snake)
+                                .collect();
+        View {
+            snake: &self.snakes[side],
+            others,
+            grid: &self.grid,
+            foods: self.foods.items(),
+            tick: self.tick,
+        }
+    }
+
+    /// Plays a tick with one move a snake, all of them picked before any
+    /// snake moved. The snakes then move one after another, taking turns at
+    /// going first so neither side gains from the order.
+    pub fn step(&mut self, moves: &[Direction]) {
+        let count = self.snakes.len();
+        for k in 0..count {
+            let side = (k + self.tick as usize) % count;
+            if !self.alive[side] {
+                continue;
+            }
+            let snake = &mut self.snakes[side];
+            if moves[side] != Direction::None {
+                snake.steer(moves[side]);
+            }
+            snake.update(&mut self.grid);
+            match snake.ate {
+                Some(Ate::Food(_)) => {
+                    self.foods.take(snake.head_pos(), &mut self.grid);
+                }
+                Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake) => {
+                    self.alive[side] = false;
+                    self.grid.clear_snake(snake);
+                }
+                _ => {},
+            }
+        }
+        self.tick += 1;
+        let head = self.snakes[0].head_pos();
+        self.foods.update(self.tick, head, &mut self.rng, &mut self.grid);
+        self.foods.fill(&mut self.rng, &mut self.grid);
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.alive.iter().filter(|alive| **alive).count() <= 1 || self.tick >= self.config.max_ticks()
+    }
+
+    /// The side that won: the last snake left, or the longest of those
+    /// still going when the time ran out. Snakes that crashed on the same
+    /// tick are measured against each other. `None` is a draw.
+    pub fn winner(&self) -> Option<usize> {
+        let mut sides: Vec<usize> = (0..self.snakes.len()).filter(|side| self.alive[*side]).collect();
+        if sides.is_empty() {
+            sides = (0..self.snakes.len()).collect();
+        }
+        let longest = sides.iter().map(|side| self.snakes[*side].length()).max()?;
+        let best: Vec<usize> = sides.into_iter().filter(|side| self.snakes[*side].length() == longest).collect();
+        match best.as_slice() {
+            &[side] => Some(side),
+            _ => None,
+        }
+    }
+}
+
+/// A finished duel as it is kept on disk: the setup, the bots, the result
+/// and the moves of every tick, so the game can be played again move for
+/// move.
+///
+/// ```text
+/// seed 7
+/// size 30 20
+/// speed 10
+/// seconds 120
+/// arena maze
+/// topology torus
+/// snake greedy
+/// snake lookahead
+/// winner 1
+/// ticks 2
+/// up left
+/// right down
+/// ```
+///
+/// The winner is a side counted from 0, or `draw`. A snake that is out has
+/// `none` for its move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub seed: u64,
+    pub config: DuelConfig,
+    pub names: Vec<String>,
+    pub winner: Option<usize>,
+    pub ticks: u64,
+    pub moves: Vec<Vec<Direction>>,
+}
+
+impl Record {
+    /// The duel played again with the recorded moves.
+    pub fn replay(&self) -> Duel {
+        let mut duel = Duel::new(self.config, self.seed);
+        for moves in self.moves.iter() {
+            duel.step(moves);
+        }
+        duel
+    }
+}
+
+/// Plays a duel on `seed` between `bots`, named `names`, to the end. Every
+/// bot draws its random numbers from a stream of its own, so a bot playing
+/// at random does not change where the mice turn up.
+pub fn play(config: DuelConfig, seed: u64, bots: &mut [Box<dyn Bot>], names: Vec<String>) -> Record {
+    let mut duel = Duel::new(config, seed);
+    let mut rngs: Vec<Pcg32> = (0..bots.len()).map(|side| Pcg32::new(seed, side as u64 + 1)).collect();
+    let mut moves = Vec::new();
+    for (side, bot) in bots.iter_mut().enumerate() {
+        bot.start(&duel.view(side));
+    }
+    while !duel.is_over() {
+        let tick: Vec<Direction> = bots.iter_mut()
+                                       .zip(rngs.iter_mut())
+                                       .enumerate()
+                                       .map(|(side, (bot, rng))| {
+                                           if duel.alive[side] {
+                                               bot.choose(&duel.view(side), rng)
+                                           } else {
+                                               Direction::None
+                                           }
+                                       })
+                                       .collect();
+        duel.step(&tick);
+        moves.push(tick);
+    }
+    for (side, bot) in bots.iter_mut().enumerate() {
+        bot.end(&duel.view(side));
+    }
+    Record {
+        seed,
+        config,
+        names,
+        winner: duel.winner(),
+        ticks: duel.tick,
+        moves,
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "size {} {}", self.config.size.0, self.config.size.1)?;
+        writeln!(f, "speed {}", self.config.speed)?;
+        writeln!(f, "seconds {}", self.config.seconds)?;
+        writeln!(f, "arena {}", self.config.arena)?;
+        writeln!(f, "topology {}", self.config.topology)?;
+        for name in self.names.iter() {
+            writeln!(f, "snake {}", name)?;
+        }
+        match self.winner {
+            Some(side) => writeln!(f, "winner {}", side)?,
+            None => writeln!(f, "winner draw")?,
+        }
+        writeln!(f, "ticks {}", self.ticks)?;
+        for moves in self.moves.iter() {
+            let line: Vec<String> = moves.iter().map(|dir| Input::Absolute(*dir).to_string()).collect();
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// The value on the next line, which has to start with `key`.
+fn field<'a, I: Iterator<Item = &'a str>>(lines: &mut I, key: &str) -> Result<String, String> {
+    lines.next()
+         .and_then(|l| l.strip_prefix(key))
+         .and_then(|l| l.strip_prefix(' '))
+         .map(|l| l.trim().to_string())
+         .ok_or(format!("missing {}", key))
+}
+
+impl FromStr for Record {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().filter(|l| !l.trim().is_empty()).peekable();
+        let number = |key: &str, value: &str| value.parse::<u64>().map_err(|_| format!("bad value for {}: {}", key, value));
+
+        let seed = number("seed", &field(&mut lines, "seed")?)?;
+        let size = field(&mut lines, "size")?;
+        let size: Vec<i16> = size.split_whitespace()
+                                 .map(|n| n.parse::<i16>().map_err(|_| format!("bad size: {}", size)))
+                                 .collect::<Result<_, _>>()?;
+        let size = match size.as_slice() {
+            &[width, height] => (width, height),
+            _ => return Err("bad size line".to_string()),
+        };
+        if size.0 < DUEL_MIN_SIZE.0 || size.1 < DUEL_MIN_SIZE.1 {
+            return Err(format!("board is {}x{}, under {}x{}", size.0, size.1, DUEL_MIN_SIZE.0, DUEL_MIN_SIZE.1));
+        }
+        let config = DuelConfig {
+            size,
+            speed: number("speed", &field(&mut lines, "speed")?)? as u32,
+            seconds: number("seconds", &field(&mut lines, "seconds")?)? as u32,
+            arena: field(&mut lines, "arena")?.parse()?,
+            topology: field(&mut lines, "topology")?.parse()?,
+        };
+
+        let mut names = Vec::new();
+        while let Some(name) = lines.peek().and_then(|l| l.strip_prefix("snake ")) {
+            names.push(name.trim().to_string());
+            lines.next();
+        }
+        let winner = match field(&mut lines, "winner")?.as_str() {
+            "draw" => None,
+            side => Some(number("winner", side)? as usize),
+        };
+        let ticks = number("ticks", &field(&mut lines, "ticks")?)?;
+
+        let mut moves = Vec::new();
+        for line in lines {
+            let tick: Vec<Direction> = line.split_whitespace()
+                .map(|word| match word.parse::<Input>()? {
+                    Input::Absolute(dir) => Ok(dir),
+                    Input::Relative(_) => Err(format!("not a direction: {}", word)),
+                })
+                .collect::<Result<_, String>>()?;
+            if tick.len() != names.len() {
+                return Err(format!("expected {} moves: {}", names.len(), line));
+            }
+            moves.push(tick);
+        }
+        Ok(Record { seed, config, names, winner, ticks, moves })
+    }
+}
@@ -0,0 +1,303 @@
+use std::io::{Read, Write};
+
+use ggez::mint::Point2;
+use ggez::{filesystem, graphics, Context, GameResult};
+
+use crate::assets::Assets;
+use crate::board::Board;
+use crate::campaign::{Level, Target};
+use crate::consts::*;
+use crate::elements::{Direction, Food, GridPosition, Segment, Snake, Wall};
+use crate::grid::CellKind;
+
+pub const EDITOR_PATH: &str = "/custom_level.txt";
+
+/// What a click paints.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    Wall,
+    /// Clears walls, mice and tiles, opening holes in the border.
+    Hole,
+    /// Moves the snake's start there, or turns it when clicked on its head.
+    Snake,
+    /// A mouse that is on the board when the level starts.
+    Mouse,
+}
+
+impl Tool {
+    pub const ALL: [Tool; 4] = [Tool::Wall, Tool::Hole, Tool::Snake, Tool::Mouse];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Tool::Wall => "WALL",
+            Tool::Hole => "HOLE",
+            Tool::Snake => "SNAKE",
+            Tool::Mouse => "MOUSE",
+        }
+    }
+}
+
+/// Something about the level being edited that makes it no fun to play.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// Open cells the snake can never get to.
+    Unreachable(Vec<GridPosition>),
+    /// The first move runs into a wall or the snake itself.
+    FatalStart,
+}
+
+/// A level being drawn with the mouse. Every stroke can be undone and redone,
+/// and the level is kept in the same text form as the campaign levels.
+pub struct Editor {
+    level: Level,
+    undo: Vec<Level>,
+    redo: Vec<Level>,
+    stroke: Option<Level>,
+    /// What is wrong with `level`, worked out again after every edit.
+    problems: Vec<Problem>,
+    pub tool: Tool,
+}
+
+impl Editor {
+    pub fn new(level: Level) -> Self {
+        let mut editor = Editor { level, undo: Vec::new(), redo: Vec::new(), stroke: None, problems: Vec::new(), tool: Tool::Wall };
+        editor.check();
+        editor
+    }
+
+    /// A closed box with the snake where the game puts it.
+    pub fn blank() -> Self {
+        let mut walls = Vec::new();
+        for x in 0..GRID_SIZE.0 {
+            for y in 1..GRID_SIZE.1 {
+                if y == 1 || y == GRID_SIZE.1 - 1 || x == 0 || x == GRID_SIZE.0 - 1 {
+                    walls.push(GridPosition::new(x, y));
+                }
+            }
+        }
+        let snake = Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into());
+        Editor::new(Level {
+            name: "Custom".to_string(),
+            target: Target::Points(10),
+            time: 120,
+            stars: (60, 90),
+            board: Board::new(GRID_SIZE, Wall::from_positions(walls), snake, Vec::new()),
+        })
+    }
+
+    /// Opens the level saved last time, or a blank one.
+    pub fn load(ctx: &mut Context) -> Editor {
+        if !filesystem::exists(ctx, EDITOR_PATH) {
+            return Editor::blank();
+        }
+
+        let mut text = String::new();
+        let read = filesystem::open(ctx, EDITOR_PATH)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.into()));
+        if let Err(e) = read {
+            eprintln!("Could not read the custom level: {}", e);
+            return Editor::blank();
+        }
+
+        match text.parse() {
+            Ok(level) => Editor::new(level),
+            Err(e) => {
+                eprintln!("Could not parse the custom level: {}", e);
+                Editor::blank()
+            }
+        }
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::create(ctx, EDITOR_PATH)?;
+        write!(file, "{}", self.level)?;
+        Ok(())
+    }
+
+    pub fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Starts a stroke: everything painted until `end_stroke` is undone in one go.
+    pub fn begin_stroke(&mut self) {
+        if self.stroke.is_none() {
+            self.stroke = Some(self.level.clone());
+        }
+    }
+
+    pub fn end_stroke(&mut self) {
+        if let Some(before) = self.stroke.take() {
+            if before.to_string() != self.level.to_string() {
+                self.undo.push(before);
+                self.redo.clear();
+                self.check();
+            }
+        }
+    }
+
+    /// Paints the cell at `pos` with `tool`. The score bar cannot be painted.
+    pub fn paint(&mut self, pos: GridPosition, tool: Tool) {
+        if pos.coords().1 == 0 || !self.on_board(pos) {
+            return;
+        }
+        if tool == Tool::Snake {
+            let snake = &self.level.board.snake;
+            let dir = if pos == snake.head_pos() { snake.dir.turn_right() } else { snake.dir };
+            self.place_snake(pos, dir);
+            return;
+        }
+
+        let on_snake = self.level.board.snake.positions().any(|p| p == pos);
+        let board = &mut self.level.board;
+        match tool {
+            Tool::Wall if !on_snake => {
+                board.foods.retain(|food| food.pos != pos);
+                if !board.walls.positions().any(|p| p == pos) {
+                    board.walls.add(pos);
+                }
+            }
+            Tool::Hole => {
+                board.walls.remove(pos);
+                board.tiles.remove(pos);
+                board.foods.retain(|food| food.pos != pos);
+            }
+            Tool::Mouse if !on_snake => {
+                board.walls.remove(pos);
+                if !board.foods.iter().any(|food| food.pos == pos) {
+                    board.foods.push(Food::new(pos));
+                }
+            }
+            _ => {},
+        }
+    }
+
+    /// A two-cell snake with its head at `head`, heading `dir`. Walls and
+    /// mice under it are cleared.
+    fn place_snake(&mut self, head: GridPosition, dir: Direction) {
+        let tail = GridPosition::new_from_move_in(head, dir.inverse(), GRID_SIZE);
+        let board = &mut self.level.board;
+        for pos in [head, tail].iter() {
+            board.walls.remove(*pos);
+            board.foods.retain(|food| food.pos != *pos);
+        }
+        board.snake = Snake::from_segments(&[Segment::new(head, dir), Segment::new(tail, dir)], dir);
+    }
+
+    fn on_board(&self, pos: GridPosition) -> bool {
+        let (x, y) = pos.coords();
+        x >= 0 && y >= 0 && x < GRID_SIZE.0 && y < GRID_SIZE.1
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(level) = self.undo.pop() {
+            self.redo.push(std::mem::replace(&mut self.level, level));
+            self.check();
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(level) = self.redo.pop() {
+            self.undo.push(std::mem::replace(&mut self.level, level));
+            self.check();
+        }
+    }
+
+    /// Open cells below the score bar that cannot be reached from the head,
+    /// going through holes in the border like the snake does.
+    pub fn unreachable(&self) -> Vec<GridPosition> {
+        let grid = self.level.board.grid();
+        let seen = grid.reachable(self.level.board.snake.head_pos());
+
+        let mut cells = Vec::new();
+        for y in 1..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                let pos = GridPosition::new(x, y);
+                if grid.get(pos) != CellKind::Wall && !seen.contains(&pos) {
+                    cells.push(pos);
+                }
+            }
+        }
+        cells
+    }
+
+    /// Whether the snake dies on its very first move.
+    pub fn fatal_start(&self) -> bool {
+        let snake = &self.level.board.snake;
+        let grid = self.level.board.grid();
+        let ahead = match grid.step(snake.head_pos(), snake.dir) {
+            Some(pos) => pos,
+            None => return true,
+        };
+        match grid.get(ahead) {
+            CellKind::Wall => true,
+            CellKind::Snake | CellKind::Rival(_) => ahead != snake.positions().last().unwrap(),
+            _ => false,
+        }
+    }
+
+    /// What was wrong with the level when the last stroke, undo or redo
+    /// finished. Cells painted in the middle of a stroke are not counted yet.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    fn check(&mut self) {
+        let mut problems = Vec::new();
+        let unreachable = self.unreachable();
+        if !unreachable.is_empty() {
+            problems.push(Problem::Unreachable(unreachable));
+        }
+        if self.fatal_start() {
+            problems.push(Problem::FatalStart);
+        }
+        self.problems = problems;
+    }
+
+    pub fn draw(&self, ctx: &mut Context, assets: &mut Assets) -> GameResult {
+        let board = &self.level.board;
+        board.tiles.draw(ctx, assets)?;
+        board.walls.draw(ctx, assets)?;
+        for food in board.foods.iter() {
+            food.draw(ctx, assets)?;
+        }
+        board.snake.draw(ctx, assets, 1.0, false, &board.grid())?;
+
+        let mut warnings = Vec::new();
+        let mut marks = graphics::MeshBuilder::new();
+        let mut marked = false;
+        for problem in self.problems.iter() {
+            let cells = match problem {
+                Problem::Unreachable(cells) => {
+                    warnings.push(format!("{} CELLS UNREACHABLE", cells.len()));
+                    cells.clone()
+                }
+                Problem::FatalStart => {
+                    warnings.push("FATAL START".to_string());
+                    vec![board.snake.head_pos()]
+                }
+            };
+            for pos in cells {
+                let pnt2: Point2<f32> = pos.into();
+                let rect = graphics::Rect::new(pnt2.x, pnt2.y, GRID_CELL_SIZE.0 as f32, GRID_CELL_SIZE.1 as f32);
+                marks.rectangle(graphics::DrawMode::fill(), rect, graphics::Color::new(0.9, 0.1, 0.1, 0.4));
+                marked = true;
+            }
+        }
+        if marked {
+            let mesh = marks.build(ctx)?;
+            graphics::draw(ctx, &mesh, graphics::DrawParam::default())?;
+        }
+
+        let tools: Vec<String> = Tool::ALL.iter().enumerate().map(|(i, tool)| {
+            if *tool == self.tool { format!("[{} {}]", i + 1, tool.name()) } else { format!("{} {}", i + 1, tool.name()) }
+        }).collect();
+        let hud = format!("{}   Z UNDO  Y REDO  S SAVE  P PLAY  ESC BACK", tools.join(" "));
+        let text = assets.text(&hud, graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, text, (Point2 { x: 10.0, y: 2.0 },))?;
+        if !warnings.is_empty() {
+            let text = assets.text(&warnings.join("   "), graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, text, (Point2 { x: 10.0, y: 20.0 },))?;
+        }
+        Ok(())
+    }
+}
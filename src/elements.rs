@@ -1,4 +1,6 @@
-use std::collections::LinkedList;
+use std::collections::{LinkedList, VecDeque};
+use std::fmt;
+use std::str::FromStr;
 
 use ggez::event::{KeyCode};
 use ggez::mint::Point2;
@@ -6,12 +8,19 @@ use ggez::{graphics::{self, DrawParam},
            Context,
            GameResult};
 
-use rand;
 use rand::Rng;
 
+use serde::{Deserialize, Serialize};
+
+use crate::assets::{Assets, Sprite};
 use crate::consts::*;
+use crate::controls::{ControlScheme, Input};
+use crate::grid::{CellKind, Occupancy};
+use crate::path::SnakePath;
+use crate::powerups::PowerUpKind;
+use crate::tiles::{group_color, Tile};
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct GridPosition {
     x: i16,
     y: i16,
@@ -37,8 +46,11 @@ impl GridPosition {
         GridPosition { x, y }
     }
 
-    pub fn random(min_x: i16, min_y: i16, max_x: i16, max_y: i16) -> Self {
-        let mut rng = rand::thread_rng();
+    pub fn coords(&self) -> (i16, i16) {
+        (self.x, self.y)
+    }
+
+    pub fn random<R: Rng>(rng: &mut R, min_x: i16, min_y: i16, max_x: i16, max_y: i16) -> Self {
         let mut p: i16 = 0;
         while p<2{
             p = rng.gen_range::<i16, i16, i16>(min_x, max_x);
@@ -51,11 +63,20 @@ impl GridPosition {
     }
 
     pub fn new_from_move(pos: GridPosition, dir: Direction) -> Self {
+        GridPosition::new_from_move_in(pos, dir, GRID_SIZE)
+    }
+
+    /// Like `new_from_move`, on a board of the given size instead of the window's.
+    /// Row 0 sits under the score bar, so going off the top comes back on the
+    /// bottom row and going off the bottom comes back on row 1.
+    pub fn new_from_move_in(pos: GridPosition, dir: Direction, size: (i16, i16)) -> Self {
         match dir {
-            Direction::Up => GridPosition::new(pos.x, (pos.y - 1).modulo(GRID_SIZE.1)),
-            Direction::Down => GridPosition::new(pos.x, (pos.y + 1).modulo(GRID_SIZE.1)),
-            Direction::Left => GridPosition::new((pos.x - 1).modulo(GRID_SIZE.0), pos.y),
-            Direction::Right => GridPosition::new((pos.x + 1).modulo(GRID_SIZE.0), pos.y),
+            Direction::Up if pos.y <= 1 => GridPosition::new(pos.x, size.1 - 1),
+            Direction::Up => GridPosition::new(pos.x, pos.y - 1),
+            Direction::Down if pos.y >= size.1 - 1 => GridPosition::new(pos.x, 1),
+            Direction::Down => GridPosition::new(pos.x, pos.y + 1),
+            Direction::Left => GridPosition::new((pos.x - 1).modulo(size.0), pos.y),
+            Direction::Right => GridPosition::new((pos.x + 1).modulo(size.0), pos.y),
             Direction::None => GridPosition::new(pos.x, pos.y),
         }
     }
@@ -88,7 +109,7 @@ impl From<GridPosition> for Point2<f32> {
 }
 
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Direction {
     Up,
     Down,
@@ -99,6 +120,9 @@ pub enum Direction {
 
 impl Direction {
 
+    /// The four ways there are to move.
+    pub const MOVES: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
     pub fn inverse(&self) -> Self {
         match *self {
             Direction::Up => Direction::Down,
@@ -109,6 +133,34 @@ impl Direction {
         }
     }
 
+    pub fn turn_left(&self) -> Self {
+        match *self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+            _ => Direction::None,
+        }
+    }
+
+    pub fn turn_right(&self) -> Self {
+        match *self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+            _ => Direction::None,
+        }
+    }
+
+    pub fn turn(&self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.turn_left(),
+            Turn::Right => self.turn_right(),
+            Turn::Straight => *self,
+        }
+    }
+
     pub fn from_keycode(key: KeyCode) -> Option<Direction> {
         match key {
             KeyCode::Up => Some(Direction::Up),
@@ -120,10 +172,19 @@ impl Direction {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A move relative to the current heading. Straight is the "no input" action,
+/// so a relative controller only ever chooses between three moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Turn {
+    Left,
+    Straight,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Segment {
     pub pos: GridPosition,
-    dir: Direction,
+    pub dir: Direction,
 }
 
 impl Segment {
@@ -132,105 +193,297 @@ impl Segment {
     }
 }
 
+/// What a piece of food does to the snake when eaten.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FoodKind {
+    Mouse,
+    Golden,
+    Poisoned,
+    Bonus,
+}
+
+impl FoodKind {
+    pub const ALL: [FoodKind; 4] = [FoodKind::Mouse, FoodKind::Golden, FoodKind::Poisoned, FoodKind::Bonus];
+
+    pub fn points(&self) -> u32 {
+        match *self {
+            FoodKind::Mouse => 1,
+            FoodKind::Golden => 5,
+            FoodKind::Poisoned => 0,
+            FoodKind::Bonus => 3,
+        }
+    }
+
+    /// Cells the snake grows by, negative for food that shrinks it.
+    pub fn growth(&self) -> i32 {
+        match *self {
+            FoodKind::Mouse => 1,
+            FoodKind::Golden => 2,
+            FoodKind::Poisoned => -3,
+            FoodKind::Bonus => 1,
+        }
+    }
+
+    /// How often this kind is picked compared to the others.
+    pub fn weight(&self) -> u32 {
+        match *self {
+            FoodKind::Mouse => 70,
+            FoodKind::Golden => 8,
+            FoodKind::Poisoned => 14,
+            FoodKind::Bonus => 8,
+        }
+    }
+
+    /// Ticks the food stays on the board, or `None` if it waits to be eaten.
+    pub fn lifetime(&self) -> Option<u32> {
+        match *self {
+            FoodKind::Bonus => Some(BONUS_TICKS),
+            _ => None,
+        }
+    }
+
+    pub fn sprite(&self) -> Sprite {
+        match *self {
+            FoodKind::Mouse => Sprite::Mouse,
+            FoodKind::Golden => Sprite::GoldenMouse,
+            FoodKind::Poisoned => Sprite::PoisonedMouse,
+            FoodKind::Bonus => Sprite::BonusMouse,
+        }
+    }
+}
+
+impl fmt::Display for FoodKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            FoodKind::Mouse => write!(f, "mouse"),
+            FoodKind::Golden => write!(f, "golden"),
+            FoodKind::Poisoned => write!(f, "poisoned"),
+            FoodKind::Bonus => write!(f, "bonus"),
+        }
+    }
+}
+
+impl FromStr for FoodKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mouse" => Ok(FoodKind::Mouse),
+            "golden" => Ok(FoodKind::Golden),
+            "poisoned" => Ok(FoodKind::Poisoned),
+            "bonus" => Ok(FoodKind::Bonus),
+            _ => Err(format!("unknown food: {}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Food {
     pub pos: GridPosition,
-    image: graphics::Image,
+    pub kind: FoodKind,
+    pub ticks_left: Option<u32>,
 }
 
 impl Food {
-    pub fn new(pos: GridPosition, ctx: &mut Context) -> GameResult<Food> {
-        let image = graphics::Image::new(ctx, "/mouse.png")?;
-        let s = Food { pos, image };
-        Ok(s)
+    pub fn new(pos: GridPosition) -> Food {
+        Food::of_kind(pos, FoodKind::Mouse)
+    }
+
+    pub fn of_kind(pos: GridPosition, kind: FoodKind) -> Food {
+        Food { pos, kind, ticks_left: kind.lifetime() }
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult {
         let pnt2: Point2<f32> = self.pos.into();
-        graphics::draw(ctx, &self.image, (pnt2,))?;
+        graphics::draw(ctx, assets.image(self.kind.sprite()), (pnt2,))?;
         Ok(())
     }
 }
 
-#[derive(Clone, Debug)]
+/// A cell of wall that can open. Doors and switches are linked by the group
+/// they share with the keys and switches that work them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Door {
+    pub pos: GridPosition,
+    pub group: u8,
+    pub kind: DoorKind,
+    pub open: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorKind {
+    /// Shut until a key of its group is eaten, then gone for good.
+    Locked,
+    /// Flips between shut and open while a switch of its group is held down.
+    /// `rests_open` is how it stands when none is.
+    Gate { rests_open: bool },
+}
+
+impl Door {
+    pub fn new(pos: GridPosition, group: u8, kind: DoorKind) -> Door {
+        let open = matches!(kind, DoorKind::Gate { rests_open: true });
+        Door { pos, group, kind, open }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Wall {
     list: LinkedList<Segment>,
-    image: graphics::Image,
+    #[serde(default)]
+    doors: Vec<Door>,
 }
 
-fn if_hole() -> bool {
-    let mut rng = rand::thread_rng();
+fn if_hole<R: Rng>(rng: &mut R) -> bool {
     rng.gen::<u32>() % 100 < 30
 }
 
 impl Wall {
-    pub fn new(ctx: &mut Context) -> GameResult<Wall> {
+    pub fn new<R: Rng>(rng: &mut R) -> Wall {
+        Wall::bordered(rng, GRID_SIZE)
+    }
+
+    /// The border with random holes in the sides on a board of `size`.
+    pub fn bordered<R: Rng>(rng: &mut R, size: (i16, i16)) -> Wall {
         let mut list = LinkedList::new();
-        for i in 0..GRID_SIZE.0{
-            for j in 0..GRID_SIZE.1{
-                if j==1 || j+1 == GRID_SIZE.1 {
+        for i in 0..size.0{
+            for j in 0..size.1{
+                if j==1 || j+1 == size.1 {
                     list.push_back(Segment::new((i as i16, j as i16).into(), Direction::None));
-                } else if (i == 0 || i+1 == GRID_SIZE.0) && !if_hole() && j != 0 {
+                } else if (i == 0 || i+1 == size.0) && !if_hole(rng) && j != 0 {
                     list.push_back(Segment::new((i as i16, j as i16).into(), Direction::None));
                 }
             }
         }
 
-        let image = graphics::Image::new(ctx, "/wall.png")?;
+        Wall { list, doors: Vec::new() }
+    }
 
-        let s = Wall {
-            list,
-            image: image,
-        };
+    /// Walls exactly on the given cells, for boards that are not drawn at random.
+    pub fn from_positions<I: IntoIterator<Item = GridPosition>>(positions: I) -> Wall {
+        let list = positions.into_iter()
+                            .map(|pos| Segment::new(pos, Direction::None))
+                            .collect();
+        Wall { list, doors: Vec::new() }
+    }
+
+    /// Every cell that blocks the way: the wall segments and the shut doors.
+    pub fn positions(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.list.iter()
+                 .map(|seg| seg.pos)
+                 .chain(self.doors.iter().filter(|door| !door.open).map(|door| door.pos))
+    }
+
+    pub fn doors(&self) -> impl Iterator<Item = &Door> + '_ {
+        self.doors.iter()
+    }
+
+    /// Puts in a door, replacing whatever door was at its cell.
+    pub fn add_door(&mut self, door: Door) {
+        self.doors.retain(|d| d.pos != door.pos);
+        self.doors.push(door);
+    }
+
+    /// Takes out every locked door of `group`, opening its cells.
+    pub fn unlock(&mut self, group: u8, grid: &mut Occupancy) {
+        for door in self.doors.iter().filter(|door| door.kind == DoorKind::Locked && door.group == group) {
+            grid.set(door.pos, CellKind::Empty);
+        }
+        self.doors.retain(|door| door.kind != DoorKind::Locked || door.group != group);
+    }
 
-        Ok(s)
+    /// Sets the gates of `group` the way they stand while a switch is `held`
+    /// down or not. A gate does not shut on anything: it waits until its
+    /// cell is clear.
+    pub fn press(&mut self, group: u8, held: bool, grid: &mut Occupancy) {
+        for door in self.doors.iter_mut().filter(|door| door.group == group) {
+            if let DoorKind::Gate { rests_open } = door.kind {
+                let open = rests_open != held;
+                if open && !door.open {
+                    grid.set(door.pos, CellKind::Empty);
+                    door.open = true;
+                } else if !open && door.open && grid.get(door.pos) == CellKind::Empty {
+                    grid.set(door.pos, CellKind::Wall);
+                    door.open = false;
+                }
+            }
+        }
     }
 
-    pub fn draw(&self, ctx: &mut Context) -> GameResult<()> {
+    /// Puts up a wall segment at `pos`.
+    pub fn add(&mut self, pos: GridPosition) {
+        self.list.push_back(Segment::new(pos, Direction::None));
+    }
+
+    /// Knocks out the wall segment at `pos`, leaving a hole.
+    pub fn remove(&mut self, pos: GridPosition) {
+        self.list = self.list.iter().filter(|seg| seg.pos != pos).cloned().collect();
+        self.doors.retain(|door| door.pos != pos);
+    }
+
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult<()> {
         for seg in self.list.iter() {
             let pnt2: Point2<f32> = seg.pos.into();
-            graphics::draw(ctx, &self.image, (pnt2,))?;
+            graphics::draw(ctx, assets.image(Sprite::Wall), (pnt2,))?;
+        }
+        for door in self.doors.iter() {
+            let pnt2: Point2<f32> = door.pos.into();
+            let sprite = match door.kind {
+                DoorKind::Locked => Sprite::Door,
+                DoorKind::Gate { .. } => Sprite::Gate,
+            };
+            let alpha = if door.open { 0.3 } else { 1.0 };
+            let color = group_color(door.group, alpha);
+            graphics::draw(ctx, assets.image(sprite), DrawParam::default().dest(pnt2).color(color))?;
         }
         Ok(())
     }
 }
 
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Ate {
     Itself,
-    Food,
+    Food(FoodKind),
+    PowerUp(PowerUpKind),
     Wall,
+    /// Ran into a wall with a wall breaker and went through it.
+    BrokenWall,
+    /// Ran into another snake.
+    OtherSnake,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Snake {
 
     head: Segment,
     pub dir: Direction,
-    body: LinkedList<Segment>,
+    body: VecDeque<Segment>,
     tail: Segment,
     pub ate: Option<Ate>,
     pub last_update_dir: Direction,
     pub next_dir: Option<Direction>,
-    head_image: graphics::Image,
-    body_image: graphics::Image,
-    turn_body_image: graphics::Image,
-    tail_image: graphics::Image,
-    blood_image: graphics::Image,
-    blood_wall_image: graphics::Image,
+    pub controls: ControlScheme,
+    pub wrapped: bool,
+    last_tail: GridPosition,
+    growing: u32,
+    /// Passes through its own body instead of dying.
+    pub ghost: bool,
+    /// Goes through the next wall it runs into instead of dying.
+    pub breaks_walls: bool,
+    /// Cells the snake covers twice after passing through itself.
+    overlaps: u32,
+    /// Which rival this is, or `None` for the player's snake. Rivals mark
+    /// their cells apart on the grid so snakes can tell each other from
+    /// themselves.
+    #[serde(default)]
+    pub rival: Option<u8>,
 }
 
 impl Snake {
-    pub fn new(pos: GridPosition, ctx: &mut Context) -> GameResult<Snake> {
-        let body = LinkedList::new();
-        let head_image = graphics::Image::new(ctx, "/shead.png")?;
-        let body_image = graphics::Image::new(ctx, "/sbody.png")?;
-        let turn_body_image = graphics::Image::new(ctx, "/sturn.png")?;
-        let tail_image = graphics::Image::new(ctx, "/send.png")?;
-        let blood_image = graphics::Image::new(ctx, "/blood.png")?;
-        let blood_wall_image = graphics::Image::new(ctx, "/holewall.png")?;
-
-        let s = Snake {
+    pub fn new(pos: GridPosition) -> Snake {
+        let body = VecDeque::new();
+
+        Snake {
             head: Segment::new(pos, Direction::Right),
             dir: Direction::Right,
             last_update_dir: Direction::Right,
@@ -238,180 +491,277 @@ impl Snake {
             tail: Segment::new((pos.x - 1, pos.y).into(), Direction::Right),
             ate: None,
             next_dir: None,
-            head_image: head_image,
-            body_image: body_image,
-            turn_body_image: turn_body_image,
-            tail_image: tail_image,
-            blood_image: blood_image,
-            blood_wall_image: blood_wall_image,
-        };
-        Ok(s)
+            controls: ControlScheme::Absolute,
+            wrapped: false,
+            last_tail: (pos.x - 1, pos.y).into(),
+            growing: 0,
+            ghost: false,
+            breaks_walls: false,
+            overlaps: 0,
+            rival: None,
+        }
+    }
+
+    /// A snake laid out on the given segments, from the head to the tail as
+    /// `segments` returns them, heading `dir`. There must be at least two.
+    pub fn from_segments(segments: &[Segment], dir: Direction) -> Snake {
+        assert!(segments.len() >= 2, "a snake needs a head and a tail");
+        let head = segments[0];
+        let tail = segments[segments.len() - 1];
+        let body = segments[1..segments.len() - 1].iter().rev().cloned().collect();
+
+        Snake {
+            head,
+            dir,
+            last_update_dir: head.dir,
+            body,
+            tail,
+            ate: None,
+            next_dir: None,
+            controls: ControlScheme::Absolute,
+            wrapped: false,
+            last_tail: tail.pos,
+            growing: 0,
+            ghost: false,
+            breaks_walls: false,
+            overlaps: 0,
+            rival: None,
+        }
+    }
+
+    pub fn head_pos(&self) -> GridPosition {
+        self.head.pos
+    }
+
+    /// Segments from the head to the tail. Each one points the way the snake
+    /// moved to enter its cell, the tail the way it will leave.
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> + '_ {
+        std::iter::once(&self.head)
+            .chain(self.body.iter().rev())
+            .chain(std::iter::once(&self.tail))
+    }
+
+    /// Cells taken by the snake, from the head to the tail.
+    pub fn positions(&self) -> impl Iterator<Item = GridPosition> + '_ {
+        self.segments().map(|seg| seg.pos)
+    }
+
+    pub fn length(&self) -> usize {
+        self.body.len() + 2
     }
 
-    pub fn eats(&self, food: &Food) -> bool {
-        if self.head.pos == food.pos {
+    pub fn steer(&mut self, dir: Direction) -> bool {
+        if self.dir != self.last_update_dir && dir.inverse() != self.dir {
+            self.next_dir = Some(dir);
+            true
+        } else if dir.inverse() != self.last_update_dir {
+            self.next_dir = Some(dir);
             true
         } else {
             false
         }
     }
 
-    pub fn eats_self(&self) -> bool {
-        for seg in self.body.iter() {
-            if self.head.pos == seg.pos {
-                return true;
-            }
+    pub fn turn(&mut self, turn: Turn) -> bool {
+        let dir = self.next_dir.unwrap_or(self.dir).turn(turn);
+        self.steer(dir)
+    }
+
+    pub fn apply_input(&mut self, input: Input) -> bool {
+        match input {
+            Input::Absolute(dir) => self.steer(dir),
+            Input::Relative(turn) => self.turn(turn),
         }
-        false
     }
 
-    pub fn collides(&self, walls: &Wall) -> bool {
-        for wall in walls.list.iter() {
-            if self.head.pos == wall.pos {
-                return true;
-            }
+    /// What the snake's cells read as on the grid.
+    pub fn cell(&self) -> CellKind {
+        match self.rival {
+            Some(rival) => CellKind::Rival(rival),
+            None => CellKind::Snake,
         }
-        false
     }
 
+    pub fn eats(&self, grid: &Occupancy) -> Option<FoodKind> {
+        match grid.get(self.head.pos) {
+            CellKind::Food(kind) => Some(kind),
+            _ => None,
+        }
+    }
 
-    pub fn update(&mut self, food: &Food, walls: &Wall) {
+    /// The tail leaves its cell on the same tick unless the snake is still
+    /// growing, so moving into it is safe.
+    fn hits_body(&self, grid: &Occupancy) -> bool {
+        grid.get(self.head.pos) == self.cell()
+            && (self.head.pos != self.tail.pos || self.growing > 0)
+    }
+
+    pub fn eats_self(&self, grid: &Occupancy) -> bool {
+        !self.ghost && self.hits_body(grid)
+    }
+
+    /// Whether the head ran into another snake. Its tail counts too: the
+    /// other snake has not moved yet when this one does.
+    pub fn hits_other(&self, grid: &Occupancy) -> bool {
+        let cell = grid.get(self.head.pos);
+        matches!(cell, CellKind::Snake | CellKind::Rival(_)) && cell != self.cell()
+    }
+
+    pub fn collides(&self, grid: &Occupancy) -> bool {
+        grid.get(self.head.pos) == CellKind::Wall
+    }
+
+
+    /// Moves the snake one cell and records what it ran into in `ate`.
+    /// `grid` is checked before the move and kept in sync with it.
+    pub fn update(&mut self, grid: &mut Occupancy) {
 
         if self.last_update_dir == self.dir && self.next_dir.is_some() {
             self.dir = self.next_dir.unwrap();
             self.next_dir = None;
         }
+        if let Some(Tile::Conveyor(way)) = grid.tile(self.head.pos) {
+            self.dir = way;
+        }
 
-        let new_head_pos = GridPosition::new_from_move(self.head.pos, self.dir);
+        self.last_tail = self.tail.pos;
+
+        let new_head_pos = match grid.step(self.head.pos, self.dir) {
+            Some(pos) => pos,
+            None => {
+                // Off the edge of a board that does not wrap, or the wrong way
+                // onto a tile: as deadly as a wall.
+                self.ate = Some(Ate::Wall);
+                self.last_update_dir = self.dir;
+                return;
+            }
+        };
+        self.wrapped = (new_head_pos.x - self.head.pos.x).abs() > 1
+                       || (new_head_pos.y - self.head.pos.y).abs() > 1;
         let new_head = Segment::new(new_head_pos, self.dir);
         self.body.push_back(self.head.clone());
         self.head = new_head;
-        if self.eats_self() {
+        if self.eats_self(grid) {
             self.ate = Some(Ate::Itself);
-        } else if self.eats(food) {
-            self.ate = Some(Ate::Food);
-        } else if self.collides(walls){
+        } else if self.hits_other(grid) {
+            self.ate = Some(Ate::OtherSnake);
+        } else if let Some(kind) = self.eats(grid) {
+            self.ate = Some(Ate::Food(kind));
+        } else if self.collides(grid) && self.breaks_walls {
+            self.ate = Some(Ate::BrokenWall);
+        } else if self.collides(grid) {
             self.ate = Some(Ate::Wall);
+        } else if let CellKind::PowerUp(kind) = grid.get(self.head.pos) {
+            self.ate = Some(Ate::PowerUp(kind));
         } else {
             self.ate = None;
         }
 
-        if let None = self.ate {
-            self.tail = self.body.front().unwrap().clone();
-            self.body.pop_front();
-            if self.body.is_empty(){
-                self.tail.dir = self.head.dir;
-            }else{
-                self.tail.dir = self.body.front().unwrap().dir;
+        match self.ate {
+            Some(Ate::Food(kind)) if kind.growth() < 0 => self.growing = 0,
+            Some(Ate::Food(kind)) => self.growing += kind.growth() as u32,
+            Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake) => {
+                self.last_update_dir = self.dir;
+                return;
+            }
+            _ => {},
+        }
+
+        if self.hits_body(grid) {
+            self.overlaps += 1;
+        }
+        let grows = self.growing > 0;
+        if grows {
+            self.growing -= 1;
+        } else {
+            self.clear_tail(grid);
+        }
+        grid.set(self.head.pos, self.cell());
+
+        if !grows {
+            self.move_tail();
+        }
+        if let Some(Ate::Food(kind)) = self.ate {
+            if kind.growth() < 0 {
+                self.shrink(kind.growth().unsigned_abs() as usize, grid);
             }
         }
 
         self.last_update_dir = self.dir;
     }
 
-    pub fn draw(&self, ctx: &mut Context, gameover: bool) -> GameResult<()> {
-
-        let drawparam = DrawParam::default();
+    /// Empties the cell the tail is leaving, unless another piece of a snake
+    /// that went through itself is still there.
+    fn clear_tail(&mut self, grid: &mut Occupancy) {
+        if self.overlaps > 0 && self.body.iter().any(|seg| seg.pos == self.tail.pos) {
+            self.overlaps -= 1;
+        } else {
+            grid.set(self.tail.pos, CellKind::Empty);
+        }
+    }
 
-        let mut iter = self.body.iter();
-        iter.next();
-        for seg in self.body.iter() {
-            let next_seg = iter.next();
-            let dir = next_seg.unwrap_or(&self.head).dir;
-            let pnt2: Point2<f32> = seg.pos.into();
+    fn move_tail(&mut self) {
+        self.tail = self.body.pop_front().unwrap();
+        self.tail.dir = match self.body.front() {
+            Some(seg) => seg.dir,
+            None => self.head.dir,
+        };
+    }
 
-            if dir != seg.dir{
-                let param = get_param_for_turned(seg.dir, dir);
-                graphics::draw(ctx, &self.turn_body_image, drawparam.rotation(param.0).offset(param.1).dest(pnt2))?;
-            }else{
-                let param = get_param(dir);
-                graphics::draw(ctx, &self.body_image, drawparam.rotation(param.0).offset(param.1).dest(pnt2))?;
+    /// Drops up to `cells` pieces from the tail, never below a head and a tail.
+    pub fn shrink(&mut self, cells: usize, grid: &mut Occupancy) {
+        self.growing = 0;
+        for _ in 0..cells {
+            if self.body.is_empty() {
+                break;
             }
+            self.clear_tail(grid);
+            self.move_tail();
         }
+        self.last_tail = self.tail.pos;
+    }
 
-        let mut param = get_param(self.dir);
-        let mut pnt2: Point2<f32> = self.head.pos.into();
-        graphics::draw(ctx, &self.head_image, drawparam.rotation(param.0).offset(param.1).dest(pnt2))?;
+    /// Draws the snake `alpha` of the way from the previous tick to the current one.
+    /// Every piece slides one cell along the path during a tick, bending around
+    /// corners, across the edges of the board and through teleporters the way
+    /// `grid` leads it.
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets, alpha: f32, gameover: bool, grid: &Occupancy) -> GameResult<()> {
+
+        let mut cells: Vec<GridPosition> = Vec::with_capacity(self.body.len() + 3);
+        cells.push(self.head.pos);
+        cells.extend(self.body.iter().rev().map(|seg| seg.pos));
+        cells.push(self.tail.pos);
+        if self.last_tail != self.tail.pos {
+            cells.push(self.last_tail);
+        }
+        let path = SnakePath::new(&cells, grid);
+        let shift = 1.0 - alpha;
+        let tail_index = self.body.len() + 1;
+        let color = match self.rival {
+            Some(rival) => {
+                let (r, g, b) = RIVAL_TINTS[rival as usize % RIVAL_TINTS.len()];
+                graphics::Color::new(r, g, b, 1.0)
+            }
+            None => graphics::WHITE,
+        };
 
-        pnt2 = self.tail.pos.into();
-        param = get_param(self.tail.dir);
-        graphics::draw(ctx, &self.tail_image, drawparam.rotation(param.0).offset(param.1).dest(pnt2))?;
+        path.draw_bent(ctx, assets.image(Sprite::Tail), (tail_index as f32 + shift).min(cells.len() as f32 - 1.0), color)?;
+        for i in (1..tail_index).rev() {
+            path.draw_bent(ctx, assets.image(Sprite::Body), i as f32 + shift, color)?;
+        }
+        path.draw_bent(ctx, assets.image(Sprite::Head), shift, color)?;
 
         if gameover{
-            pnt2 = self.head.pos.into();
+            let pnt2: Point2<f32> = self.head.pos.into();
             let x = self.head.pos.x;
             let y = self.head.pos.y;
             if x == 0 || x+1 == GRID_SIZE.0 || y == 1 || y+1 == GRID_SIZE.1{
-                graphics::draw(ctx, &self.blood_wall_image, (pnt2,))?;
+                graphics::draw(ctx, assets.image(Sprite::HoleWall), (pnt2,))?;
             }else{
-                graphics::draw(ctx, &self.blood_image, (pnt2,))?;
+                graphics::draw(ctx, assets.image(Sprite::Blood), (pnt2,))?;
             }
         }
 
         Ok(())
     }
 }
-
-fn get_param(dir: Direction) -> (f32, Point2<f32>){
-        let mut offset = Point2 {x:0.0, y:0.0};
-        let mut rotation = 0.0;
-        match dir {
-            Direction::Down => {
-                offset = Point2 {x:0.98, y:0.98};
-                rotation = std::f32::consts::PI;
-            },
-            Direction::Right => {
-                offset = Point2 {x:0.0, y:1.0};
-                rotation = std::f32::consts::PI/2.0;
-            },
-            Direction::Left => {
-                offset = Point2 {x:1.0, y:0.0};
-                rotation = -std::f32::consts::PI/2.0;
-            }
-            _ => {},
-        }
-        (rotation, offset)
-}
-
-fn get_param_for_turned(mydir: Direction, nextdir: Direction) -> (f32, Point2<f32>){
-        let mut offset = Point2 {x:0.0, y:0.0};
-        let mut rotation = 0.0;
-        match nextdir {
-            Direction::Up => {
-                if mydir == Direction::Right{
-                    offset = Point2 {x:0.98, y:0.98};
-                    rotation = std::f32::consts::PI;
-                }else if mydir == Direction::Left {
-                    offset = Point2 {x:0.98, y:0.0};
-                    rotation = -std::f32::consts::PI/2.0;
-                }
-            },
-            Direction::Right => {
-                if mydir == Direction::Down{
-                    offset = Point2 {x:1.0, y:0.0};
-                    rotation = -std::f32::consts::PI/2.0;
-                }else if mydir == Direction::Up{
-                    offset = Point2 { x:0.0, y:-0.02 };
-                }
-            },
-            Direction::Left => {
-                if mydir == Direction::Down{
-                    offset = Point2 {x:0.98, y:0.98};
-                    rotation = std::f32::consts::PI;
-                }else if mydir == Direction::Up{
-                    offset = Point2 {x:0.0, y:1.0};
-                    rotation = std::f32::consts::PI/2.0;
-                }
-            }
-            Direction::Down => {
-                if mydir == Direction::Right {
-                    offset = Point2 {x: 0.0, y:1.0};
-                    rotation = std::f32::consts::PI/2.0;
-                } else if mydir == Direction::Left {
-                    offset = Point2 {x: -0.02, y:0.0};
-                }
-            }
-            _ => {},
-        }
-        (rotation, offset)
-}
\ No newline at end of file
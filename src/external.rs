@@ -0,0 +1,250 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand_pcg::Pcg32;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bots::{Bot, View};
+use crate::controls::Input;
+use crate::elements::{Direction, GridPosition, Snake};
+use crate::grid::CellKind;
+
+/// A snake as a bot is told about it: its cells from the head back to the
+/// tail, and the way it is heading.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnakeState {
+    pub segments: Vec<(i16, i16)>,
+    pub dir: String,
+}
+
+impl SnakeState {
+    fn new(snake: &Snake) -> Self {
+        SnakeState {
+            segments: snake.positions().map(|pos| pos.coords()).collect(),
+            dir: Input::Absolute(snake.dir).to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FoodState {
+    pub x: i16,
+    pub y: i16,
+    pub kind: String,
+}
+
+/// Everything an external bot is sent on a tick, as one line of JSON:
+///
+/// ```text
+/// {"tick":0,"width":30,"height":20,"edges":"portals","walls":[[0,1],...],
+///  "holes":[[0,4],...],"you":{"segments":[[7,10],[6,10]],"dir":"right"},
+///  "snakes":[...],"food":[{"x":12,"y":5,"kind":"mouse"}]}
+/// ```
+///
+/// Row 0 is the score bar, which nothing ever goes into: the rows wrap
+/// between 1 and `height - 1`, so a snake going up off row 1 comes back on
+/// the bottom row and one going down off the bottom row comes back on row 1.
+/// Columns wrap between 0 and `width - 1`. `edges` names the topology, which
+/// says which edges wrap at all and which are mirrored. `holes` are the gaps
+/// in the border the snake can go through to the other side, and `snakes`
+/// the other snakes on the board.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TickState {
+    pub tick: u64,
+    pub width: i16,
+    pub height: i16,
+    pub edges: String,
+    pub walls: Vec<(i16, i16)>,
+    pub holes: Vec<(i16, i16)>,
+    pub you: SnakeState,
+    pub snakes: Vec<SnakeState>,
+    pub food: Vec<FoodState>,
+}
+
+impl TickState {
+    pub fn new(view: &View) -> Self {
+        let (width, height) = view.grid.size();
+        let mut walls = Vec::new();
+        let mut holes = Vec::new();
+        for y in 1..height {
+            for x in 0..width {
+                let wall = view.grid.get(GridPosition::new(x, y)) == CellKind::Wall;
+                let border = x == 0 || x == width - 1 || y == 1 || y == height - 1;
+                if wall {
+                    walls.push((x, y));
+                } else if border {
+                    holes.push((x, y));
+                }
+            }
+        }
+        TickState {
+            tick: view.tick,
+            width,
+            height,
+            edges: view.grid.topology().to_string(),
+            walls,
+            holes,
+            you: SnakeState::new(view.snake),
+            snakes: view.others.iter().map(|snake| SnakeState::new(snake)).collect(),
+            food: view.foods.iter()
+                            .map(|food| {
+                                let (x, y) = food.pos.coords();
+                                FoodState { x, y, kind: food.kind.to_string() }
+                            })
+                            .collect(),
+        }
+    }
+}
+
+/// The move in a bot's answer: a bare `up`, `down`, `left` or `right`, or
+/// the same as `{"move": "up"}`.
+pub fn parse_move(line: &str) -> Option<Direction> {
+    let line = line.trim();
+    let word = if line.starts_with('{') {
+        let value: serde_json::Value = serde_json::from_str(line).ok()?;
+        value.get("move")?.as_str()?.to_string()
+    } else {
+        line.trim_matches('"').to_string()
+    };
+    match word.to_lowercase().as_str() {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// Splits a command line into words, keeping what is in quotes together.
+fn split_command(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut started = false;
+    for c in command.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                started = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if started {
+                    words.push(std::mem::take(&mut word));
+                    started = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                started = true;
+            }
+        }
+    }
+    if started {
+        words.push(word);
+    }
+    words
+}
+
+/// A bot in a program of its own, in any language. Every tick it is sent
+/// the `TickState` as a line on its standard input and has `timeout` to
+/// answer with a line on its standard output. No answer in time, or one
+/// that is not a move, keeps the snake going straight; an answer that comes
+/// late is thrown away.
+pub struct ExternalBot {
+    command: String,
+    child: Child,
+    stdin: Option<ChildStdin>,
+    lines: Receiver<String>,
+    timeout: Duration,
+    /// Answers still to come to ticks that timed out.
+    late: usize,
+}
+
+impl ExternalBot {
+    pub fn spawn(command: &str, timeout: Duration) -> io::Result<ExternalBot> {
+        let words = split_command(command);
+        let (program, args) = words.split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty bot command"))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+
+        let stdout = child.stdout.take().unwrap();
+        let (sender, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let sent = line.map(|line| sender.send(line).is_ok()).unwrap_or(false);
+                if !sent {
+                    break;
+                }
+            }
+        });
+
+        Ok(ExternalBot {
+            command: command.to_string(),
+            stdin: child.stdin.take(),
+            child,
+            lines,
+            timeout,
+            late: 0,
+        })
+    }
+
+    pub fn command(&self) -> &str {
+        &self.command
+    }
+
+    /// Sends `state` and waits for the answer to it.
+    fn ask(&mut self, state: &TickState) -> Option<String> {
+        let stdin = self.stdin.as_mut()?;
+        let line = serde_json::to_string(state).ok()?;
+        if writeln!(stdin, "{}", line).and_then(|_| stdin.flush()).is_err() {
+            eprintln!("{}: the bot stopped reading its input", self.command);
+            self.stdin = None;
+            return None;
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let left = deadline.saturating_duration_since(Instant::now());
+            match self.lines.recv_timeout(left) {
+                Ok(_) if self.late > 0 => self.late -= 1,
+                Ok(answer) => return Some(answer),
+                Err(RecvTimeoutError::Timeout) => {
+                    self.late += 1;
+                    return None;
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    eprintln!("{}: the bot quit", self.command);
+                    self.stdin = None;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Bot for ExternalBot {
+    fn choose(&mut self, view: &View, _rng: &mut Pcg32) -> Direction {
+        let state = TickState::new(view);
+        self.ask(&state)
+            .and_then(|answer| parse_move(&answer))
+            .unwrap_or(view.snake.dir)
+    }
+}
+
+impl Drop for ExternalBot {
+    fn drop(&mut self) {
+        self.stdin = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,198 @@
+use ggez::{Context, GameResult};
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assets::Assets;
+use crate::consts::{FLEE_RADIUS, MAX_MICE};
+use crate::elements::{Direction, Food, FoodKind, GridPosition};
+use crate::grid::{CellKind, Occupancy};
+
+/// Tries at finding a free cell before a spawn is given up until next tick.
+const SPAWN_TRIES: u32 = 100;
+
+/// How mice get around when they are allowed to move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MouseMoves {
+    /// Ticks between two steps of a mouse.
+    pub every: u32,
+    /// Ticks between two litters, or 0 for mice that do not breed.
+    pub breed_every: u32,
+}
+
+/// All the food on the board. Eaten food is replaced straight away and timed
+/// food is taken off when it runs out, so the board is kept at `capacity`.
+/// With `moves` set the mice wander about, run from the snake and breed.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FoodManager {
+    items: Vec<Food>,
+    capacity: usize,
+    moves: Option<MouseMoves>,
+}
+
+impl FoodManager {
+    pub fn new(capacity: usize, moves: Option<MouseMoves>) -> Self {
+        FoodManager {
+            items: Vec::with_capacity(capacity),
+            capacity,
+            moves,
+        }
+    }
+
+    pub fn moves(&self) -> Option<MouseMoves> {
+        self.moves
+    }
+
+    pub fn set_moves(&mut self, moves: Option<MouseMoves>) {
+        self.moves = moves;
+    }
+
+    pub fn items(&self) -> &[Food] {
+        &self.items
+    }
+
+    /// Spawns food of random kinds on free cells until the board is full.
+    pub fn fill<R: Rng>(&mut self, rng: &mut R, grid: &mut Occupancy) {
+        let (width, height) = grid.size();
+        self.fill_in(rng, grid, (3, 3, width - 1, height - 1));
+    }
+
+    /// Like `fill`, with the food kept inside `area`, given as
+    /// `(min_x, min_y, max_x, max_y)` with the maximum left out.
+    pub fn fill_in<R: Rng>(&mut self, rng: &mut R, grid: &mut Occupancy, area: (i16, i16, i16, i16)) {
+        while self.items.len() < self.capacity {
+            let kind = pick_kind(rng);
+            match free_cell(rng, grid, area) {
+                Some(pos) => {
+                    grid.place_food(pos, kind);
+                    self.items.push(Food::of_kind(pos, kind));
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Puts a given piece of food on the board, on top of the random ones.
+    pub fn place(&mut self, food: Food, grid: &mut Occupancy) {
+        grid.place_food(food.pos, food.kind);
+        self.items.push(food);
+    }
+
+    /// Removes the food the snake just ate at `pos`.
+    pub fn take(&mut self, pos: GridPosition, grid: &mut Occupancy) -> Option<Food> {
+        let i = self.items.iter().position(|food| food.pos == pos)?;
+        grid.remove_food(pos);
+        Some(self.items.remove(i))
+    }
+
+    /// Counts down timed food, moves and breeds the mice on tick `tick` and
+    /// returns what ran out. Mice run from `head` when it comes close.
+    pub fn update<R: Rng>(&mut self, tick: u64, head: GridPosition, rng: &mut R, grid: &mut Occupancy) -> Vec<Food> {
+        for food in self.items.iter_mut() {
+            if let Some(ticks) = food.ticks_left.as_mut() {
+                *ticks = ticks.saturating_sub(1);
+            }
+        }
+        let (expired, kept) = self.items.drain(..).partition(|food| food.ticks_left == Some(0));
+        self.items = kept;
+        for food in expired.iter() {
+            grid.remove_food(food.pos);
+        }
+
+        if let Some(moves) = self.moves {
+            if tick % moves.every.max(1) as u64 == 0 {
+                self.run(head, rng, grid);
+            }
+            if moves.breed_every > 0 && tick % moves.breed_every as u64 == 0 {
+                self.breed(rng, grid);
+            }
+        }
+        expired
+    }
+
+    /// Moves every mouse one cell. Near the head a mouse takes the free cell
+    /// furthest from it, otherwise it wanders or sits still. Mice cross the
+    /// edges of the board just like the snake.
+    fn run<R: Rng>(&mut self, head: GridPosition, rng: &mut R, grid: &mut Occupancy) {
+        // Moves from the head, through holes and across wrapped edges. Only
+        // the cells near enough to be scared matter.
+        let near = grid.distances(head, FLEE_RADIUS + 1);
+        let distance = |pos: GridPosition| near.get(&pos).cloned().unwrap_or(FLEE_RADIUS + 2);
+        for food in self.items.iter_mut() {
+            let free = free_neighbours(food.pos, grid);
+            if free.is_empty() {
+                continue;
+            }
+
+            let here = distance(food.pos);
+            let to = if here <= FLEE_RADIUS {
+                let best = free.iter().map(|pos| distance(*pos)).max().unwrap();
+                if best <= here {
+                    continue;
+                }
+                let away: Vec<GridPosition> = free.into_iter().filter(|pos| distance(*pos) == best).collect();
+                away[rng.gen_range(0, away.len())]
+            } else {
+                match rng.gen_range(0, free.len() + 1) {
+                    i if i < free.len() => free[i],
+                    _ => continue,
+                }
+            };
+
+            grid.remove_food(food.pos);
+            grid.place_food(to, food.kind);
+            food.pos = to;
+        }
+    }
+
+    /// One plain mouse has a baby next to it, while there is room for more.
+    fn breed<R: Rng>(&mut self, rng: &mut R, grid: &mut Occupancy) {
+        if self.items.len() >= MAX_MICE {
+            return;
+        }
+        let parents: Vec<GridPosition> = self.items.iter()
+                                             .filter(|food| food.kind == FoodKind::Mouse)
+                                             .map(|food| food.pos)
+                                             .collect();
+        if parents.is_empty() {
+            return;
+        }
+        let free = free_neighbours(parents[rng.gen_range(0, parents.len())], grid);
+        if !free.is_empty() {
+            self.place(Food::new(free[rng.gen_range(0, free.len())]), grid);
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult {
+        for food in self.items.iter() {
+            food.draw(ctx, assets)?;
+        }
+        Ok(())
+    }
+}
+
+fn pick_kind<R: Rng>(rng: &mut R) -> FoodKind {
+    let total: u32 = FoodKind::ALL.iter().map(|kind| kind.weight()).sum();
+    let mut roll = rng.gen_range(0, total);
+    for kind in FoodKind::ALL.iter() {
+        if roll < kind.weight() {
+            return *kind;
+        }
+        roll -= kind.weight();
+    }
+    FoodKind::Mouse
+}
+
+fn free_cell<R: Rng>(rng: &mut R, grid: &Occupancy, area: (i16, i16, i16, i16)) -> Option<GridPosition> {
+    let (min_x, min_y, max_x, max_y) = area;
+    (0..SPAWN_TRIES).map(|_| GridPosition::random(rng, min_x, min_y, max_x, max_y))
+                    .find(|pos| grid.get(*pos) == CellKind::Empty && grid.tile(*pos).is_none())
+}
+
+fn free_neighbours(pos: GridPosition, grid: &Occupancy) -> Vec<GridPosition> {
+    Direction::MOVES.iter()
+                    .filter_map(|dir| grid.step(pos, *dir))
+                    .filter(|next| grid.get(*next) == CellKind::Empty)
+                    .collect()
+}
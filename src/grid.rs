@@ -0,0 +1,200 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::elements::{Direction, Food, FoodKind, GridPosition, Snake, Wall};
+use crate::powerups::PowerUpKind;
+use crate::tiles::{Tile, Tiles};
+use crate::topology::Topology;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CellKind {
+    Empty,
+    Wall,
+    Snake,
+    /// A rival snake, by its number.
+    Rival(u8),
+    Food(FoodKind),
+    PowerUp(PowerUpKind),
+}
+
+/// What occupies every cell of the board, one entry per cell in row-major
+/// order. The snake keeps it up to date as it moves, so collision checks
+/// cost the same no matter how long the snake or how many walls there are.
+///
+/// Food positions are kept apart from the cells because food can land
+/// under the snake: that cell reads as food again once the tail leaves it.
+///
+/// The grid also knows how its edges are joined and where the special tiles
+/// are, so everything that moves on it steps the same way.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occupancy {
+    size: (i16, i16),
+    topology: Topology,
+    tiles: Tiles,
+    cells: Vec<CellKind>,
+    foods: Vec<(GridPosition, FoodKind)>,
+}
+
+impl Occupancy {
+    pub fn new(size: (i16, i16)) -> Self {
+        Occupancy {
+            size,
+            topology: Topology::Portals,
+            tiles: Tiles::default(),
+            cells: vec![CellKind::Empty; size.0 as usize * size.1 as usize],
+            foods: Vec::new(),
+        }
+    }
+
+    /// A grid filled in from the current walls, snake and food.
+    pub fn build(size: (i16, i16), walls: &Wall, snake: &Snake, foods: &[Food]) -> Self {
+        let mut grid = Occupancy::new(size);
+        for pos in walls.positions() {
+            grid.set(pos, CellKind::Wall);
+        }
+        grid.place_snake(snake);
+        for food in foods {
+            grid.place_food(food.pos, food.kind);
+        }
+        grid
+    }
+
+    /// The same grid with its edges joined by `topology`.
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    /// The same grid with `tiles` laid on its floor.
+    pub fn with_tiles(mut self, tiles: Tiles) -> Self {
+        self.tiles = tiles;
+        self
+    }
+
+    pub fn size(&self) -> (i16, i16) {
+        self.size
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn tiles(&self) -> &Tiles {
+        &self.tiles
+    }
+
+    pub fn tile(&self, pos: GridPosition) -> Option<Tile> {
+        self.tiles.get(pos)
+    }
+
+    /// Takes the tile off `pos`, as when a key is picked up.
+    pub fn remove_tile(&mut self, pos: GridPosition) -> Option<Tile> {
+        let tile = self.tiles.get(pos);
+        self.tiles.remove(pos);
+        tile
+    }
+
+    /// The cell a move `dir` out of `pos` lands on, through a teleporter if
+    /// it steps onto one, or `None` where the move is not allowed: off the
+    /// edge of a board that does not wrap, or onto a tile the wrong way.
+    pub fn step(&self, pos: GridPosition, dir: Direction) -> Option<GridPosition> {
+        let next = self.topology.step(pos, dir, self.size)?;
+        self.tiles.enter(next, dir)
+    }
+
+    fn index(&self, pos: GridPosition) -> usize {
+        let (x, y) = pos.coords();
+        y as usize * self.size.0 as usize + x as usize
+    }
+
+    pub fn get(&self, pos: GridPosition) -> CellKind {
+        self.cells[self.index(pos)]
+    }
+
+    /// Cells that can be reached from `from` without going through a wall,
+    /// crossing the edges like the snake does.
+    pub fn reachable(&self, from: GridPosition) -> HashSet<GridPosition> {
+        let mut seen: HashSet<GridPosition> = HashSet::new();
+        seen.insert(from);
+        let mut todo = vec![from];
+        while let Some(pos) = todo.pop() {
+            for next in Direction::MOVES.iter().filter_map(|dir| self.step(pos, *dir)) {
+                if self.get(next) != CellKind::Wall && seen.insert(next) {
+                    todo.push(next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// How many moves it takes from `from` to every cell at most `limit`
+    /// moves away, going round walls and across the edges the way the
+    /// topology joins them.
+    pub fn distances(&self, from: GridPosition, limit: i16) -> HashMap<GridPosition, i16> {
+        let mut distances = HashMap::new();
+        distances.insert(from, 0);
+        let mut todo = VecDeque::new();
+        todo.push_back(from);
+        while let Some(pos) = todo.pop_front() {
+            let distance = distances[&pos];
+            if distance == limit {
+                continue;
+            }
+            for next in Direction::MOVES.iter().filter_map(|dir| self.step(pos, *dir)) {
+                if self.get(next) != CellKind::Wall && !distances.contains_key(&next) {
+                    distances.insert(next, distance + 1);
+                    todo.push_back(next);
+                }
+            }
+        }
+        distances
+    }
+
+    /// Marks the cells of `snake`, one that was not on the grid before.
+    pub fn place_snake(&mut self, snake: &Snake) {
+        for pos in snake.positions() {
+            self.set(pos, snake.cell());
+        }
+    }
+
+    /// Clears the cells of a snake that is taken off the board.
+    pub fn clear_snake(&mut self, snake: &Snake) {
+        for pos in snake.positions() {
+            if self.get(pos) == snake.cell() {
+                self.set(pos, CellKind::Empty);
+            }
+        }
+    }
+
+    /// Clearing a cell some food sits on uncovers the food.
+    pub fn set(&mut self, pos: GridPosition, kind: CellKind) {
+        let i = self.index(pos);
+        self.cells[i] = match kind {
+            CellKind::Empty => self.food_at(pos).map_or(CellKind::Empty, CellKind::Food),
+            _ => kind,
+        };
+    }
+
+    fn food_at(&self, pos: GridPosition) -> Option<FoodKind> {
+        self.foods.iter().find(|(p, _)| *p == pos).map(|(_, kind)| *kind)
+    }
+
+    /// Adds food. Food dropped onto a wall or the snake does not hide them:
+    /// the cell keeps what was there first until it is cleared.
+    pub fn place_food(&mut self, pos: GridPosition, kind: FoodKind) {
+        self.foods.push((pos, kind));
+        self.foods.sort_by_key(|(p, _)| p.coords());
+        if self.get(pos) == CellKind::Empty {
+            self.set(pos, CellKind::Food(kind));
+        }
+    }
+
+    /// Takes away food that was eaten or went bad.
+    pub fn remove_food(&mut self, pos: GridPosition) {
+        self.foods.retain(|(p, _)| *p != pos);
+        if let CellKind::Food(_) = self.get(pos) {
+            self.set(pos, CellKind::Empty);
+        }
+    }
+}
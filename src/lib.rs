@@ -0,0 +1,30 @@
+pub mod consts;
+pub mod window;
+pub mod elements;
+pub mod grid;
+pub mod topology;
+pub mod tiles;
+pub mod locks;
+pub mod puzzle;
+pub mod solver;
+pub mod duel;
+pub mod tournament;
+pub mod bots;
+pub mod external;
+pub mod battlesnake;
+pub mod arena;
+pub mod board;
+pub mod food;
+pub mod powerups;
+pub mod modes;
+pub mod scores;
+pub mod campaign;
+pub mod editor;
+pub mod controls;
+pub mod replay;
+pub mod settings;
+pub mod audio;
+pub mod assets;
+pub mod path;
+pub mod particles;
+pub mod save;
@@ -0,0 +1,32 @@
+use std::collections::BTreeMap;
+
+use crate::elements::{Snake, Wall};
+use crate::grid::Occupancy;
+use crate::tiles::Tile;
+
+/// Works the keys and switches for one tick, after the snake has moved. A
+/// key under the head is eaten and opens the locked doors of its group, and
+/// the gates of every group are set by whether the snake lies on one of its
+/// switches. Returns the group of the key eaten, if any.
+pub fn update(snake: &Snake, walls: &mut Wall, grid: &mut Occupancy) -> Option<u8> {
+    let head = snake.head_pos();
+    let key = match grid.tile(head) {
+        Some(Tile::Key(group)) => {
+            grid.remove_tile(head);
+            walls.unlock(group, grid);
+            Some(group)
+        }
+        _ => None,
+    };
+
+    let mut held: BTreeMap<u8, bool> = BTreeMap::new();
+    for (pos, tile) in grid.tiles().iter() {
+        if let Tile::Switch(group) = tile {
+            *held.entry(*group).or_insert(false) |= snake.positions().any(|p| p == *pos);
+        }
+    }
+    for (group, held) in held {
+        walls.press(group, held, grid);
+    }
+    key
+}
@@ -1,58 +1,160 @@
+use std::env;
+use std::iter;
+use std::process;
+use std::time::Duration;
+
 use ggez;
 
-use ggez::event::{KeyCode, KeyMods};
-use ggez::{ event,
+use ggez::event::{KeyCode, KeyMods, MouseButton};
+use ggez::{ conf,
+            event,
             graphics,
+            timer,
             Context,
             GameResult};
 
-use std::time::{Duration, Instant};
-
 use ggez::mint::Point2;
 
-mod consts;
-use consts::*;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
 
-mod window;
-use window::build_window;
+use snake_rust::consts::*;
+use snake_rust::window::build_window;
+use snake_rust::elements::*;
+use snake_rust::grid::Occupancy;
+use snake_rust::arena::ArenaKind;
+use snake_rust::topology::Topology;
+use snake_rust::locks;
+use snake_rust::bots::{self, Bot, Rival, View};
+use snake_rust::external::ExternalBot;
+use snake_rust::battlesnake::{BattlesnakeBot, Url};
+use snake_rust::board::Board;
+use snake_rust::food::FoodManager;
+use snake_rust::powerups::{Effects, PowerUpKind, PowerUps};
+use snake_rust::modes::{GameMode, ModeKind};
+use snake_rust::scores::HighScores;
+use snake_rust::campaign::{Campaign, Clear, Level, Progress};
+use snake_rust::puzzle::{self, Puzzle};
+use snake_rust::editor::{Editor, Tool};
+use snake_rust::controls::{ControlScheme, Input};
+use snake_rust::replay::Replay;
+use snake_rust::settings::Settings;
+use snake_rust::audio::{Audio, Sound};
+use snake_rust::assets::{Assets, Sprite};
+use snake_rust::particles::{Emitter, Particles};
+use snake_rust::save::SavedGame;
 
-mod elements;
-use elements::*;
 
+/// A bot and what to call it on the start screen.
+type NamedBot = (String, Box<dyn Bot>);
 
 struct GameState {
     snake: Snake,
-    food: Food,
+    foods: FoodManager,
+    powerups: PowerUps,
+    effects: Effects,
     walls: Wall,
+    grid: Occupancy,
+    /// Groups of the keys eaten so far, for the score bar.
+    keys: Vec<u8>,
+    rivals: Vec<Rival>,
     gameover: bool,
     start: bool,
     points: u32,
-    points_text: graphics::Text,
-    last_update: Instant,
-    floor_image: graphics::Image,
+    assets: Assets,
+    rng: Pcg32,
+    tick: u64,
+    replay: Replay,
+    speed: u32,
+    burst: u32,
+    mode: ModeKind,
+    rules: Box<dyn GameMode>,
+    elapsed: f32,
+    scores: HighScores,
+    high_score: Option<usize>,
+    campaign: Campaign,
+    progress: Progress,
+    level: Option<usize>,
+    puzzles: Vec<Puzzle>,
+    puzzle: Option<usize>,
+    select: Option<usize>,
+    cleared: Option<u8>,
+    editor: Option<Editor>,
+    painting: Option<(Tool, GridPosition)>,
+    playtest: Option<Level>,
+    settings: Settings,
+    audio: Audio,
+    particles: Particles,
+    has_save: bool,
+    /// A program or a Battlesnake server steering the snake instead of the
+    /// keyboard.
+    bot: Option<NamedBot>,
 }
 
 impl GameState {
 
-    pub fn new(ctx: &mut Context) -> GameResult<GameState> {
+    pub fn new(ctx: &mut Context, audio_enabled: bool) -> GameResult<GameState> {
 
-        let snake_pos = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
-        let food_pos = GridPosition::random(1, 1, GRID_SIZE.0 - 1, GRID_SIZE.1 - 1);
-        let image = graphics::Image::new(ctx, "/floor.png")?;
+        let seed = rand::random::<u64>();
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let settings = Settings::load(ctx);
+        let rules = settings.mode.rules();
+        let walls = rules.walls(&mut rng, settings.arena, settings.topology);
+        let snake = rules.snake();
+        let mut foods = FoodManager::new(rules.food_items(), settings.mouse_moves());
+        let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, foods.items())
+            .with_topology(settings.topology)
+            .with_tiles(rules.tiles());
+        let rivals = bots::spawn(rules.rivals(settings.rivals), settings.rival_tier, &mut grid);
+        foods.fill_in(&mut rng, &mut grid, rules.spawn_area(0, GRID_SIZE));
+        let assets = Assets::new(ctx)?;
+        let audio = Audio::new(ctx, &assets, audio_enabled);
+        let mut replay = Replay::new(seed, ControlScheme::Absolute, settings.mouse_moves());
+        replay.mode = settings.mode;
+        replay.arena = settings.arena;
+        replay.topology = settings.topology;
+        replay.rivals = rules.rivals(settings.rivals);
+        replay.rival_tier = settings.rival_tier;
 
 
         let s = GameState {
-            snake: Snake::new(snake_pos, ctx).unwrap(),
-            food: Food::new(food_pos, ctx).unwrap(),
-            walls: Wall::new(ctx).unwrap(),
+            snake,
+            foods,
+            powerups: PowerUps::new(),
+            effects: Effects::default(),
+            walls,
+            grid,
+            keys: Vec::new(),
+            rivals,
             gameover: false,
             start: false,
             points: 0,
-            points_text: graphics::Text::new("Points: ")
-                                        .set_font( graphics::Font::new(ctx,"/Terminus.ttf").unwrap(),
-                                                   graphics::Scale{x:35.0, y:38.0} ).to_owned(),
-            last_update: Instant::now(),
-            floor_image: image,
+            assets,
+            rng,
+            tick: 0,
+            replay,
+            speed: UPDATES_PER_SECOND,
+            burst: 0,
+            mode: settings.mode,
+            rules,
+            elapsed: 0.0,
+            scores: HighScores::load(ctx),
+            high_score: None,
+            campaign: Campaign::load(ctx),
+            progress: Progress::load(ctx),
+            level: None,
+            puzzles: puzzle::load(ctx),
+            puzzle: None,
+            select: None,
+            cleared: None,
+            editor: None,
+            painting: None,
+            playtest: None,
+            settings,
+            audio,
+            particles: Particles::new(),
+            has_save: SavedGame::exists(ctx),
+            bot: None,
         };
 
         Ok(s)
@@ -64,7 +166,7 @@ impl GameState {
                 if j != 0{
                     let gp: GridPosition = (i as i16, j as i16).into();
                     let pnt2: Point2<f32> = gp.into();
-                    graphics::draw(ctx, &self.floor_image, (pnt2,))?;
+                    graphics::draw(ctx, self.assets.image(Sprite::Floor), (pnt2,))?;
                 }
             }
         }
@@ -72,20 +174,36 @@ impl GameState {
     }
 
     fn draw_score(&mut self, ctx: &mut Context) -> GameResult {
-        let copy_txt = self.points_text.clone().add(self.points.to_string()).to_owned();
+        let text = self.assets.text(&format!("Points: {}", self.points), graphics::Scale{x:35.0, y:38.0});
         let gp: GridPosition = (5 as i16, 0 as i16).into();
         let pnt2: Point2<f32> = gp.into();
-        graphics::draw(ctx, &copy_txt, (pnt2,))?;
+        graphics::draw(ctx, text, (pnt2,))?;
+        Ok(())
+    }
+
+    fn draw_effects(&mut self, ctx: &mut Context) -> GameResult {
+        let mut lines: Vec<String> = self.rules.hud(self.elapsed).into_iter().collect();
+        lines.extend(self.effects.hud(self.ticks_per_second()));
+        if let Some(limit) = self.rules.move_limit() {
+            lines.push(format!("MOVES {}/{}", self.tick, limit));
+        }
+        if !self.keys.is_empty() {
+            let keys: Vec<String> = self.keys.iter().map(|group| group.to_string()).collect();
+            lines.push(format!("KEYS {}", keys.join(" ")));
+        }
+        let hud = lines.join("   ");
+        if hud.is_empty() {
+            return Ok(());
+        }
+        let gp: GridPosition = (16 as i16, 0 as i16).into();
+        let mut pnt2: Point2<f32> = gp.into();
+        pnt2.y += 8.0;
+        let text = self.assets.text(&hud, graphics::Scale{x:26.0, y:28.0});
+        graphics::draw(ctx, text, (pnt2,))?;
         Ok(())
     }
 
     fn draw_game_over(&mut self, ctx: &mut Context) -> GameResult{
-        let text = graphics::Text::new("GAME OVER")
-                                        .set_font( graphics::Font::new(ctx,"/Terminus.ttf").unwrap(),
-                                                   graphics::Scale{x:100.0, y:100.0} ).to_owned();
-        let little_text = graphics::Text::new("PRESS R TO RESTART OR ESCAPE TO EXIT")
-                                        .set_font( graphics::Font::new(ctx,"/Terminus.ttf").unwrap(),
-                                                   graphics::Scale{x:20.0, y:20.0} ).to_owned();
         let gp: GridPosition = (10 as i16, 8 as i16).into();
         let mut pnt2: Point2<f32> = gp.into();
         pnt2.x -= 20.0;
@@ -93,65 +211,763 @@ impl GameState {
         let mut pnt2_1: Point2<f32> = gp1.into();
         pnt2_1.y += 15.0;
         pnt2_1.x += 20.0;
-        graphics::draw(ctx, &text, (pnt2,))?;
-        graphics::draw(ctx, &little_text, (pnt2_1,))?;
+        let title = if self.cleared.is_some() { "CLEARED" } else { "GAME OVER" };
+        let text = self.assets.text(title, graphics::Scale{x:100.0, y:100.0});
+        graphics::draw(ctx, text, (pnt2,))?;
+        let little_text = self.assets.text("PRESS R TO RESTART OR ESCAPE TO EXIT", graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, little_text, (pnt2_1,))?;
+        if let Some(stars) = self.cleared {
+            pnt2_1.y += 30.0;
+            let mut line = format!("STARS: {}", stars_text(stars));
+            if self.next_level().is_some() {
+                line.push_str(" - PRESS ENTER FOR THE NEXT LEVEL");
+            }
+            let stars_line = self.assets.text(&line, graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, stars_line, (pnt2_1,))?;
+        }
+        pnt2_1.y += 30.0;
+        let scores_text = self.assets.text(&self.best_line(), graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, scores_text, (pnt2_1,))?;
         Ok(())
     }
 
     fn draw_start(&mut self, ctx: &mut Context) -> GameResult{
-        let text = graphics::Text::new("PRESS SPACE TO START THE GAME")
-                                        .set_font( graphics::Font::new(ctx,"/Terminus.ttf").unwrap(),
-                                                   graphics::Scale{x:30.0, y:30.0} ).to_owned();
         let gp: GridPosition = (14 as i16, 0 as i16).into();
         let mut pnt2: Point2<f32> = gp.into();
         pnt2.y += 5.0;
-        graphics::draw(ctx, &text, (pnt2,))?;
+        let gp1: GridPosition = (10 as i16, 8 as i16).into();
+        let mut pnt2_1: Point2<f32> = gp1.into();
+        pnt2_1.y += 15.0;
+        pnt2_1.x += 20.0;
+        let text = self.assets.text("PRESS SPACE TO START THE GAME", graphics::Scale{x:30.0, y:30.0});
+        graphics::draw(ctx, text, (pnt2,))?;
+        let controls = format!("CONTROLS: {} - PRESS C TO CHANGE", self.snake.controls.to_string().to_uppercase());
+        let controls_text = self.assets.text(&controls, graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, controls_text, (pnt2_1,))?;
+        pnt2_1.y += 30.0;
+        let mice = if self.foods.moves().is_some() { "MOVING" } else { "STILL" };
+        let mice_text = self.assets.text(&format!("MICE: {} - PRESS N TO CHANGE", mice), graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, mice_text, (pnt2_1,))?;
+        pnt2_1.y += 30.0;
+        let percent = |volume: f32| (volume * 100.0).round() as u32;
+        let sound = if self.settings.muted {
+            "SOUND: MUTED - PRESS M TO CHANGE".to_string()
+        } else {
+            format!("VOLUME: {}% (- =)  EFFECTS: {}% ([ ])  MUSIC: {}% (, .)", percent(self.settings.master_volume),
+                    percent(self.settings.sfx_volume), percent(self.settings.music_volume))
+        };
+        let sound_text = self.assets.text(&sound, graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, sound_text, (pnt2_1,))?;
+        pnt2_1.y += 30.0;
+        let motion = if self.settings.reduced_motion { "REDUCED" } else { "FULL" };
+        let motion_text = self.assets.text(&format!("MOTION: {} - PRESS O TO CHANGE", motion), graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, motion_text, (pnt2_1,))?;
+        pnt2_1.y += 30.0;
+        let mode = match self.level {
+            _ if self.playtest.is_some() => {
+                let level = self.playtest.as_ref().unwrap();
+                format!("PLAYTEST: {} - {} - PRESS ESCAPE TO EDIT", level.name.to_uppercase(), level.goal())
+            }
+            Some(index) => {
+                let level = &self.campaign.levels()[index];
+                format!("LEVEL {}: {} - {} - PRESS G TO LEAVE", index + 1, level.name.to_uppercase(), level.goal())
+            }
+            None => match self.puzzle {
+                Some(index) => {
+                    let puzzle = &self.puzzles[index];
+                    format!("PUZZLE {}: {} - {} - PRESS P FOR THE NEXT", index + 1, puzzle.name.to_uppercase(), puzzle.goal())
+                }
+                None => format!("MODE: {} - PRESS G TO CHANGE", self.mode.to_string().to_uppercase()),
+            },
+        };
+        let mode_text = self.assets.text(&mode, graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, mode_text, (pnt2_1,))?;
+        pnt2_1.y += 30.0;
+        if !self.fixed_board() {
+            let arena = format!("ARENA: {} - PRESS B TO CHANGE", self.settings.arena.to_string().to_uppercase());
+            let arena_text = self.assets.text(&arena, graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, arena_text, (pnt2_1,))?;
+            pnt2_1.y += 30.0;
+            let edges = format!("EDGES: {} - PRESS T TO CHANGE", self.settings.topology.to_string().to_uppercase());
+            let edges_text = self.assets.text(&edges, graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, edges_text, (pnt2_1,))?;
+            pnt2_1.y += 30.0;
+            let rivals = format!("RIVALS: {} {} - PRESS V AND I TO CHANGE", self.settings.rivals,
+                                 self.settings.rival_tier.to_string().to_uppercase());
+            let rivals_text = self.assets.text(&rivals, graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, rivals_text, (pnt2_1,))?;
+            pnt2_1.y += 30.0;
+        }
+        if let Some((name, _)) = &self.bot {
+            let bot_text = self.assets.text(&format!("BOT: {}", name), graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, bot_text, (pnt2_1,))?;
+            pnt2_1.y += 30.0;
+        }
+        let scores_text = self.assets.text(&self.best_line(), graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, scores_text, (pnt2_1,))?;
+        if !self.campaign.levels().is_empty() && self.playtest.is_none() {
+            pnt2_1.y += 30.0;
+            let campaign_text = self.assets.text("PRESS L TO PICK A CAMPAIGN LEVEL, E TO EDIT YOUR OWN", graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, campaign_text, (pnt2_1,))?;
+        }
+        if !self.puzzles.is_empty() && self.puzzle.is_none() && self.playtest.is_none() {
+            pnt2_1.y += 30.0;
+            let puzzle_text = self.assets.text("PRESS P FOR PUZZLES", graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, puzzle_text, (pnt2_1,))?;
+        }
+        if self.has_save {
+            pnt2_1.y += 30.0;
+            let continue_text = self.assets.text("PRESS ENTER TO CONTINUE THE SAVED GAME", graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, continue_text, (pnt2_1,))?;
+        }
         Ok(())
     }
 
-    fn is_ready_for_tick(&mut self) -> bool {
-        Instant::now() - self.last_update >= Duration::from_millis(MILLIS_PER_UPDATE)
+    /// Every level with its best result, locked ones greyed out, and a
+    /// marker on the one under the cursor.
+    fn draw_level_select(&mut self, ctx: &mut Context, cursor: usize) -> GameResult {
+        let rect = graphics::Rect::new(0.0, 0.0, SCREEN_SIZE.0, SCREEN_SIZE.1);
+        let shade = graphics::Mesh::new_rectangle(ctx, graphics::DrawMode::fill(), rect, graphics::Color::new(0.0, 0.0, 0.0, 0.8))?;
+        graphics::draw(ctx, &shade, graphics::DrawParam::default())?;
+
+        let title = format!("PICK A LEVEL - {} STARS EARNED", self.progress.stars());
+        let gp: GridPosition = (3 as i16, 1 as i16).into();
+        let pnt2: Point2<f32> = gp.into();
+        let text = self.assets.text(&title, graphics::Scale{x:30.0, y:30.0});
+        graphics::draw(ctx, text, (pnt2,))?;
+
+        for (i, level) in self.campaign.levels().iter().enumerate() {
+            let column = (i / LEVELS_PER_COLUMN) as i16;
+            let row = (i % LEVELS_PER_COLUMN) as i16;
+            let gp: GridPosition = (3 + column * 13, 3 + row).into();
+            let pnt2: Point2<f32> = gp.into();
+            let result = match self.progress.best(i) {
+                Some(clear) => stars_text(clear.stars),
+                None if self.progress.is_unlocked(i) => "---".to_string(),
+                None => "LOCKED".to_string(),
+            };
+            let marker = if i == cursor { ">" } else { " " };
+            let line = format!("{} {:02} {} {}", marker, i + 1, level.name.to_uppercase(), result);
+            let color = if self.progress.is_unlocked(i) { graphics::WHITE } else { graphics::Color::new(0.5, 0.5, 0.5, 1.0) };
+            let text = self.assets.text(&line, graphics::Scale{x:20.0, y:20.0});
+            graphics::draw(ctx, text, graphics::DrawParam::default().dest(pnt2).color(color))?;
+        }
+
+        let gp: GridPosition = (3 as i16, 3 + LEVELS_PER_COLUMN as i16 + 1).into();
+        let pnt2: Point2<f32> = gp.into();
+        let help = self.assets.text("ARROWS TO MOVE, ENTER TO PLAY, L TO GO BACK", graphics::Scale{x:20.0, y:20.0});
+        graphics::draw(ctx, help, (pnt2,))?;
+        Ok(())
+    }
+
+    /// The high score table of the current mode, marking a new entry, or the
+    /// best clear of the current level.
+    fn best_line(&self) -> String {
+        if let Some(index) = self.puzzle {
+            return format!("PAR: {} MOVES", self.puzzles[index].par);
+        }
+        if let Some(index) = self.level {
+            return match self.progress.best(index) {
+                Some(clear) => format!("BEST: {} {} POINTS IN {:.1}s", stars_text(clear.stars), clear.points, clear.seconds),
+                None => "BEST: -".to_string(),
+            };
+        }
+        let table = self.scores.table(self.mode);
+        if table.is_empty() {
+            return "BEST: -".to_string();
+        }
+        let entries: Vec<String> = table.iter().enumerate().map(|(i, points)| {
+            if self.gameover && self.high_score == Some(i) { format!("[{}]", points) } else { points.to_string() }
+        }).collect();
+        format!("BEST: {}", entries.join("  "))
+    }
+
+    /// How far the game is between the last tick and the next one, from 0 to 1.
+    /// A stopped snake is drawn where it is, without any interpolation.
+    fn interpolation(&self, ctx: &mut Context) -> f32 {
+        if self.gameover || !self.start {
+            return 1.0;
+        }
+        let remaining = timer::duration_to_f64(timer::remaining_update_time(ctx)) as f32;
+        (remaining * self.ticks_per_second() as f32).min(1.0)
+    }
+
+    /// The current speed, raised for a while after a golden mouse and
+    /// halved while time is slowed down.
+    fn ticks_per_second(&self) -> u32 {
+        let speed = if self.burst > 0 { self.speed + BURST_SPEEDUP } else { self.speed };
+        self.effects.ticks_per_second(speed)
+    }
+
+    fn change_settings(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::M => self.settings.muted = !self.settings.muted,
+            KeyCode::Minus => self.settings.master_volume = (self.settings.master_volume - VOLUME_STEP).max(0.0),
+            KeyCode::Equals => self.settings.master_volume = (self.settings.master_volume + VOLUME_STEP).min(1.0),
+            KeyCode::LBracket => self.settings.sfx_volume = (self.settings.sfx_volume - VOLUME_STEP).max(0.0),
+            KeyCode::RBracket => self.settings.sfx_volume = (self.settings.sfx_volume + VOLUME_STEP).min(1.0),
+            KeyCode::Comma => self.settings.music_volume = (self.settings.music_volume - VOLUME_STEP).max(0.0),
+            KeyCode::Period => self.settings.music_volume = (self.settings.music_volume + VOLUME_STEP).min(1.0),
+            _ => return,
+        }
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+    }
+
+    fn toggle_moving_mice(&mut self, ctx: &mut Context) {
+        self.settings.moving_mice = !self.settings.moving_mice;
+        self.foods.set_moves(self.settings.mouse_moves());
+        self.replay.mice = self.settings.mouse_moves();
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+    }
+
+    fn toggle_reduced_motion(&mut self, ctx: &mut Context) {
+        self.settings.reduced_motion = !self.settings.reduced_motion;
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
     }
 
-    fn restart_game(&mut self, ctx: &mut Context) {
-        let snake_pos = (GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into();
-        let food_pos = GridPosition::random(1, 1, GRID_SIZE.0 - 1, GRID_SIZE.1 - 1);
+    fn change_mode(&mut self, ctx: &mut Context) {
+        if self.level.take().is_some() || self.puzzle.take().is_some() {
+            self.restart_game();
+            return;
+        }
+        self.settings.mode = self.mode.next();
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+        self.restart_game();
+    }
+
+    /// Steps through the puzzles, and out of them after the last one.
+    fn next_puzzle(&mut self) {
+        self.level = None;
+        self.puzzle = match self.puzzle {
+            Some(index) if index + 1 < self.puzzles.len() => Some(index + 1),
+            Some(_) => None,
+            None => Some(0),
+        };
+        self.restart_game();
+    }
 
-        self.snake = Snake::new(snake_pos, ctx).unwrap();
-        self.food = Food::new(food_pos, ctx).unwrap();
-        self.walls = Wall::new(ctx).unwrap();
+    fn change_arena(&mut self, ctx: &mut Context) {
+        self.settings.arena = self.settings.arena.next();
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+        self.restart_game();
+    }
+
+    fn change_topology(&mut self, ctx: &mut Context) {
+        self.settings.topology = self.settings.topology.next();
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+        self.restart_game();
+    }
+
+    fn change_rivals(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        match keycode {
+            KeyCode::V => self.settings.rivals = (self.settings.rivals + 1) % (MAX_RIVALS + 1),
+            _ => self.settings.rival_tier = self.settings.rival_tier.next(),
+        }
+        if let Err(e) = self.settings.save(ctx) {
+            eprintln!("Could not save settings: {}", e);
+        }
+        self.restart_game();
+    }
+
+    /// Whether the board is laid out by hand, as levels and puzzles are.
+    fn fixed_board(&self) -> bool {
+        self.level.is_some() || self.puzzle.is_some() || self.playtest.is_some()
+    }
+
+    /// The arena new walls are drawn by. Levels bring their own walls.
+    fn arena(&self) -> ArenaKind {
+        if self.fixed_board() {
+            ArenaKind::Plain
+        } else {
+            self.settings.arena
+        }
+    }
+
+    /// How the edges of the board are joined. Levels are made for the holes
+    /// in their walls.
+    fn topology(&self) -> Topology {
+        if self.fixed_board() {
+            Topology::Portals
+        } else {
+            self.settings.topology
+        }
+    }
+
+    /// The rules of the level being played or tried out, of the puzzle, or
+    /// of the mode outside the campaign.
+    fn current_rules(&self) -> Box<dyn GameMode> {
+        if let Some(level) = &self.playtest {
+            return Box::new(level.clone());
+        }
+        if let Some(index) = self.puzzle {
+            return Box::new(self.puzzles[index].clone());
+        }
+        match self.level {
+            Some(index) => Box::new(self.campaign.levels()[index].clone()),
+            None => self.mode.rules(),
+        }
+    }
+
+    fn next_level(&self) -> Option<usize> {
+        self.level.map(|index| index + 1).filter(|index| *index < self.campaign.levels().len())
+    }
+
+    fn select_level(&mut self, keycode: KeyCode, cursor: usize) {
+        let last = self.campaign.levels().len() - 1;
+        match keycode {
+            KeyCode::Up => self.select = Some(cursor.saturating_sub(1)),
+            KeyCode::Down => self.select = Some((cursor + 1).min(last)),
+            KeyCode::Left => self.select = Some(cursor.saturating_sub(LEVELS_PER_COLUMN)),
+            KeyCode::Right => self.select = Some((cursor + LEVELS_PER_COLUMN).min(last)),
+            KeyCode::Return | KeyCode::Space if self.progress.is_unlocked(cursor) => {
+                self.level = Some(cursor);
+                self.puzzle = None;
+                self.select = None;
+                self.restart_game();
+            }
+            KeyCode::L | KeyCode::Escape => self.select = None,
+            _ => {},
+        }
+    }
+
+    fn editing(&self) -> bool {
+        self.editor.is_some() && self.playtest.is_none()
+    }
+
+    fn edit(&mut self, ctx: &mut Context, keycode: KeyCode) {
+        let editor = match self.editor.as_mut() {
+            Some(editor) => editor,
+            None => return,
+        };
+        match keycode {
+            KeyCode::Key1 | KeyCode::Key2 | KeyCode::Key3 | KeyCode::Key4 => {
+                let i = match keycode { KeyCode::Key1 => 0, KeyCode::Key2 => 1, KeyCode::Key3 => 2, _ => 3 };
+                editor.tool = Tool::ALL[i];
+            }
+            KeyCode::Z => editor.undo(),
+            KeyCode::Y => editor.redo(),
+            KeyCode::S => {
+                if let Err(e) = editor.save(ctx) {
+                    eprintln!("Could not save the custom level: {}", e);
+                }
+            }
+            KeyCode::P => {
+                self.playtest = Some(editor.level().clone());
+                self.level = None;
+                self.puzzle = None;
+                self.restart_game();
+                self.start = true;
+            }
+            KeyCode::Escape => self.editor = None,
+            _ => {},
+        }
+    }
+
+    /// The cell under the mouse pointer, whatever size the window is.
+    fn cell_at(&self, ctx: &Context, x: f32, y: f32) -> GridPosition {
+        let (width, height) = graphics::drawable_size(ctx);
+        let x = x * SCREEN_SIZE.0 / width / GRID_CELL_SIZE.0 as f32;
+        let y = y * SCREEN_SIZE.1 / height / GRID_CELL_SIZE.1 as f32;
+        GridPosition::new(x.floor() as i16, y.floor() as i16)
+    }
+
+    fn dump_board(&self, ctx: &mut Context) {
+        let mut board = Board::new(GRID_SIZE, self.walls.clone(), self.snake.clone(), self.foods.items().to_vec());
+        board.tiles = self.grid.tiles().clone();
+        if let Err(e) = board.dump(ctx) {
+            eprintln!("Could not write the board: {}", e);
+        }
+    }
+
+    fn step(&mut self, ctx: &mut Context) {
+
+        self.elapsed += 1.0 / self.ticks_per_second() as f32;
+        self.snake.ghost = self.effects.ghost > 0;
+        self.snake.breaks_walls = self.effects.wall_breakers > 0;
+        self.rules.prepare(&mut self.snake);
+        self.steer_by_bot();
+        self.snake.update(&mut self.grid);
+        self.tick += 1;
+        self.burst = self.burst.saturating_sub(1);
+        self.effects.tick();
+
+        if self.snake.wrapped {
+            self.audio.play(Sound::Wrap, &self.settings);
+        }
+
+        if let Some(ate) = self.snake.ate {
+            match ate {
+                Ate::Food(kind) => {
+                    let head = self.snake.head_pos();
+                    self.foods.take(head, &mut self.grid);
+                    if self.settings.particles() {
+                        self.particles.emit(Emitter::puff(), head);
+                    }
+                    let points = self.points + self.rules.points(kind);
+                    if kind == FoodKind::Golden {
+                        self.burst = BURST_TICKS;
+                    }
+                    self.audio.play(Sound::Eat, &self.settings);
+
+                    if points / SPEEDUP_EVERY > self.points / SPEEDUP_EVERY && self.speed < MAX_UPDATES_PER_SECOND {
+                        self.speed = (self.speed + SPEEDUP_STEP).min(MAX_UPDATES_PER_SECOND);
+                        self.audio.play(Sound::SpeedUp, &self.settings);
+                    }
+                    self.points = points;
+                }
+                Ate::PowerUp(kind) => {
+                    let head = self.snake.head_pos();
+                    self.powerups.take(head);
+                    if self.settings.particles() {
+                        self.particles.emit(Emitter::puff(), head);
+                    }
+                    self.effects.pick_up(kind);
+                    if kind == PowerUpKind::Scissors {
+                        self.snake.shrink(self.snake.length() / 2, &mut self.grid);
+                    }
+                    self.audio.play(Sound::SpeedUp, &self.settings);
+                }
+                Ate::BrokenWall => {
+                    let head = self.snake.head_pos();
+                    self.walls.remove(head);
+                    self.effects.wall_breakers -= 1;
+                    self.crash_effects(ate);
+                    self.audio.play(Sound::WallDeath, &self.settings);
+                }
+                Ate::Itself | Ate::Wall | Ate::OtherSnake => {
+                    let sound = match ate {
+                        Ate::Wall => Sound::WallDeath,
+                        _ => Sound::SelfDeath,
+                    };
+                    self.audio.play(sound, &self.settings);
+                    self.crash_effects(ate);
+                }
+            }
+        }
+
+        self.move_rivals();
+
+        if let Some(group) = locks::update(&self.snake, &mut self.walls, &mut self.grid) {
+            self.keys.push(group);
+            self.audio.play(Sound::SpeedUp, &self.settings);
+        }
+
+        if self.rules.is_won(self.points, self.snake.length()) {
+            self.end_game(ctx, true);
+            return;
+        }
+        let out_of_moves = self.rules.move_limit().map_or(false, |limit| self.tick >= limit);
+        if self.rules.is_over(self.snake.ate, self.elapsed) || out_of_moves {
+            self.end_game(ctx, false);
+            return;
+        }
+
+        for food in self.foods.update(self.tick, self.snake.head_pos(), &mut self.rng, &mut self.grid) {
+            if self.settings.particles() {
+                self.particles.emit(Emitter::puff(), food.pos);
+            }
+        }
+        self.rules.update(self.tick, &mut self.walls, &mut self.grid);
+        self.foods.fill_in(&mut self.rng, &mut self.grid, self.rules.spawn_area(self.tick, GRID_SIZE));
+        if self.rules.power_ups() {
+            self.powerups.update(self.tick, &mut self.rng, &mut self.grid);
+        }
+    }
+
+    /// Lets the bot pick the snake's next move, recorded like a key press so
+    /// the game plays back without it.
+    fn steer_by_bot(&mut self) {
+        let bot = match self.bot.as_mut() {
+            Some((_, bot)) => bot,
+            None => return,
+        };
+        let view = View {
+            snake: &self.snake,
+            others: self.rivals.iter().map(|rival| &rival.snake).collect(),
+            grid: &self.grid,
+            foods: self.foods.items(),
+            tick: self.tick,
+        };
+        if self.tick == 0 {
+            bot.start(&view);
+        }
+        // Random numbers of its own, so the board does not change with it.
+        let dir = bot.choose(&view, &mut Pcg32::seed_from_u64(self.tick));
+        let input = Input::Absolute(dir);
+        if self.snake.apply_input(input) {
+            self.replay.record(self.tick, input);
+        }
+    }
+
+    /// Moves every rival a cell, after the player. Rivals eat the food and
+    /// take the power-ups they run into, for no points and no effect, and one
+    /// that crashes is taken off the board.
+    fn move_rivals(&mut self) {
+        for i in 0..self.rivals.len() {
+            let (before, rest) = self.rivals.split_at_mut(i);
+            let (rival, after) = rest.split_first_mut().unwrap();
+            let others = iter::once(&self.snake)
+                .chain(before.iter().chain(after.iter()).map(|rival| &rival.snake))
+                .collect();
+            let view = View {
+                snake: &rival.snake,
+                others,
+                grid: &self.grid,
+                foods: self.foods.items(),
+                tick: self.tick,
+            };
+            let dir = rival.bot.choose(&view, &mut self.rng);
+
+            let snake = &mut self.rivals[i].snake;
+            snake.steer(dir);
+            snake.update(&mut self.grid);
+            let head = snake.head_pos();
+            match snake.ate {
+                Some(Ate::Food(_)) => {
+                    self.foods.take(head, &mut self.grid);
+                }
+                Some(Ate::PowerUp(_)) => {
+                    self.powerups.take(head);
+                }
+                Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake) => {
+                    if self.settings.particles() {
+                        self.particles.emit(Emitter::blood(), head);
+                    }
+                }
+                _ => {},
+            }
+        }
+
+        let grid = &mut self.grid;
+        self.rivals.retain(|rival| {
+            let crashed = matches!(rival.snake.ate, Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake));
+            if crashed {
+                grid.clear_snake(&rival.snake);
+            }
+            !crashed
+        });
+    }
+
+    /// Stops the game. A won level goes into the campaign progress, any
+    /// other game into the high scores of its mode.
+    fn end_game(&mut self, ctx: &mut Context, won: bool) {
+        self.gameover = true;
+        if let Some((_, bot)) = self.bot.as_mut() {
+            bot.end(&View {
+                snake: &self.snake,
+                others: self.rivals.iter().map(|rival| &rival.snake).collect(),
+                grid: &self.grid,
+                foods: self.foods.items(),
+                tick: self.tick,
+            });
+        }
+        if let Err(e) = self.replay.save(ctx) {
+            eprintln!("Could not save replay: {}", e);
+        }
+        if let Some(level) = &self.playtest {
+            if won {
+                self.cleared = Some(level.stars(self.elapsed));
+            }
+            return;
+        }
+        if let Some(index) = self.puzzle {
+            if won {
+                self.cleared = Some(self.puzzles[index].stars(self.tick));
+            }
+            return;
+        }
+        match self.level {
+            Some(index) => {
+                if !won {
+                    return;
+                }
+                let stars = self.campaign.levels()[index].stars(self.elapsed);
+                self.cleared = Some(stars);
+                let clear = Clear { stars, points: self.points, seconds: self.elapsed };
+                if self.progress.record(index, clear) {
+                    if let Err(e) = self.progress.save(ctx) {
+                        eprintln!("Could not save campaign progress: {}", e);
+                    }
+                }
+            }
+            None => {
+                self.high_score = self.scores.submit(self.mode, self.points);
+                if self.high_score.is_some() {
+                    if let Err(e) = self.scores.save(ctx) {
+                        eprintln!("Could not save high scores: {}", e);
+                    }
+                }
+            }
+        }
+    }
+
+    fn crash_effects(&mut self, ate: Ate) {
+        let head = self.snake.head_pos();
+        if self.settings.particles() {
+            match ate {
+                Ate::Wall | Ate::BrokenWall => self.particles.emit(Emitter::debris(), head),
+                _ => self.particles.emit(Emitter::blood(), head),
+            }
+        }
+        if let Ate::Wall | Ate::BrokenWall = ate {
+            if self.settings.shake() {
+                self.particles.shake(SHAKE_STRENGTH, SHAKE_SECONDS);
+            }
+        }
+    }
+
+    /// Ends a game the rules never end by themselves, as the player leaves
+    /// it, so its points still count.
+    fn leave_game(&mut self, ctx: &mut Context) {
+        if self.start && self.in_progress() && self.rules.endless() {
+            self.end_game(ctx, false);
+        }
+    }
+
+    /// A game that has made at least one move and is not over yet.
+    fn in_progress(&self) -> bool {
+        self.tick > 0 && !self.gameover
+    }
+
+    fn save_game(&mut self, ctx: &mut Context) {
+        if !self.in_progress() || self.playtest.is_some() || self.puzzle.is_some() {
+            return;
+        }
+        let saved = SavedGame {
+            snake: self.snake.clone(),
+            foods: self.foods.clone(),
+            powerups: self.powerups.clone(),
+            effects: self.effects,
+            walls: self.walls.clone(),
+            tiles: self.grid.tiles().clone(),
+            keys: self.keys.clone(),
+            rivals: self.rivals.clone(),
+            points: self.points,
+            tick: self.tick,
+            rng: self.rng.clone(),
+            speed: self.speed,
+            burst: self.burst,
+            mode: self.mode,
+            level: self.level,
+            elapsed: self.elapsed,
+            replay: self.replay.clone(),
+        };
+        if let Err(e) = saved.save(ctx) {
+            eprintln!("Could not save the game: {}", e);
+        }
+    }
+
+    /// Loads the saved game and waits on the start screen until the player
+    /// is ready. The save is removed so it can only be continued once.
+    fn continue_game(&mut self, ctx: &mut Context) {
+        match SavedGame::load(ctx) {
+            Ok(saved) => {
+                self.restart_game();
+                self.snake = saved.snake;
+                self.foods = saved.foods;
+                self.powerups = saved.powerups;
+                self.effects = saved.effects;
+                self.walls = saved.walls;
+                self.keys = saved.keys;
+                self.rivals = saved.rivals;
+                self.points = saved.points;
+                self.tick = saved.tick;
+                self.rng = saved.rng;
+                self.speed = saved.speed;
+                self.burst = saved.burst;
+                self.mode = saved.mode;
+                self.level = saved.level.filter(|index| *index < self.campaign.levels().len());
+                self.rules = self.current_rules();
+                self.elapsed = saved.elapsed;
+                self.replay = saved.replay;
+                self.grid = Occupancy::build(GRID_SIZE, &self.walls, &self.snake, self.foods.items())
+                    .with_topology(self.replay.topology)
+                    .with_tiles(saved.tiles);
+                for rival in self.rivals.iter() {
+                    self.grid.place_snake(&rival.snake);
+                }
+                self.powerups.mark(&mut self.grid);
+            }
+            Err(e) => eprintln!("Could not load the saved game: {}", e),
+        }
+        SavedGame::delete(ctx);
+        self.has_save = false;
+    }
+
+    fn restart_game(&mut self) {
+        let seed = rand::random::<u64>();
+        let controls = self.snake.controls;
+        self.rng = Pcg32::seed_from_u64(seed);
+
+        self.mode = self.settings.mode;
+        self.rules = self.current_rules();
+        self.snake = self.rules.snake();
+        self.snake.controls = controls;
+        let (arena, topology) = (self.arena(), self.topology());
+        self.walls = self.rules.walls(&mut self.rng, arena, topology);
+        self.keys.clear();
+        // Puzzles are worked out for mice that sit still.
+        let mice = if self.puzzle.is_some() { None } else { self.settings.mouse_moves() };
+        self.foods = FoodManager::new(self.rules.food_items(), mice);
+        self.grid = Occupancy::build(GRID_SIZE, &self.walls, &self.snake, self.foods.items())
+            .with_topology(topology)
+            .with_tiles(self.rules.tiles());
+        // Levels and puzzles are made for the player alone.
+        let rivals = if self.fixed_board() { 0 } else { self.rules.rivals(self.settings.rivals) };
+        self.rivals = bots::spawn(rivals, self.settings.rival_tier, &mut self.grid);
+        for food in self.rules.foods() {
+            self.foods.place(food, &mut self.grid);
+        }
+        self.foods.fill_in(&mut self.rng, &mut self.grid, self.rules.spawn_area(0, GRID_SIZE));
+        self.powerups = PowerUps::new();
+        self.effects = Effects::default();
         self.gameover = false;
         self.start = false;
         self.points = 0;
-        self.last_update = Instant::now();
+        self.tick = 0;
+        self.replay = Replay::new(seed, controls, mice);
+        self.replay.mode = self.mode;
+        self.replay.level = self.level;
+        self.replay.puzzle = self.puzzle;
+        self.replay.arena = arena;
+        self.replay.topology = topology;
+        self.replay.rivals = rivals;
+        self.replay.rival_tier = self.settings.rival_tier;
+        self.speed = UPDATES_PER_SECOND;
+        self.burst = 0;
+        self.elapsed = 0.0;
+        self.high_score = None;
+        self.cleared = None;
+        self.particles.clear();
     }
 
 }
 
 impl event::EventHandler for GameState {
 
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
-
-        if self.is_ready_for_tick() {
-            if !self.gameover && self.start {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
 
-                self.snake.update(&self.food, &self.walls);
+        self.audio.update_music(&self.settings);
+        self.particles.update(timer::duration_to_f64(timer::delta(ctx)) as f32);
 
-                if let Some(ate) = self.snake.ate {
-                    match ate {
-                        Ate::Food => {
-                            let new_food_pos = GridPosition::random(3, 3, GRID_SIZE.0 - 1, GRID_SIZE.1 - 1);
-                            self.food.pos = new_food_pos;
-                            self.points += 1;
-                        }
-                        Ate::Itself | Ate::Wall => {
-                            self.gameover = true;
-                        }
-                    }
-                }
+        while timer::check_update_time(ctx, self.ticks_per_second()) {
+            // A turn-based snake waits for a key before every move.
+            let waiting = self.rules.turn_based() && self.snake.next_dir.is_none() && self.bot.is_none();
+            if !self.gameover && self.start && !waiting {
+                self.step(ctx);
             }
-
-            self.last_update = Instant::now();
         }
 
         Ok(())
@@ -161,20 +977,37 @@ impl event::EventHandler for GameState {
 
         graphics::clear(ctx, [0.0, 0.0, 0.0, 0.0].into());
 
+        let (dx, dy) = self.particles.shake_offset();
+        graphics::set_screen_coordinates(ctx, graphics::Rect::new(dx, dy, SCREEN_SIZE.0, SCREEN_SIZE.1))?;
+
         self.draw_floor(ctx)?;
-        self.walls.draw(ctx)?;
-        self.snake.draw(ctx, self.gameover)?;
-        self.food.draw(ctx)?;
+        if let Some(editor) = self.editor.as_ref().filter(|_| self.playtest.is_none()) {
+            editor.draw(ctx, &mut self.assets)?;
+            graphics::present(ctx)?;
+            return Ok(());
+        }
+        self.grid.tiles().draw(ctx, &self.assets)?;
+        self.walls.draw(ctx, &self.assets)?;
+        let alpha = self.interpolation(ctx);
+        self.snake.draw(ctx, &self.assets, alpha, self.gameover, &self.grid)?;
+        for rival in self.rivals.iter() {
+            rival.snake.draw(ctx, &self.assets, alpha, false, &self.grid)?;
+        }
+        self.foods.draw(ctx, &self.assets)?;
+        self.powerups.draw(ctx, &self.assets)?;
+        self.particles.draw(ctx)?;
         self.draw_score(ctx)?;
+        self.draw_effects(ctx)?;
         if self.gameover{
             self.draw_game_over(ctx)?;
         }
-        if !self.start{
+        if let Some(cursor) = self.select {
+            self.draw_level_select(ctx, cursor)?;
+        } else if !self.start{
             self.draw_start(ctx)?;
         }
 
         graphics::present(ctx)?;
-        ggez::timer::yield_now();
         Ok(())
     }
 
@@ -186,38 +1019,186 @@ impl event::EventHandler for GameState {
         _repeat: bool,
     ) {
 
+        self.change_settings(_ctx, keycode);
+        if keycode == KeyCode::F12 {
+            self.dump_board(_ctx);
+        }
+
+        if let Some(cursor) = self.select {
+            self.select_level(keycode, cursor);
+            return;
+        }
+        if self.editing() {
+            self.edit(_ctx, keycode);
+            return;
+        }
+        if self.playtest.is_some() && keycode == KeyCode::Escape {
+            self.playtest = None;
+            self.restart_game();
+            return;
+        }
+
         if self.start{
 
-            if let Some(dir) = Direction::from_keycode(keycode) {
+            let mut input = self.snake.controls.input_from_keycode(keycode);
+            if keycode == KeyCode::Space && self.rules.turn_based() {
+                input = Some(Input::Relative(Turn::Straight));
+            }
+            if let Some(input) = input {
 
-                if self.snake.dir != self.snake.last_update_dir && dir.inverse() != self.snake.dir {
-                    self.snake.next_dir = Some(dir);
-                } else if dir.inverse() != self.snake.last_update_dir {
-                    self.snake.next_dir = Some(dir);
+                if !self.gameover && self.bot.is_none() && self.snake.apply_input(input) {
+                    self.replay.record(self.tick, input);
                 }
             }
 
             if keycode == KeyCode::Escape {
+                self.leave_game(_ctx);
+                self.save_game(_ctx);
                 event::quit(_ctx);
             }else if keycode == KeyCode::R {
-                self.restart_game(_ctx);
+                self.leave_game(_ctx);
+                self.restart_game();
+            }else if keycode == KeyCode::Return && self.cleared.is_some() {
+                if let Some(next) = self.next_level() {
+                    self.level = Some(next);
+                    self.restart_game();
+                }
             }
 
         }else{
              if keycode == KeyCode::Escape {
+                self.save_game(_ctx);
                 event::quit(_ctx);
             }else if keycode == KeyCode::Space {
+                if self.has_save && self.tick == 0 {
+                    SavedGame::delete(_ctx);
+                    self.has_save = false;
+                }
                 self.start = true;
+                self.audio.play(Sound::Start, &self.settings);
+            }else if keycode == KeyCode::Return && self.has_save {
+                self.continue_game(_ctx);
+            }else if keycode == KeyCode::C && self.tick == 0 {
+                self.snake.controls = self.snake.controls.toggle();
+                self.replay.controls = self.snake.controls;
+            }else if keycode == KeyCode::N && self.tick == 0 {
+                self.toggle_moving_mice(_ctx);
+            }else if keycode == KeyCode::O && self.tick == 0 {
+                self.toggle_reduced_motion(_ctx);
+            }else if keycode == KeyCode::G && self.tick == 0 && self.playtest.is_none() {
+                self.change_mode(_ctx);
+            }else if keycode == KeyCode::B && self.tick == 0 && !self.fixed_board() {
+                self.change_arena(_ctx);
+            }else if keycode == KeyCode::T && self.tick == 0 && !self.fixed_board() {
+                self.change_topology(_ctx);
+            }else if (keycode == KeyCode::V || keycode == KeyCode::I) && self.tick == 0 && !self.fixed_board() {
+                self.change_rivals(_ctx, keycode);
+            }else if keycode == KeyCode::P && self.tick == 0 && self.playtest.is_none() && !self.puzzles.is_empty() {
+                self.next_puzzle();
+            }else if keycode == KeyCode::L && self.tick == 0 && self.playtest.is_none() && !self.campaign.levels().is_empty() {
+                self.select = Some(self.level.unwrap_or(0));
+            }else if keycode == KeyCode::E && self.tick == 0 && self.playtest.is_none() {
+                self.editor = Some(Editor::load(_ctx));
+            }
+        }
+    }
+
+    fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        if !self.editing() {
+            return;
+        }
+        let pos = self.cell_at(ctx, x, y);
+        let editor = self.editor.as_mut().unwrap();
+        let tool = match button {
+            MouseButton::Left => editor.tool,
+            MouseButton::Right => Tool::Hole,
+            _ => return,
+        };
+        editor.begin_stroke();
+        editor.paint(pos, tool);
+        self.painting = Some((tool, pos));
+    }
+
+    fn mouse_motion_event(&mut self, ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        let pos = self.cell_at(ctx, x, y);
+        if let (Some((tool, last)), Some(editor)) = (self.painting, self.editor.as_mut()) {
+            // The snake is placed with single clicks.
+            if pos != last && tool != Tool::Snake {
+                editor.paint(pos, tool);
+                self.painting = Some((tool, pos));
+            }
+        }
+    }
+
+    fn mouse_button_up_event(&mut self, _ctx: &mut Context, _button: MouseButton, _x: f32, _y: f32) {
+        if let Some(editor) = self.editor.as_mut() {
+            editor.end_stroke();
+        }
+        self.painting = None;
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> bool {
+        self.leave_game(ctx);
+        self.save_game(ctx);
+        false
+    }
+}
+
+fn stars_text(stars: u8) -> String {
+    format!("{:-<3}", "*".repeat(stars as usize))
+}
+
+/// The bot given by `--bot-cmd "python mybot.py"` or by `--bot-url
+/// http://localhost:8000`, with as long to answer as `--bot-timeout MS`
+/// allows.
+fn bot_from_args() -> Result<Option<NamedBot>, String> {
+    let mut args = env::args().skip(1);
+    let mut command = None;
+    let mut url: Option<Url> = None;
+    let mut timeout = BOT_MOVE_TIMEOUT_MS;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bot-cmd" => command = Some(args.next().ok_or("missing value for --bot-cmd")?),
+            "--bot-url" => url = Some(args.next().ok_or("missing value for --bot-url")?.parse()?),
+            "--bot-timeout" => {
+                let value = args.next().ok_or("missing value for --bot-timeout")?;
+                timeout = value.parse().map_err(|_| format!("bad value for --bot-timeout: {}", value))?;
             }
+            _ => return Err(format!("unknown option: {}", arg)),
         }
     }
+    let timeout = Duration::from_millis(timeout);
+    match (command, url) {
+        (Some(_), Some(_)) => Err("give --bot-cmd or --bot-url, not both".to_string()),
+        (Some(command), None) => match ExternalBot::spawn(&command, timeout) {
+            Ok(bot) => Ok(Some((command, Box::new(bot)))),
+            Err(e) => Err(format!("{}: {}", command, e)),
+        },
+        (None, Some(url)) => Ok(Some((url.to_string(), Box::new(BattlesnakeBot::new(url, timeout))))),
+        (None, None) => Ok(None),
+    }
 }
 
 fn main() -> GameResult {
 
-    let (ctx, events_loop) = &mut build_window().build()?;
+    let bot = bot_from_args().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        process::exit(1);
+    });
+
+    let mut audio_enabled = true;
+    let (ctx, events_loop) = &mut match build_window().build() {
+        Ok(window) => window,
+        Err(e) => {
+            eprintln!("Could not start with audio ({}), continuing without sound", e);
+            audio_enabled = false;
+            build_window().modules(conf::ModuleConf { audio: false, ..Default::default() })
+                          .build()?
+        }
+    };
 
-    let state = &mut GameState::new(ctx).unwrap();
+    let state = &mut GameState::new(ctx, audio_enabled).unwrap();
+    state.bot = bot;
 
     event::run(ctx, events_loop, state)
 }
\ No newline at end of file
@@ -0,0 +1,256 @@
+use std::fmt;
+use std::str::FromStr;
+
+use rand_pcg::Pcg32;
+
+use serde::{Deserialize, Serialize};
+
+use crate::arena::ArenaKind;
+use crate::consts::*;
+use crate::elements::{Ate, Food, FoodKind, GridPosition, Snake, Wall};
+use crate::grid::{CellKind, Occupancy};
+use crate::tiles::Tiles;
+use crate::topology::Topology;
+
+/// The rules a game is played by. Every hook has the classic behaviour as
+/// its default, so a mode only spells out what it changes.
+///
+/// Modes keep no state of their own: everything they look at is part of the
+/// game, so saved games and replays only need to remember which mode it was.
+pub trait GameMode {
+    /// Walls the game starts with, drawn by the `arena` generator around
+    /// the starting snake, with the border `topology` asks for.
+    fn walls(&self, rng: &mut Pcg32, arena: ArenaKind, topology: Topology) -> Wall {
+        arena.build(rng, GRID_SIZE, &self.snake(), topology)
+    }
+
+    /// The snake the game starts with.
+    fn snake(&self) -> Snake {
+        Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into())
+    }
+
+    /// Teleporters, one-way arrows and conveyors on the floor.
+    fn tiles(&self) -> Tiles {
+        Tiles::default()
+    }
+
+    /// Food put on the board before the random spawns fill it up.
+    fn foods(&self) -> Vec<Food> {
+        Vec::new()
+    }
+
+    /// Food items kept on the board at once.
+    fn food_items(&self) -> usize {
+        FOOD_ITEMS
+    }
+
+    /// Where new food may turn up on tick `tick`, as
+    /// `(min_x, min_y, max_x, max_y)` with the maximum left out.
+    fn spawn_area(&self, _tick: u64, size: (i16, i16)) -> (i16, i16, i16, i16) {
+        (3, 3, size.0 - 1, size.1 - 1)
+    }
+
+    /// Points for eating a piece of food.
+    fn points(&self, kind: FoodKind) -> u32 {
+        kind.points()
+    }
+
+    /// Whether power-ups turn up at all.
+    fn power_ups(&self) -> bool {
+        true
+    }
+
+    /// Whether the snake waits for a key before every move instead of
+    /// running on by itself.
+    fn turn_based(&self) -> bool {
+        false
+    }
+
+    /// Moves the game is lost after, if there is a limit.
+    fn move_limit(&self) -> Option<u64> {
+        None
+    }
+
+    /// Called before every tick, after the power-ups had their say.
+    fn prepare(&self, _snake: &mut Snake) {}
+
+    /// Called after every tick to change the board.
+    fn update(&self, _tick: u64, _walls: &mut Wall, _grid: &mut Occupancy) {}
+
+    /// Whether the game ends after a tick where the snake ran into `ate`,
+    /// `elapsed` seconds into the game.
+    fn is_over(&self, ate: Option<Ate>, _elapsed: f32) -> bool {
+        matches!(ate, Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake))
+    }
+
+    /// Whether the game only ends when the player leaves it. Its points go
+    /// into the high scores then.
+    fn endless(&self) -> bool {
+        false
+    }
+
+    /// How many of the `asked` rivals join the game. A game that never ends
+    /// has no way to lose to them, so it gets none.
+    fn rivals(&self, asked: u8) -> u8 {
+        if self.endless() { 0 } else { asked }
+    }
+
+    /// Whether the game is won with `points` and a snake of `length`.
+    fn is_won(&self, _points: u32, _length: usize) -> bool {
+        false
+    }
+
+    /// Text shown next to the score.
+    fn hud(&self, _elapsed: f32) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ModeKind {
+    #[default]
+    Classic,
+    TimeAttack,
+    Survival,
+    Zen,
+}
+
+impl ModeKind {
+    pub const ALL: [ModeKind; 4] = [ModeKind::Classic, ModeKind::TimeAttack, ModeKind::Survival, ModeKind::Zen];
+
+    /// The mode after this one on the start screen.
+    pub fn next(&self) -> Self {
+        let i = ModeKind::ALL.iter().position(|kind| kind == self).unwrap();
+        ModeKind::ALL[(i + 1) % ModeKind::ALL.len()]
+    }
+
+    pub fn rules(&self) -> Box<dyn GameMode> {
+        match *self {
+            ModeKind::Classic => Box::new(Classic),
+            ModeKind::TimeAttack => Box::new(TimeAttack),
+            ModeKind::Survival => Box::new(Survival),
+            ModeKind::Zen => Box::new(Zen),
+        }
+    }
+}
+
+impl fmt::Display for ModeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ModeKind::Classic => write!(f, "classic"),
+            ModeKind::TimeAttack => write!(f, "time-attack"),
+            ModeKind::Survival => write!(f, "survival"),
+            ModeKind::Zen => write!(f, "zen"),
+        }
+    }
+}
+
+impl FromStr for ModeKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "classic" => Ok(ModeKind::Classic),
+            "time-attack" => Ok(ModeKind::TimeAttack),
+            "survival" => Ok(ModeKind::Survival),
+            "zen" => Ok(ModeKind::Zen),
+            _ => Err(format!("unknown game mode: {}", s)),
+        }
+    }
+}
+
+/// Eat until you die.
+pub struct Classic;
+
+impl GameMode for Classic {}
+
+/// Catch as many mice as possible before the time runs out.
+pub struct TimeAttack;
+
+impl GameMode for TimeAttack {
+    fn food_items(&self) -> usize {
+        FOOD_ITEMS + 2
+    }
+
+    fn points(&self, kind: FoodKind) -> u32 {
+        match kind {
+            FoodKind::Poisoned => 0,
+            _ => 1,
+        }
+    }
+
+    fn is_over(&self, ate: Option<Ate>, elapsed: f32) -> bool {
+        Classic.is_over(ate, elapsed) || elapsed >= TIME_ATTACK_SECONDS
+    }
+
+    fn hud(&self, elapsed: f32) -> Option<String> {
+        Some(format!("TIME {:.0}s", (TIME_ATTACK_SECONDS - elapsed).max(0.0).ceil()))
+    }
+}
+
+/// A new ring of wall creeps in from the border every `SURVIVAL_RING_TICKS`,
+/// one cell per tick. Cells taken when the wall gets there stay open.
+pub struct Survival;
+
+impl Survival {
+    /// The ring being put up on tick `tick`, or the last one once they are
+    /// all up. Ring 0 is the border.
+    fn current_ring(tick: u64) -> i16 {
+        ((tick / SURVIVAL_RING_TICKS) as i16).min(SURVIVAL_RINGS)
+    }
+
+    /// Cells of the `ring`th ring inside the border, going round clockwise.
+    fn ring(ring: i16, size: (i16, i16)) -> Vec<GridPosition> {
+        let (left, right) = (ring, size.0 - 1 - ring);
+        let (top, bottom) = (1 + ring, size.1 - 1 - ring);
+        let mut cells = Vec::new();
+        cells.extend((left..=right).map(|x| GridPosition::new(x, top)));
+        cells.extend((top + 1..=bottom).map(|y| GridPosition::new(right, y)));
+        cells.extend((left..right).rev().map(|x| GridPosition::new(x, bottom)));
+        cells.extend((top + 1..bottom).rev().map(|y| GridPosition::new(left, y)));
+        cells
+    }
+}
+
+impl GameMode for Survival {
+    fn spawn_area(&self, tick: u64, size: (i16, i16)) -> (i16, i16, i16, i16) {
+        let ring = Survival::current_ring(tick);
+        (3.max(ring + 1), 3.max(ring + 2), size.0 - 1 - ring, size.1 - 1 - ring)
+    }
+
+    fn update(&self, tick: u64, walls: &mut Wall, grid: &mut Occupancy) {
+        let ring = (tick / SURVIVAL_RING_TICKS) as i16;
+        let step = (tick % SURVIVAL_RING_TICKS) as usize;
+        if !(1..=SURVIVAL_RINGS).contains(&ring) {
+            return;
+        }
+        if let Some(pos) = Survival::ring(ring, grid.size()).get(step) {
+            if grid.get(*pos) == CellKind::Empty {
+                walls.add(*pos);
+                grid.set(*pos, CellKind::Wall);
+            }
+        }
+    }
+}
+
+/// No walls and no way to die: the snake wraps at every edge and slides
+/// through itself.
+pub struct Zen;
+
+impl GameMode for Zen {
+    fn walls(&self, _rng: &mut Pcg32, _arena: ArenaKind, _topology: Topology) -> Wall {
+        Wall::from_positions(Vec::new())
+    }
+
+    fn prepare(&self, snake: &mut Snake) {
+        snake.ghost = true;
+    }
+
+    fn is_over(&self, _ate: Option<Ate>, _elapsed: f32) -> bool {
+        false
+    }
+
+    fn endless(&self) -> bool {
+        true
+    }
+}
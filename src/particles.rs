@@ -0,0 +1,159 @@
+use ggez::mint::Point2;
+use ggez::{graphics::{self, Color, DrawMode, MeshBuilder, Rect},
+           Context,
+           GameResult};
+
+use rand::Rng;
+
+use crate::consts::*;
+use crate::elements::GridPosition;
+
+/// How a burst of particles looks: how many there are, how fast they fly
+/// and for how long, and how they fade.
+#[derive(Clone, Copy, Debug)]
+pub struct Emitter {
+    pub count: usize,
+    pub speed: (f32, f32),
+    pub lifetime: (f32, f32),
+    pub size: (f32, f32),
+    pub color: Color,
+    pub gravity: f32,
+}
+
+impl Emitter {
+    pub fn blood() -> Self {
+        Emitter {
+            count: 40,
+            speed: (40.0, 220.0),
+            lifetime: (0.4, 1.2),
+            size: (2.0, 6.0),
+            color: Color::new(0.6, 0.0, 0.0, 1.0),
+            gravity: 300.0,
+        }
+    }
+
+    pub fn puff() -> Self {
+        Emitter {
+            count: 16,
+            speed: (20.0, 80.0),
+            lifetime: (0.2, 0.5),
+            size: (3.0, 5.0),
+            color: Color::new(0.85, 0.85, 0.85, 0.8),
+            gravity: -40.0,
+        }
+    }
+
+    pub fn debris() -> Self {
+        Emitter {
+            count: 30,
+            speed: (60.0, 260.0),
+            lifetime: (0.5, 1.0),
+            size: (3.0, 8.0),
+            color: Color::new(0.45, 0.4, 0.35, 1.0),
+            gravity: 500.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Particle {
+    pos: (f32, f32),
+    vel: (f32, f32),
+    age: f32,
+    lifetime: f32,
+    size: f32,
+    color: Color,
+    gravity: f32,
+}
+
+/// Short-lived visual effects: particle bursts and camera shake.
+///
+/// Effects use their own random numbers, so they never change what happens
+/// in the game and replays stay the same with effects on or off.
+pub struct Particles {
+    particles: Vec<Particle>,
+    shake_time: f32,
+    shake_strength: f32,
+}
+
+impl Particles {
+    pub fn new() -> Self {
+        Particles {
+            particles: Vec::new(),
+            shake_time: 0.0,
+            shake_strength: 0.0,
+        }
+    }
+
+    /// Sends a burst of particles out of the center of a cell.
+    pub fn emit(&mut self, emitter: Emitter, at: GridPosition) {
+        let mut rng = rand::thread_rng();
+        let corner: Point2<f32> = at.into();
+        let center = (corner.x + GRID_CELL_SIZE.0 as f32 / 2.0, corner.y + GRID_CELL_SIZE.1 as f32 / 2.0);
+
+        for _ in 0..emitter.count {
+            let angle = rng.gen_range(0.0, 2.0 * std::f32::consts::PI);
+            let speed = rng.gen_range(emitter.speed.0, emitter.speed.1);
+            self.particles.push(Particle {
+                pos: center,
+                vel: (angle.cos() * speed, angle.sin() * speed),
+                age: 0.0,
+                lifetime: rng.gen_range(emitter.lifetime.0, emitter.lifetime.1),
+                size: rng.gen_range(emitter.size.0, emitter.size.1),
+                color: emitter.color,
+                gravity: emitter.gravity,
+            });
+        }
+    }
+
+    pub fn shake(&mut self, strength: f32, duration: f32) {
+        self.shake_strength = self.shake_strength.max(strength);
+        self.shake_time = self.shake_time.max(duration);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.shake_time = 0.0;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for p in self.particles.iter_mut() {
+            p.age += dt;
+            p.vel.1 += p.gravity * dt;
+            p.pos.0 += p.vel.0 * dt;
+            p.pos.1 += p.vel.1 * dt;
+        }
+        self.particles.retain(|p| p.age < p.lifetime);
+
+        self.shake_time = (self.shake_time - dt).max(0.0);
+        if self.shake_time == 0.0 {
+            self.shake_strength = 0.0;
+        }
+    }
+
+    /// Offset of the camera for the current frame, zero when not shaking.
+    pub fn shake_offset(&self) -> (f32, f32) {
+        if self.shake_time == 0.0 {
+            return (0.0, 0.0);
+        }
+        let mut rng = rand::thread_rng();
+        (rng.gen_range(-1.0, 1.0) * self.shake_strength,
+         rng.gen_range(-1.0, 1.0) * self.shake_strength)
+    }
+
+    pub fn draw(&self, ctx: &mut Context) -> GameResult {
+        if self.particles.is_empty() {
+            return Ok(());
+        }
+
+        let mut mb = MeshBuilder::new();
+        for p in self.particles.iter() {
+            let fade = 1.0 - p.age / p.lifetime;
+            let color = Color::new(p.color.r, p.color.g, p.color.b, p.color.a * fade);
+            let rect = Rect::new(p.pos.0 - p.size / 2.0, p.pos.1 - p.size / 2.0, p.size, p.size);
+            mb.rectangle(DrawMode::fill(), rect, color);
+        }
+        let mesh = mb.build(ctx)?;
+        graphics::draw(ctx, &mesh, (Point2 { x: 0.0, y: 0.0 },))
+    }
+}
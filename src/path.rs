@@ -0,0 +1,191 @@
+use ggez::mint::{Point2, Vector2};
+use ggez::{graphics::{self, DrawParam},
+           Context,
+           GameResult};
+
+use crate::consts::*;
+use crate::elements::{Direction, GridPosition};
+use crate::grid::Occupancy;
+use crate::topology::Topology;
+
+/// Horizontal strips a sprite is cut into when it is bent along the path.
+/// Four strips of ten pixels are enough for a quarter turn to look round.
+const SLICES: usize = 4;
+
+/// Strips are stretched a little so the outer side of a curve has no gaps.
+const SLICE_OVERLAP: f32 = 1.15;
+
+/// The line a snake follows, from the head to the tail, through the centers of
+/// the cells it occupies. Corners are rounded into quarter circles that join
+/// the middles of the cell edges, so a sprite sliding along it turns smoothly.
+///
+/// Points are kept unwrapped: a step across the edge of the board or through
+/// a teleporter continues straight on instead of jumping. Every point has a
+/// frame that brings it back onto the board, shifted after a jump and flipped
+/// after a mirrored edge, and drawing repeats whatever sticks out on the
+/// opposite edge.
+pub struct SnakePath {
+    points: Vec<(f32, f32)>,
+    frames: Vec<Frame>,
+    topology: Topology,
+}
+
+/// Maps path points near one cell onto the board: each axis is flipped or
+/// not, then shifted.
+#[derive(Clone, Copy, Debug)]
+struct Frame {
+    flip: (f32, f32),
+    offset: (f32, f32),
+}
+
+impl Frame {
+    fn to_board(self, p: (f32, f32)) -> (f32, f32) {
+        (self.flip.0 * p.0 + self.offset.0, self.flip.1 * p.1 + self.offset.1)
+    }
+
+    fn to_path(self, p: (f32, f32)) -> (f32, f32) {
+        ((p.0 - self.offset.0) * self.flip.0, (p.1 - self.offset.1) * self.flip.1)
+    }
+
+    fn turn(self, v: (f32, f32)) -> (f32, f32) {
+        (self.flip.0 * v.0, self.flip.1 * v.1)
+    }
+}
+
+impl SnakePath {
+    pub fn new(cells: &[GridPosition], grid: &Occupancy) -> Self {
+        let topology = grid.topology();
+        let mut points: Vec<(f32, f32)> = Vec::with_capacity(cells.len());
+        let mut frames: Vec<Frame> = Vec::with_capacity(cells.len());
+        for (i, cell) in cells.iter().enumerate() {
+            let (x, y) = cell.coords();
+            let at = (x as f32, y as f32);
+            if i == 0 {
+                points.push(at);
+                frames.push(Frame { flip: (1.0, 1.0), offset: (0.0, 0.0) });
+                continue;
+            }
+
+            let last = points[i - 1];
+            let mut frame = frames[i - 1];
+            // The move that took the snake from this cell to the one before.
+            let moved = Direction::MOVES.iter()
+                                        .find(|dir| grid.step(*cell, **dir) == Some(cells[i - 1]));
+            let point = match moved {
+                Some(dir) => {
+                    if topology.is_mirrored(*cell, *dir, GRID_SIZE) {
+                        match dir {
+                            Direction::Up | Direction::Down => frame.flip.0 = -frame.flip.0,
+                            _ => frame.flip.1 = -frame.flip.1,
+                        }
+                    }
+                    let (dx, dy) = delta(*dir);
+                    (last.0 - frame.flip.0 * dx, last.1 - frame.flip.1 * dy)
+                }
+                // Not next to each other: slide straight across the board.
+                None => frame.to_path(at),
+            };
+            frame.offset = (at.0 - frame.flip.0 * point.0, at.1 - frame.flip.1 * point.1);
+            points.push(point);
+            frames.push(frame);
+        }
+        SnakePath { points, frames, topology }
+    }
+
+    /// The point `t` cells from the head along the path, in cell units on the
+    /// board, and the direction the snake moves there.
+    fn point(&self, t: f32) -> ((f32, f32), (f32, f32)) {
+        let last = self.points.len() - 1;
+        let i = (t.round().max(0.0) as usize).min(last);
+        let frame = self.frames[i];
+        let (point, tangent) = self.unwrapped_point(i, t);
+        (frame.to_board(point), frame.turn(tangent))
+    }
+
+    fn unwrapped_point(&self, i: usize, t: f32) -> ((f32, f32), (f32, f32)) {
+        let last = self.points.len() - 1;
+        let d = t - i as f32;
+        let p = self.points[i];
+
+        let u_in = if i < last { sub(p, self.points[i + 1]) } else { sub(self.points[i - 1], p) };
+        let u_out = if i > 0 { sub(self.points[i - 1], p) } else { u_in };
+
+        if u_in == u_out {
+            return ((p.0 - d * u_in.0, p.1 - d * u_in.1), u_in);
+        }
+
+        let theta = (0.5 - d).max(0.0).min(1.0) * std::f32::consts::FRAC_PI_2;
+        let (sin, cos) = theta.sin_cos();
+        let corner = (p.0 - 0.5 * u_in.0 + 0.5 * u_out.0, p.1 - 0.5 * u_in.1 + 0.5 * u_out.1);
+        let point = (corner.0 - 0.5 * cos * u_out.0 + 0.5 * sin * u_in.0,
+                     corner.1 - 0.5 * cos * u_out.1 + 0.5 * sin * u_in.1);
+        let tangent = (sin * u_out.0 + cos * u_in.0, sin * u_out.1 + cos * u_in.1);
+        (point, tangent)
+    }
+
+    /// Draws an upward-facing sprite centered `t` cells from the head, bent
+    /// to follow the path.
+    pub fn draw_bent(&self, ctx: &mut Context, image: &graphics::Image, t: f32, color: graphics::Color) -> GameResult {
+        for i in 0..SLICES {
+            let slice = 1.0 / SLICES as f32;
+            let ((x, y), (dx, dy)) = self.point(t - 0.5 + (i as f32 + 0.5) * slice);
+            let param = DrawParam::default().src(graphics::Rect::new(0.0, i as f32 * slice, 1.0, slice))
+                                            .offset(Point2 { x: 0.5, y: 0.5 })
+                                            .scale(Vector2 { x: 1.0, y: SLICE_OVERLAP })
+                                            .color(color);
+            for (dest, flip) in wrapped_copies(x, y, self.topology) {
+                let rotation = (flip.0 * dx).atan2(-flip.1 * dy);
+                graphics::draw(ctx, image, param.rotation(rotation).dest(dest))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn sub(a: (f32, f32), b: (f32, f32)) -> (f32, f32) {
+    (a.0 - b.0, a.1 - b.1)
+}
+
+/// One cell's step in `dir`, in cell units.
+fn delta(dir: Direction) -> (f32, f32) {
+    match dir {
+        Direction::Up => (0.0, -1.0),
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0),
+        Direction::None => (0.0, 0.0),
+    }
+}
+
+/// Screen positions of the center of a cell-unit point on the board, plus a
+/// copy on the opposite edge while it is partly off the board, each with the
+/// flips the copy is seen with.
+fn wrapped_copies(x: f32, y: f32, topology: Topology) -> Vec<(Point2<f32>, (f32, f32))> {
+    let size = (GRID_SIZE.0 as f32, GRID_SIZE.1 as f32);
+    // The board wraps between row 1 and the bottom row, under the score bar.
+    let rows = size.1 - 1.0;
+    let mut copies = vec![(x, y, (1.0, 1.0))];
+    if topology.wraps() {
+        let across_sides = |(x, y, flip): (f32, f32, (f32, f32))| {
+            let x = if x < size.0 / 2.0 { x + size.0 } else { x - size.0 };
+            if topology.mirrors_sides() { (x, size.1 - y, (flip.0, -flip.1)) } else { (x, y, flip) }
+        };
+        let across_top = |(x, y, flip): (f32, f32, (f32, f32))| {
+            let y = if y < size.1 / 2.0 { y + rows } else { y - rows };
+            if topology.mirrors_top() { (size.0 - 1.0 - x, y, (-flip.0, flip.1)) } else { (x, y, flip) }
+        };
+        if x < 0.5 || x > size.0 - 1.5 {
+            copies.push(across_sides(copies[0]));
+        }
+        if y < 1.5 || y > size.1 - 1.5 {
+            for i in 0..copies.len() {
+                copies.push(across_top(copies[i]));
+            }
+        }
+    }
+
+    let cell = (GRID_CELL_SIZE.0 as f32, GRID_CELL_SIZE.1 as f32);
+    copies.into_iter()
+          .map(|(x, y, flip)| (Point2 { x: x * cell.0 + cell.0 / 2.0, y: y * cell.1 + cell.1 / 2.0 }, flip))
+          .collect()
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use ggez::mint::Point2;
+use ggez::{graphics, Context, GameResult};
+
+use rand::Rng;
+
+use serde::{Deserialize, Serialize};
+
+use crate::assets::{Assets, Sprite};
+use crate::consts::*;
+use crate::elements::GridPosition;
+use crate::grid::{CellKind, Occupancy};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    /// Halves the tick rate for a while.
+    SlowTime,
+    /// Lets the snake pass through its own body for a while.
+    Ghost,
+    /// Lets the snake break through the next wall segment it runs into.
+    WallBreaker,
+    /// Cuts the snake in half on the spot.
+    Scissors,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [PowerUpKind::SlowTime, PowerUpKind::Ghost,
+                                       PowerUpKind::WallBreaker, PowerUpKind::Scissors];
+
+    pub fn sprite(&self) -> Sprite {
+        match *self {
+            PowerUpKind::SlowTime => Sprite::SlowTime,
+            PowerUpKind::Ghost => Sprite::Ghost,
+            PowerUpKind::WallBreaker => Sprite::WallBreaker,
+            PowerUpKind::Scissors => Sprite::Scissors,
+        }
+    }
+}
+
+impl fmt::Display for PowerUpKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PowerUpKind::SlowTime => write!(f, "SLOW"),
+            PowerUpKind::Ghost => write!(f, "GHOST"),
+            PowerUpKind::WallBreaker => write!(f, "BREAKER"),
+            PowerUpKind::Scissors => write!(f, "SCISSORS"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PowerUp {
+    pub pos: GridPosition,
+    pub kind: PowerUpKind,
+    pub ticks_left: u32,
+}
+
+/// Power-ups lying on the board. One turns up every `POWERUP_EVERY` ticks on
+/// a free cell and goes away if it is not picked up in `POWERUP_TICKS`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct PowerUps {
+    items: Vec<PowerUp>,
+}
+
+impl PowerUps {
+    pub fn new() -> Self {
+        PowerUps { items: Vec::new() }
+    }
+
+    pub fn items(&self) -> &[PowerUp] {
+        &self.items
+    }
+
+    /// Marks the power-ups on a freshly built grid.
+    pub fn mark(&self, grid: &mut Occupancy) {
+        for item in self.items.iter() {
+            grid.set(item.pos, CellKind::PowerUp(item.kind));
+        }
+    }
+
+    pub fn update<R: Rng>(&mut self, tick: u64, rng: &mut R, grid: &mut Occupancy) {
+        for item in self.items.iter_mut() {
+            item.ticks_left = item.ticks_left.saturating_sub(1);
+            if item.ticks_left == 0 && grid.get(item.pos) == CellKind::PowerUp(item.kind) {
+                grid.set(item.pos, CellKind::Empty);
+            }
+        }
+        self.items.retain(|item| item.ticks_left > 0);
+
+        if tick % POWERUP_EVERY == 0 && self.items.len() < MAX_POWERUPS {
+            let kind = PowerUpKind::ALL[rng.gen_range(0, PowerUpKind::ALL.len())];
+            let (width, height) = grid.size();
+            let pos = GridPosition::random(rng, 3, 3, width - 1, height - 1);
+            if grid.get(pos) == CellKind::Empty && grid.tile(pos).is_none() {
+                grid.set(pos, CellKind::PowerUp(kind));
+                self.items.push(PowerUp { pos, kind, ticks_left: POWERUP_TICKS });
+            }
+        }
+    }
+
+    /// Puts a given power-up on the board, on top of the random ones.
+    pub fn place(&mut self, item: PowerUp, grid: &mut Occupancy) {
+        grid.set(item.pos, CellKind::PowerUp(item.kind));
+        self.items.push(item);
+    }
+
+    /// Removes the power-up the snake just picked up at `pos`.
+    pub fn take(&mut self, pos: GridPosition) -> Option<PowerUp> {
+        let i = self.items.iter().position(|item| item.pos == pos)?;
+        Some(self.items.remove(i))
+    }
+
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult {
+        for item in self.items.iter() {
+            let pnt2: Point2<f32> = item.pos.into();
+            graphics::draw(ctx, assets.image(item.kind.sprite()), (pnt2,))?;
+        }
+        Ok(())
+    }
+}
+
+/// Effects of the power-ups picked up so far. Timed effects add up when the
+/// same power-up is picked up again, wall breakers add up as charges, and
+/// different effects run side by side. Everything counts in ticks so replays
+/// play out the same.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Effects {
+    pub slow_time: u32,
+    pub ghost: u32,
+    pub wall_breakers: u32,
+}
+
+impl Effects {
+    /// Starts the effect of `kind`. Scissors act at once, so they are left
+    /// to the caller.
+    pub fn pick_up(&mut self, kind: PowerUpKind) {
+        match kind {
+            PowerUpKind::SlowTime => self.slow_time += SLOW_TIME_TICKS,
+            PowerUpKind::Ghost => self.ghost += GHOST_TICKS,
+            PowerUpKind::WallBreaker => self.wall_breakers += 1,
+            PowerUpKind::Scissors => {},
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.slow_time = self.slow_time.saturating_sub(1);
+        self.ghost = self.ghost.saturating_sub(1);
+    }
+
+    /// The tick rate with the effects applied.
+    pub fn ticks_per_second(&self, ticks_per_second: u32) -> u32 {
+        if self.slow_time > 0 { (ticks_per_second / 2).max(1) } else { ticks_per_second }
+    }
+
+    /// One line per running effect for the HUD, timers given in seconds.
+    pub fn hud(&self, ticks_per_second: u32) -> Vec<String> {
+        let seconds = |ticks: u32| ticks as f32 / ticks_per_second as f32;
+        let mut lines = Vec::new();
+        if self.slow_time > 0 {
+            lines.push(format!("{} {:.1}s", PowerUpKind::SlowTime, seconds(self.slow_time)));
+        }
+        if self.ghost > 0 {
+            lines.push(format!("{} {:.1}s", PowerUpKind::Ghost, seconds(self.ghost)));
+        }
+        if self.wall_breakers > 0 {
+            lines.push(format!("{} x{}", PowerUpKind::WallBreaker, self.wall_breakers));
+        }
+        lines
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::str::FromStr;
+
+use ggez::Context;
+
+use rand_pcg::Pcg32;
+
+use crate::arena::ArenaKind;
+use crate::board::Board;
+use crate::campaign::{load_numbered, split_header};
+use crate::consts::GRID_SIZE;
+use crate::elements::{Food, FoodKind, Snake, Wall};
+use crate::modes::GameMode;
+use crate::tiles::{Tile, Tiles};
+use crate::topology::Topology;
+
+pub const PUZZLES_DIR: &str = "/puzzles";
+
+/// A fixed layout where every mouse on the board has to be eaten in at most
+/// `moves` moves. The snake only moves when it is steered, one cell a key.
+/// Puzzles are kept as text like campaign levels:
+///
+/// ```text
+/// name Corner Shop
+/// moves 24
+/// par 17
+/// ```
+///
+/// `par` is the fewest moves it can be done in, as `snake_solver` finds it.
+/// Clearing the puzzle in par earns three stars, halfway between par and the
+/// limit two, and otherwise one. Puzzles only use grey mice, which never go
+/// bad, and leave out keys, switches and doors.
+#[derive(Clone, Debug)]
+pub struct Puzzle {
+    pub name: String,
+    pub moves: u32,
+    pub par: u32,
+    pub board: Board,
+}
+
+impl Puzzle {
+    /// Stars for clearing the puzzle in `moves`.
+    pub fn stars(&self, moves: u64) -> u8 {
+        if moves <= self.par as u64 {
+            3
+        } else if moves <= (self.par + self.moves) as u64 / 2 {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// The goal, the way the start screen shows it.
+    pub fn goal(&self) -> String {
+        format!("EAT EVERY MOUSE IN {} MOVES", self.moves)
+    }
+
+    /// Points for eating every mouse on the board.
+    fn total(&self) -> u32 {
+        self.board.foods.iter().map(|food| food.kind.points()).sum()
+    }
+}
+
+impl GameMode for Puzzle {
+    fn walls(&self, _rng: &mut Pcg32, _arena: ArenaKind, _topology: Topology) -> Wall {
+        self.board.walls.clone()
+    }
+
+    fn snake(&self) -> Snake {
+        self.board.snake.clone()
+    }
+
+    fn tiles(&self) -> Tiles {
+        self.board.tiles.clone()
+    }
+
+    fn foods(&self) -> Vec<Food> {
+        self.board.foods.clone()
+    }
+
+    fn food_items(&self) -> usize {
+        0
+    }
+
+    fn power_ups(&self) -> bool {
+        false
+    }
+
+    fn turn_based(&self) -> bool {
+        true
+    }
+
+    fn move_limit(&self) -> Option<u64> {
+        Some(self.moves as u64)
+    }
+
+    fn is_won(&self, points: u32, _length: usize) -> bool {
+        points >= self.total()
+    }
+}
+
+impl fmt::Display for Puzzle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "name {}", self.name)?;
+        writeln!(f, "moves {}", self.moves)?;
+        writeln!(f, "par {}", self.par)?;
+        write!(f, "{}", self.board)
+    }
+}
+
+impl FromStr for Puzzle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut name = None;
+        let mut moves = None;
+        let mut par = None;
+        let (header, rows) = split_header(s);
+        for (key, value) in header {
+            let number = |value: &str| value.parse::<u32>().map_err(|_| format!("bad value for {}: {}", key, value));
+            match key {
+                "name" => name = Some(value.to_string()),
+                "moves" => moves = Some(number(value)?),
+                "par" => par = Some(number(value)?),
+                _ => return Err(format!("unknown key: {}", key)),
+            }
+        }
+
+        let board: Board = rows.parse()?;
+        if board.size != GRID_SIZE {
+            return Err(format!("puzzle is {}x{}, not {}x{}", board.size.0, board.size.1, GRID_SIZE.0, GRID_SIZE.1));
+        }
+        if board.foods.is_empty() {
+            return Err("a puzzle needs mice".to_string());
+        }
+        if board.foods.iter().any(|food| food.kind != FoodKind::Mouse) {
+            return Err("puzzles only use grey mice".to_string());
+        }
+        let locks = board.walls.doors().next().is_some()
+            || board.tiles.iter().any(|(_, tile)| matches!(tile, Tile::Key(_) | Tile::Switch(_)));
+        if locks {
+            return Err("puzzles leave out keys, switches and doors".to_string());
+        }
+        let moves = moves.ok_or("missing moves")?;
+        let par = par.unwrap_or(moves);
+        if par > moves {
+            return Err(format!("par {} is over the limit of {} moves", par, moves));
+        }
+        Ok(Puzzle {
+            name: name.ok_or("missing name")?,
+            moves,
+            par,
+            board,
+        })
+    }
+}
+
+/// The puzzles in `PUZZLES_DIR`, numbered from `01.txt` up.
+pub fn load(ctx: &mut Context) -> Vec<Puzzle> {
+    load_numbered(ctx, PUZZLES_DIR)
+}
@@ -0,0 +1,203 @@
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+
+use ggez::{filesystem, Context, GameResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::arena::ArenaKind;
+use crate::bots::BotTier;
+use crate::controls::{ControlScheme, Input};
+use crate::food::MouseMoves;
+use crate::modes::ModeKind;
+use crate::topology::Topology;
+
+pub const REPLAY_PATH: &str = "/last.replay";
+
+/// Everything needed to play a game back: the seed of the board RNG, the
+/// control scheme the player used, the game mode, campaign level or puzzle, the
+/// arena the walls were drawn by, how the edges of the board were joined, the
+/// rivals on it, how the mice moved if they did and every accepted input with the tick it was
+/// given on.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub controls: ControlScheme,
+    #[serde(default)]
+    pub mode: ModeKind,
+    /// The campaign level, counted from 0, or `None` outside the campaign.
+    #[serde(default)]
+    pub level: Option<usize>,
+    /// The puzzle, counted from 0, or `None` outside the puzzles.
+    #[serde(default)]
+    pub puzzle: Option<usize>,
+    #[serde(default)]
+    pub arena: ArenaKind,
+    #[serde(default)]
+    pub topology: Topology,
+    /// How many rivals the game started with, and how well they played.
+    #[serde(default)]
+    pub rivals: u8,
+    #[serde(default)]
+    pub rival_tier: BotTier,
+    pub mice: Option<MouseMoves>,
+    pub inputs: Vec<(u64, Input)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64, controls: ControlScheme, mice: Option<MouseMoves>) -> Self {
+        Replay {
+            seed,
+            controls,
+            mode: ModeKind::Classic,
+            level: None,
+            puzzle: None,
+            arena: ArenaKind::Plain,
+            topology: Topology::Portals,
+            rivals: 0,
+            rival_tier: BotTier::Greedy,
+            mice,
+            inputs: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, input: Input) {
+        self.inputs.push((tick, input));
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::create(ctx, REPLAY_PATH)?;
+        write!(file, "{}", self)?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Replay {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "controls {}", self.controls)?;
+        if self.mode != ModeKind::Classic {
+            writeln!(f, "mode {}", self.mode)?;
+        }
+        if let Some(level) = self.level {
+            writeln!(f, "level {}", level + 1)?;
+        }
+        if let Some(puzzle) = self.puzzle {
+            writeln!(f, "puzzle {}", puzzle + 1)?;
+        }
+        if self.arena != ArenaKind::Plain {
+            writeln!(f, "arena {}", self.arena)?;
+        }
+        if self.topology != Topology::Portals {
+            writeln!(f, "topology {}", self.topology)?;
+        }
+        if self.rivals > 0 {
+            writeln!(f, "rivals {} {}", self.rivals, self.rival_tier)?;
+        }
+        if let Some(mice) = self.mice {
+            writeln!(f, "mice {} {}", mice.every, mice.breed_every)?;
+        }
+        for (tick, input) in self.inputs.iter() {
+            writeln!(f, "{} {}", tick, input)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().peekable();
+        let seed = lines.next()
+                        .and_then(|l| l.strip_prefix("seed "))
+                        .ok_or("missing seed")?
+                        .parse::<u64>()
+                        .map_err(|e| e.to_string())?;
+        let controls = lines.next()
+                            .and_then(|l| l.strip_prefix("controls "))
+                            .ok_or("missing control scheme")?
+                            .parse::<ControlScheme>()?;
+
+        let mut mode = ModeKind::Classic;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("mode ")) {
+            mode = line.trim().parse()?;
+            lines.next();
+        }
+
+        let mut level = None;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("level ")) {
+            match line.trim().parse::<usize>() {
+                Ok(number) if number > 0 => level = Some(number - 1),
+                _ => return Err(format!("bad level line: {}", line)),
+            }
+            lines.next();
+        }
+
+        let mut puzzle = None;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("puzzle ")) {
+            match line.trim().parse::<usize>() {
+                Ok(number) if number > 0 => puzzle = Some(number - 1),
+                _ => return Err(format!("bad puzzle line: {}", line)),
+            }
+            lines.next();
+        }
+
+        let mut arena = ArenaKind::Plain;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("arena ")) {
+            arena = line.trim().parse()?;
+            lines.next();
+        }
+
+        let mut topology = Topology::Portals;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("topology ")) {
+            topology = line.trim().parse()?;
+            lines.next();
+        }
+
+        let mut rivals = (0, BotTier::Greedy);
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("rivals ")) {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            match parts.as_slice() {
+                &[count, tier] => {
+                    let count = count.parse::<u8>().map_err(|_| format!("bad rivals line: {}", line))?;
+                    rivals = (count, tier.parse()?);
+                }
+                _ => return Err(format!("bad rivals line: {}", line)),
+            }
+            lines.next();
+        }
+
+        let mut mice = None;
+        if let Some(line) = lines.peek().and_then(|l| l.strip_prefix("mice ")) {
+            let numbers: Vec<u32> = line.split_whitespace()
+                                        .map(|n| n.parse::<u32>().map_err(|e| e.to_string()))
+                                        .collect::<Result<_, _>>()?;
+            match numbers.as_slice() {
+                &[every, breed_every] => mice = Some(MouseMoves { every, breed_every }),
+                _ => return Err(format!("bad mice line: {}", line)),
+            }
+            lines.next();
+        }
+
+        let mut replay = Replay::new(seed, controls, mice);
+        replay.mode = mode;
+        replay.level = level;
+        replay.puzzle = puzzle;
+        replay.arena = arena;
+        replay.topology = topology;
+        replay.rivals = rivals.0;
+        replay.rival_tier = rivals.1;
+        for line in lines.filter(|l| !l.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let tick = parts.next()
+                            .ok_or("missing tick")?
+                            .parse::<u64>()
+                            .map_err(|e| e.to_string())?;
+            let input = parts.next().ok_or("missing input")?.parse::<Input>()?;
+            replay.record(tick, input);
+        }
+        Ok(replay)
+    }
+}
@@ -0,0 +1,76 @@
+use std::io::{Read, Write};
+
+use ggez::{filesystem, Context, GameError, GameResult};
+
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::bots::Rival;
+use crate::elements::{Snake, Wall};
+use crate::food::FoodManager;
+use crate::modes::ModeKind;
+use crate::powerups::{Effects, PowerUps};
+use crate::replay::Replay;
+use crate::tiles::Tiles;
+
+pub const SAVE_PATH: &str = "/save.json";
+
+/// A game in progress as it is written to disk. It holds only the model:
+/// assets, audio and effects are rebuilt on load, so the file does not depend
+/// on anything that lives in the graphics context.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedGame {
+    pub snake: Snake,
+    pub foods: FoodManager,
+    pub powerups: PowerUps,
+    pub effects: Effects,
+    pub walls: Wall,
+    /// The tiles as they are now, without the keys already eaten.
+    #[serde(default)]
+    pub tiles: Tiles,
+    #[serde(default)]
+    pub keys: Vec<u8>,
+    #[serde(default)]
+    pub rivals: Vec<Rival>,
+    pub points: u32,
+    pub tick: u64,
+    pub rng: Pcg32,
+    pub speed: u32,
+    pub burst: u32,
+    /// Saves from before game modes load as Classic games.
+    #[serde(default)]
+    pub mode: ModeKind,
+    pub level: Option<usize>,
+    #[serde(default)]
+    pub elapsed: f32,
+    pub replay: Replay,
+}
+
+impl SavedGame {
+    pub fn exists(ctx: &Context) -> bool {
+        filesystem::exists(ctx, SAVE_PATH)
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let json = serde_json::to_string(self)
+            .map_err(|e| GameError::FilesystemError(e.to_string()))?;
+        let mut file = filesystem::create(ctx, SAVE_PATH)?;
+        file.write_all(json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn load(ctx: &mut Context) -> GameResult<SavedGame> {
+        let mut json = String::new();
+        filesystem::open(ctx, SAVE_PATH)?.read_to_string(&mut json)?;
+        serde_json::from_str(&json)
+            .map_err(|e| GameError::FilesystemError(e.to_string()))
+    }
+
+    pub fn delete(ctx: &mut Context) {
+        if SavedGame::exists(ctx) {
+            if let Err(e) = filesystem::delete(ctx, SAVE_PATH) {
+                eprintln!("Could not delete saved game: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use ggez::{filesystem, Context, GameResult};
+
+use crate::consts::HIGH_SCORES;
+use crate::modes::ModeKind;
+
+pub const SCORES_PATH: &str = "/highscores.txt";
+
+/// The best `HIGH_SCORES` results of every game mode, kept in the user
+/// directory as one `mode score score ...` line per mode, best first.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScores {
+    tables: HashMap<ModeKind, Vec<u32>>,
+}
+
+impl HighScores {
+    pub fn load(ctx: &mut Context) -> HighScores {
+        if !filesystem::exists(ctx, SCORES_PATH) {
+            return HighScores::default();
+        }
+
+        let mut text = String::new();
+        let read = filesystem::open(ctx, SCORES_PATH)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.into()));
+        if let Err(e) = read {
+            eprintln!("Could not read high scores: {}", e);
+            return HighScores::default();
+        }
+
+        text.parse().unwrap_or_else(|e| {
+            eprintln!("Could not parse high scores: {}", e);
+            HighScores::default()
+        })
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::create(ctx, SCORES_PATH)?;
+        write!(file, "{}", self)?;
+        Ok(())
+    }
+
+    /// The table of `mode`, best first.
+    pub fn table(&self, mode: ModeKind) -> &[u32] {
+        self.tables.get(&mode).map_or(&[], |table| table.as_slice())
+    }
+
+    /// Enters `points` in the table of `mode` and returns its place, counted
+    /// from 0, or `None` when it was not good enough to make the table.
+    pub fn submit(&mut self, mode: ModeKind, points: u32) -> Option<usize> {
+        let table = self.tables.entry(mode).or_default();
+        let place = table.iter().position(|best| points > *best).unwrap_or(table.len());
+        if place >= HIGH_SCORES {
+            return None;
+        }
+        table.insert(place, points);
+        table.truncate(HIGH_SCORES);
+        Some(place)
+    }
+}
+
+impl fmt::Display for HighScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for mode in ModeKind::ALL.iter() {
+            let table = self.table(*mode);
+            if table.is_empty() {
+                continue;
+            }
+            write!(f, "{}", mode)?;
+            for points in table.iter() {
+                write!(f, " {}", points)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for HighScores {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut scores = HighScores::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let mut parts = line.split_whitespace();
+            let mode = parts.next().unwrap_or("").parse::<ModeKind>()?;
+            let mut table: Vec<u32> = parts.map(|n| n.parse::<u32>().map_err(|_| format!("bad score: {}", n)))
+                                           .collect::<Result<_, _>>()?;
+            table.sort_unstable_by(|a, b| b.cmp(a));
+            table.truncate(HIGH_SCORES);
+            scores.tables.insert(mode, table);
+        }
+        Ok(scores)
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use ggez::{filesystem, Context, GameResult};
+
+use crate::arena::ArenaKind;
+use crate::bots::BotTier;
+use crate::consts::MAX_RIVALS;
+use crate::food::MouseMoves;
+use crate::modes::ModeKind;
+use crate::topology::Topology;
+
+pub const SETTINGS_PATH: &str = "/settings.txt";
+
+/// Player preferences kept between sessions in the user directory as plain
+/// `key = value` lines. Unknown keys are ignored so older files keep loading.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Settings {
+    pub master_volume: f32,
+    pub sfx_volume: f32,
+    pub music_volume: f32,
+    pub muted: bool,
+    pub screen_shake: bool,
+    pub reduced_motion: bool,
+    pub moving_mice: bool,
+    pub mouse_speed: u32,
+    pub breed_ticks: u32,
+    pub mode: ModeKind,
+    pub arena: ArenaKind,
+    pub topology: Topology,
+    pub rivals: u8,
+    pub rival_tier: BotTier,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            master_volume: 0.8,
+            sfx_volume: 1.0,
+            music_volume: 0.5,
+            muted: false,
+            screen_shake: true,
+            reduced_motion: false,
+            moving_mice: false,
+            mouse_speed: 3,
+            breed_ticks: 0,
+            mode: ModeKind::Classic,
+            arena: ArenaKind::Plain,
+            topology: Topology::Portals,
+            rivals: 0,
+            rival_tier: BotTier::Greedy,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(ctx: &mut Context) -> Settings {
+        if !filesystem::exists(ctx, SETTINGS_PATH) {
+            return Settings::default();
+        }
+
+        let mut text = String::new();
+        let read = filesystem::open(ctx, SETTINGS_PATH)
+            .and_then(|mut file| file.read_to_string(&mut text).map_err(|e| e.into()));
+        if let Err(e) = read {
+            eprintln!("Could not read settings: {}", e);
+            return Settings::default();
+        }
+
+        text.parse().unwrap_or_else(|e| {
+            eprintln!("Could not parse settings: {}", e);
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self, ctx: &mut Context) -> GameResult {
+        let mut file = filesystem::create(ctx, SETTINGS_PATH)?;
+        write!(file, "{}", self)?;
+        Ok(())
+    }
+
+    pub fn sfx_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master_volume * self.sfx_volume }
+    }
+
+    pub fn music_gain(&self) -> f32 {
+        if self.muted { 0.0 } else { self.master_volume * self.music_volume }
+    }
+
+    pub fn particles(&self) -> bool {
+        !self.reduced_motion
+    }
+
+    pub fn shake(&self) -> bool {
+        self.screen_shake && !self.reduced_motion
+    }
+
+    pub fn mouse_moves(&self) -> Option<MouseMoves> {
+        if self.moving_mice {
+            Some(MouseMoves { every: self.mouse_speed, breed_every: self.breed_ticks })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Settings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "master_volume = {}", self.master_volume)?;
+        writeln!(f, "sfx_volume = {}", self.sfx_volume)?;
+        writeln!(f, "music_volume = {}", self.music_volume)?;
+        writeln!(f, "muted = {}", self.muted)?;
+        writeln!(f, "screen_shake = {}", self.screen_shake)?;
+        writeln!(f, "reduced_motion = {}", self.reduced_motion)?;
+        writeln!(f, "moving_mice = {}", self.moving_mice)?;
+        writeln!(f, "mouse_speed = {}", self.mouse_speed)?;
+        writeln!(f, "breed_ticks = {}", self.breed_ticks)?;
+        writeln!(f, "mode = {}", self.mode)?;
+        writeln!(f, "arena = {}", self.arena)?;
+        writeln!(f, "topology = {}", self.topology)?;
+        writeln!(f, "rivals = {}", self.rivals)?;
+        writeln!(f, "rival_tier = {}", self.rival_tier)
+    }
+}
+
+impl FromStr for Settings {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut settings = Settings::default();
+        for line in s.lines().filter(|l| !l.trim().is_empty()) {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().ok_or(format!("missing value for {}", key))?.trim();
+            match key {
+                "master_volume" => settings.master_volume = parse_volume(value)?,
+                "sfx_volume" => settings.sfx_volume = parse_volume(value)?,
+                "music_volume" => settings.music_volume = parse_volume(value)?,
+                "muted" => settings.muted = parse_flag(key, value)?,
+                "screen_shake" => settings.screen_shake = parse_flag(key, value)?,
+                "reduced_motion" => settings.reduced_motion = parse_flag(key, value)?,
+                "moving_mice" => settings.moving_mice = parse_flag(key, value)?,
+                "mouse_speed" => settings.mouse_speed = parse_ticks(key, value)?.max(1),
+                "breed_ticks" => settings.breed_ticks = parse_ticks(key, value)?,
+                "mode" => settings.mode = value.parse()?,
+                "arena" => settings.arena = value.parse()?,
+                "topology" => settings.topology = value.parse()?,
+                "rivals" => settings.rivals = parse_ticks(key, value)?.min(MAX_RIVALS as u32) as u8,
+                "rival_tier" => settings.rival_tier = value.parse()?,
+                _ => {},
+            }
+        }
+        Ok(settings)
+    }
+}
+
+fn parse_volume(value: &str) -> Result<f32, String> {
+    value.parse::<f32>()
+         .map(|v| v.clamp(0.0, 1.0))
+         .map_err(|_| format!("bad volume: {}", value))
+}
+
+fn parse_ticks(key: &str, value: &str) -> Result<u32, String> {
+    value.parse::<u32>()
+         .map_err(|_| format!("bad value for {}: {}", key, value))
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool, String> {
+    value.parse::<bool>()
+         .map_err(|_| format!("bad value for {}: {}", key, value))
+}
@@ -0,0 +1,229 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use crate::board::Board;
+use crate::controls::{ControlScheme, Input};
+use crate::elements::{Ate, Direction, FoodKind, GridPosition};
+use crate::grid::{CellKind, Occupancy};
+use crate::replay::Replay;
+use crate::tiles::Tile;
+
+/// States looked at before a search is given up, unless told otherwise.
+pub const MAX_STATES: usize = 2_000_000;
+
+/// Cells the body path has room for, two bits a move with a spare one for
+/// the move being made.
+const MAX_LENGTH: usize = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SolveError {
+    /// The board has something the search does not model.
+    Unsupported(String),
+    /// No way to eat every mouse within the move limit.
+    Unsolvable,
+    /// The search ran out of room before it was done.
+    TooManyStates(usize),
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SolveError::Unsupported(what) => write!(f, "the solver does not handle {}", what),
+            SolveError::Unsolvable => write!(f, "no solution within the move limit"),
+            SolveError::TooManyStates(count) => write!(f, "gave up after {} states", count),
+        }
+    }
+}
+
+/// Where the snake is and what it has eaten. The body is kept as the path of
+/// moves that leads from the tail to the head, two bits a move with the
+/// tail's first, so a state is a few words however long the snake is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct State {
+    head: GridPosition,
+    tail: GridPosition,
+    dir: u8,
+    path: u128,
+    length: u8,
+    eaten: u64,
+}
+
+fn bits(dir: Direction) -> u8 {
+    match dir {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right | Direction::None => 3,
+    }
+}
+
+fn from_bits(bits: u8) -> Direction {
+    Direction::MOVES[bits as usize & 3]
+}
+
+impl State {
+    /// The cells of the body from the tail up to the head, the head left out.
+    fn body(&self, grid: &Occupancy) -> Vec<GridPosition> {
+        let mut cells = Vec::with_capacity(self.length as usize);
+        let mut pos = self.tail;
+        for i in 0..self.length as usize - 1 {
+            cells.push(pos);
+            let dir = from_bits((self.path >> (2 * i)) as u8);
+            pos = grid.step(pos, dir).expect("the body path follows legal moves");
+        }
+        cells
+    }
+
+    /// The state after steering `input`, or `None` if the snake dies.
+    fn next(&self, input: Direction, grid: &Occupancy, mice: &[GridPosition]) -> Option<State> {
+        let dir = match grid.tile(self.head) {
+            Some(Tile::Conveyor(way)) => way,
+            _ => input,
+        };
+        let head = grid.step(self.head, dir)?;
+        if grid.get(head) == CellKind::Wall {
+            return None;
+        }
+        let body = self.body(grid);
+        if body.iter().skip(1).any(|pos| *pos == head) {
+            return None;
+        }
+
+        let moves = self.length as usize - 1;
+        let path = self.path | (bits(dir) as u128) << (2 * moves);
+        let mut next = State { head, dir: bits(dir), ..*self };
+        match mice.iter().position(|pos| *pos == head).filter(|i| self.eaten & (1 << i) == 0) {
+            Some(i) => {
+                next.eaten |= 1 << i;
+                next.path = path;
+                next.length += 1;
+            }
+            None => {
+                next.tail = grid.step(self.tail, from_bits(path as u8)).unwrap();
+                next.path = path >> 2;
+            }
+        }
+        Some(next)
+    }
+}
+
+/// The fewest moves that eat every mouse on `board` within `limit` moves,
+/// found by a breadth-first search over the snake's states. Every move is a
+/// key press, so the first solution found is an optimal one. The snake moves
+/// by the same rules as in the game: edges, teleporters, one-way tiles and
+/// conveyors included.
+pub fn solve(board: &Board, limit: u32, max_states: usize) -> Result<Vec<Direction>, SolveError> {
+    if board.walls.doors().next().is_some()
+        || board.tiles.iter().any(|(_, tile)| matches!(tile, Tile::Key(_) | Tile::Switch(_))) {
+        return Err(SolveError::Unsupported("keys, switches and doors".to_string()));
+    }
+    if board.foods.iter().any(|food| food.kind != FoodKind::Mouse) {
+        return Err(SolveError::Unsupported("food other than grey mice".to_string()));
+    }
+    if board.foods.len() > 64 || board.snake.length() + board.foods.len() > MAX_LENGTH {
+        return Err(SolveError::Unsupported("that many mice".to_string()));
+    }
+
+    // The walls and tiles alone: the body is in the state and the mice are
+    // looked up by their place in the list.
+    let mut grid = Occupancy::build(board.size, &board.walls, &board.snake, &[])
+        .with_tiles(board.tiles.clone());
+    for pos in board.snake.positions() {
+        grid.set(pos, CellKind::Empty);
+    }
+    let mice: Vec<GridPosition> = board.foods.iter().map(|food| food.pos).collect();
+    let all = if mice.len() == 64 { u64::MAX } else { (1 << mice.len()) - 1 };
+
+    let segments: Vec<_> = board.snake.segments().collect();
+    let mut path = 0u128;
+    for (i, seg) in segments.iter().rev().skip(1).enumerate() {
+        path |= (bits(seg.dir) as u128) << (2 * i);
+    }
+    let start = State {
+        head: board.snake.head_pos(),
+        tail: segments[segments.len() - 1].pos,
+        dir: bits(board.snake.dir),
+        path,
+        length: segments.len() as u8,
+        eaten: 0,
+    };
+
+    // Every state found, with the one it came from and the key that led to it.
+    let mut found: Vec<(State, usize, Direction)> = vec![(start, 0, Direction::None)];
+    let mut seen: HashMap<State, u32> = HashMap::new();
+    seen.insert(start, 0);
+    let mut todo = VecDeque::new();
+    todo.push_back(0);
+    while let Some(i) = todo.pop_front() {
+        let (state, _, _) = found[i];
+        if state.eaten == all {
+            let mut moves = Vec::new();
+            let mut i = i;
+            while i != 0 {
+                moves.push(found[i].2);
+                i = found[i].1;
+            }
+            moves.reverse();
+            return Ok(moves);
+        }
+        let depth = seen[&state];
+        if depth >= limit {
+            continue;
+        }
+
+        // Steering does nothing on a conveyor, so one key will do there.
+        let heading = from_bits(state.dir);
+        let inputs: Vec<Direction> = match grid.tile(state.head) {
+            Some(Tile::Conveyor(_)) => vec![heading],
+            _ => Direction::MOVES.iter().cloned().filter(|dir| dir.inverse() != heading).collect(),
+        };
+        for input in inputs {
+            if let Some(next) = state.next(input, &grid, &mice) {
+                if let Entry::Vacant(entry) = seen.entry(next) {
+                    if found.len() >= max_states {
+                        return Err(SolveError::TooManyStates(found.len()));
+                    }
+                    entry.insert(depth + 1);
+                    found.push((next, i, input));
+                    todo.push_back(found.len() - 1);
+                }
+            }
+        }
+    }
+    Err(SolveError::Unsolvable)
+}
+
+/// The board after every one of `moves`, played with the game's own snake.
+/// It stops early if the snake dies.
+pub fn play(board: &Board, moves: &[Direction]) -> Vec<Board> {
+    let mut board = board.clone();
+    let mut grid = board.grid();
+    let mut boards = Vec::with_capacity(moves.len());
+    for dir in moves {
+        board.snake.steer(*dir);
+        board.snake.update(&mut grid);
+        match board.snake.ate {
+            Some(Ate::Food(_)) => {
+                let head = board.snake.head_pos();
+                grid.remove_food(head);
+                board.foods.retain(|food| food.pos != head);
+            }
+            Some(Ate::Wall) | Some(Ate::Itself) | Some(Ate::OtherSnake) => break,
+            _ => {},
+        }
+        boards.push(board.clone());
+    }
+    boards
+}
+
+/// A replay of `moves`, one key a tick, for the puzzle numbered `puzzle`
+/// from 0.
+pub fn replay(moves: &[Direction], puzzle: Option<usize>) -> Replay {
+    let mut replay = Replay::new(0, ControlScheme::Absolute, None);
+    replay.puzzle = puzzle;
+    for (tick, dir) in moves.iter().enumerate() {
+        replay.record(tick as u64, Input::Absolute(*dir));
+    }
+    replay
+}
@@ -0,0 +1,192 @@
+use ggez::mint::Point2;
+use ggez::{graphics::{self, DrawParam},
+           Context,
+           GameResult};
+
+use serde::{Deserialize, Serialize};
+
+use crate::assets::{Assets, Sprite};
+use crate::consts::*;
+use crate::elements::{Direction, GridPosition};
+
+/// Floor that does something to whatever moves onto it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tile {
+    /// One end of a teleporter pair, numbered 0 to 9. Moving onto it comes
+    /// out on the other end, still heading the same way.
+    Teleporter(u8),
+    /// Can only be entered heading this way.
+    OneWay(Direction),
+    /// Carries the snake this way whatever it is steered to. It cannot be
+    /// entered against the belt.
+    Conveyor(Direction),
+    /// A key that opens the locked doors of its group when eaten.
+    Key(u8),
+    /// A pressure switch that works the gates of its group while the snake
+    /// lies on it.
+    Switch(u8),
+}
+
+impl Tile {
+    /// The character a board shows the tile as: the digit of a teleporter,
+    /// `n e s w` for one-way arrows, `N E S W` for conveyors, `k` for a key
+    /// and `o` for a switch. The group of a key or switch is not shown.
+    pub fn to_char(self) -> char {
+        match self {
+            Tile::Teleporter(pair) => (b'0' + pair) as char,
+            Tile::OneWay(dir) => compass(dir),
+            Tile::Conveyor(dir) => compass(dir).to_ascii_uppercase(),
+            Tile::Key(_) => 'k',
+            Tile::Switch(_) => 'o',
+        }
+    }
+
+    /// The group a key or switch belongs to.
+    pub fn group(self) -> Option<u8> {
+        match self {
+            Tile::Key(group) | Tile::Switch(group) => Some(group),
+            _ => None,
+        }
+    }
+
+    /// The same key or switch in another group.
+    pub fn in_group(self, group: u8) -> Tile {
+        match self {
+            Tile::Key(_) => Tile::Key(group),
+            Tile::Switch(_) => Tile::Switch(group),
+            tile => tile,
+        }
+    }
+
+    pub fn from_char(c: char) -> Option<Tile> {
+        match c {
+            '0'..='9' => Some(Tile::Teleporter(c as u8 - b'0')),
+            'n' | 'e' | 's' | 'w' => Some(Tile::OneWay(from_compass(c))),
+            'N' | 'E' | 'S' | 'W' => Some(Tile::Conveyor(from_compass(c.to_ascii_lowercase()))),
+            'k' => Some(Tile::Key(0)),
+            'o' => Some(Tile::Switch(0)),
+            _ => None,
+        }
+    }
+}
+
+fn compass(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'n',
+        Direction::Right | Direction::None => 'e',
+        Direction::Down => 's',
+        Direction::Left => 'w',
+    }
+}
+
+fn from_compass(c: char) -> Direction {
+    match c {
+        'n' => Direction::Up,
+        's' => Direction::Down,
+        'w' => Direction::Left,
+        _ => Direction::Right,
+    }
+}
+
+/// Tints telling the teleporter pairs, and the groups of keys, switches and
+/// doors, apart.
+const PAIR_COLORS: [(f32, f32, f32); 5] = [(0.6, 0.4, 1.0), (0.3, 0.9, 1.0), (1.0, 0.5, 0.8),
+                                           (0.5, 1.0, 0.5), (1.0, 0.8, 0.3)];
+
+pub fn group_color(group: u8, alpha: f32) -> graphics::Color {
+    let (r, g, b) = PAIR_COLORS[group as usize % PAIR_COLORS.len()];
+    graphics::Color::new(r, g, b, alpha)
+}
+
+/// The special tiles of a board. Teleporters come in pairs sharing a number;
+/// a lone one does nothing until its partner is put down.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tiles {
+    list: Vec<(GridPosition, Tile)>,
+}
+
+impl Tiles {
+    pub fn new(list: Vec<(GridPosition, Tile)>) -> Result<Self, String> {
+        let mut tiles = Tiles::default();
+        for (pos, tile) in list {
+            if let Tile::Teleporter(pair) = tile {
+                if tiles.list.iter().filter(|(_, t)| *t == tile).count() >= 2 {
+                    return Err(format!("more than two teleporters numbered {}", pair));
+                }
+            }
+            tiles.set(pos, tile);
+        }
+        Ok(tiles)
+    }
+
+    /// Puts `tile` on `pos`, replacing whatever tile was there.
+    pub fn set(&mut self, pos: GridPosition, tile: Tile) {
+        self.remove(pos);
+        self.list.push((pos, tile));
+        self.list.sort_by_key(|(p, _)| (p.coords().1, p.coords().0));
+    }
+
+    pub fn remove(&mut self, pos: GridPosition) {
+        self.list.retain(|(p, _)| *p != pos);
+    }
+
+    pub fn get(&self, pos: GridPosition) -> Option<Tile> {
+        self.list.iter().find(|(p, _)| *p == pos).map(|(_, tile)| *tile)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(GridPosition, Tile)> + '_ {
+        self.list.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    /// The other end of the teleporter on `pos`.
+    pub fn partner(&self, pos: GridPosition) -> Option<GridPosition> {
+        let tile = self.get(pos)?;
+        self.list.iter()
+                 .find(|(p, t)| *t == tile && *p != pos)
+                 .map(|(p, _)| *p)
+    }
+
+    /// Where a move heading `dir` onto `pos` ends up, or `None` when the tile
+    /// there does not let it in.
+    pub fn enter(&self, pos: GridPosition, dir: Direction) -> Option<GridPosition> {
+        match self.get(pos) {
+            Some(Tile::OneWay(way)) if way != dir => None,
+            Some(Tile::Conveyor(way)) if way == dir.inverse() => None,
+            Some(Tile::Teleporter(_)) => Some(self.partner(pos).unwrap_or(pos)),
+            _ => Some(pos),
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, assets: &Assets) -> GameResult {
+        for (pos, tile) in self.list.iter() {
+            let corner: Point2<f32> = (*pos).into();
+            let center = Point2 { x: corner.x + GRID_CELL_SIZE.0 as f32 / 2.0,
+                                  y: corner.y + GRID_CELL_SIZE.1 as f32 / 2.0 };
+            let param = DrawParam::default().dest(center).offset(Point2 { x: 0.5, y: 0.5 });
+            let (sprite, param) = match *tile {
+                Tile::Teleporter(pair) => (Sprite::Teleporter, param.color(group_color(pair, 1.0))),
+                Tile::OneWay(dir) => (Sprite::OneWay, param.rotation(angle(dir))),
+                Tile::Conveyor(dir) => (Sprite::Conveyor, param.rotation(angle(dir))),
+                Tile::Key(group) => (Sprite::Key, param.color(group_color(group, 1.0))),
+                Tile::Switch(group) => (Sprite::Switch, param.color(group_color(group, 1.0))),
+            };
+            graphics::draw(ctx, assets.image(sprite), param)?;
+        }
+        Ok(())
+    }
+}
+
+/// How far to turn a sprite that points up so it points `dir`.
+fn angle(dir: Direction) -> f32 {
+    let quarter = std::f32::consts::FRAC_PI_2;
+    match dir {
+        Direction::Up | Direction::None => 0.0,
+        Direction::Right => quarter,
+        Direction::Down => 2.0 * quarter,
+        Direction::Left => 3.0 * quarter,
+    }
+}
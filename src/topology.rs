@@ -0,0 +1,155 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::elements::{Direction, GridPosition, Wall};
+
+/// How the edges of the board are joined, which decides where a move off
+/// one edge comes back in. Row 0 is the score bar and not part of the board:
+/// the top edge runs along row 1, so a move up out of row 1 comes back in on
+/// the bottom row and nothing ever steps into row 0.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Topology {
+    /// Every edge wraps straight across, but the border is walled and only
+    /// the holes in it let the snake through. The way the game always played.
+    #[default]
+    Portals,
+    /// A closed box: the holes in the border are filled in and nothing wraps.
+    Bounded,
+    /// No border at all and every edge wraps straight across.
+    Torus,
+    /// No border; the sides wrap straight across and the top and bottom wrap
+    /// mirrored, coming back in on the other side of the board.
+    Klein,
+    /// No border and every edge wraps mirrored.
+    Projective,
+}
+
+impl Topology {
+    pub const ALL: [Topology; 5] = [Topology::Portals, Topology::Bounded, Topology::Torus,
+                                    Topology::Klein, Topology::Projective];
+
+    /// The topology after this one on the start screen.
+    pub fn next(&self) -> Self {
+        let i = Topology::ALL.iter().position(|topology| topology == self).unwrap();
+        Topology::ALL[(i + 1) % Topology::ALL.len()]
+    }
+
+    /// The cell a move `dir` out of `pos` lands on, or `None` when it runs
+    /// off a board that does not wrap.
+    pub fn step(&self, pos: GridPosition, dir: Direction, size: (i16, i16)) -> Option<GridPosition> {
+        if !self.crosses_edge(pos, dir, size) {
+            return Some(GridPosition::new_from_move_in(pos, dir, size));
+        }
+        if *self == Topology::Bounded {
+            return None;
+        }
+        let (x, y) = pos.coords();
+        let (x, y) = match dir {
+            Direction::Up => (x, size.1 - 1),
+            Direction::Down => (x, 1),
+            Direction::Left => (size.0 - 1, y),
+            _ => (0, y),
+        };
+        if self.is_mirrored(pos, dir, size) {
+            match dir {
+                Direction::Up | Direction::Down => Some(GridPosition::new(size.0 - 1 - x, y)),
+                // Rows 1 to `size.1 - 1` turn over onto each other.
+                _ => Some(GridPosition::new(x, size.1 - y)),
+            }
+        } else {
+            Some(GridPosition::new(x, y))
+        }
+    }
+
+    /// Whether a move `dir` out of `pos` crosses an edge that wraps mirrored,
+    /// so the board is seen flipped on the other side.
+    pub fn is_mirrored(&self, pos: GridPosition, dir: Direction, size: (i16, i16)) -> bool {
+        if !self.crosses_edge(pos, dir, size) {
+            return false;
+        }
+        match dir {
+            Direction::Up | Direction::Down => self.mirrors_top(),
+            _ => self.mirrors_sides(),
+        }
+    }
+
+    /// Whether the top and bottom edges are joined mirrored.
+    pub fn mirrors_top(&self) -> bool {
+        *self == Topology::Klein || *self == Topology::Projective
+    }
+
+    /// Whether the left and right edges are joined mirrored.
+    pub fn mirrors_sides(&self) -> bool {
+        *self == Topology::Projective
+    }
+
+    fn crosses_edge(&self, pos: GridPosition, dir: Direction, size: (i16, i16)) -> bool {
+        let (x, y) = pos.coords();
+        match dir {
+            Direction::Up => y <= 1,
+            Direction::Down => y == size.1 - 1,
+            Direction::Left => x == 0,
+            Direction::Right => x == size.0 - 1,
+            Direction::None => false,
+        }
+    }
+
+    /// Whether anything wraps at all, and so has to be drawn on both sides.
+    pub fn wraps(&self) -> bool {
+        *self != Topology::Bounded
+    }
+
+    /// `walls` with the border reworked to suit: filled in for a closed box,
+    /// taken away where the edges wrap freely, left alone for portals.
+    pub fn shape(&self, walls: Wall, size: (i16, i16)) -> Wall {
+        let border = |pos: &GridPosition| {
+            let (x, y) = pos.coords();
+            y >= 1 && (x == 0 || x == size.0 - 1 || y == 1 || y == size.1 - 1)
+        };
+        match *self {
+            Topology::Portals => walls,
+            Topology::Bounded => {
+                let mut walls = walls;
+                for y in 1..size.1 {
+                    for x in 0..size.0 {
+                        let pos = GridPosition::new(x, y);
+                        if border(&pos) && !walls.positions().any(|p| p == pos) {
+                            walls.add(pos);
+                        }
+                    }
+                }
+                walls
+            }
+            _ => Wall::from_positions(walls.positions().filter(|pos| !border(pos)).collect::<Vec<_>>()),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Topology::Portals => write!(f, "portals"),
+            Topology::Bounded => write!(f, "bounded"),
+            Topology::Torus => write!(f, "torus"),
+            Topology::Klein => write!(f, "klein"),
+            Topology::Projective => write!(f, "projective"),
+        }
+    }
+}
+
+impl FromStr for Topology {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "portals" => Ok(Topology::Portals),
+            "bounded" => Ok(Topology::Bounded),
+            "torus" => Ok(Topology::Torus),
+            "klein" => Ok(Topology::Klein),
+            "projective" => Ok(Topology::Projective),
+            _ => Err(format!("unknown topology: {}", s)),
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::bots::Bot;
+use crate::consts::*;
+use crate::duel::{self, DuelConfig, Record};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Everybody meets everybody once a round.
+    RoundRobin,
+    /// Every round pairs bots with the same score that have not met yet.
+    Swiss,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Format::RoundRobin => write!(f, "round-robin"),
+            Format::Swiss => write!(f, "swiss"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "round-robin" => Ok(Format::RoundRobin),
+            "swiss" => Ok(Format::Swiss),
+            _ => Err(format!("unknown tournament format: {}", s)),
+        }
+    }
+}
+
+/// A TrueSkill rating: the skill it is thought to have and how sure of that
+/// the rating is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Skill {
+    pub mu: f64,
+    pub sigma: f64,
+}
+
+impl Default for Skill {
+    fn default() -> Self {
+        Skill { mu: TRUESKILL_MU, sigma: TRUESKILL_SIGMA }
+    }
+}
+
+impl Skill {
+    /// The skill it almost surely has at least, what the table is sorted by.
+    pub fn conservative(&self) -> f64 {
+        self.mu - 3.0 * self.sigma
+    }
+}
+
+fn pdf(x: f64) -> f64 {
+    (-x * x / 2.0).exp() / (2.0 * PI).sqrt()
+}
+
+/// The normal distribution function, by the Abramowitz and Stegun formula
+/// for `erf`, good to about 1e-7.
+fn cdf(x: f64) -> f64 {
+    let z = x.abs() / 2f64.sqrt();
+    let t = 1.0 / (1.0 + 0.327_591_1 * z);
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-z * z).exp();
+    if x >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// Where `cdf` reaches `p`, found by halving the interval.
+fn inverse_cdf(p: f64) -> f64 {
+    let (mut low, mut high) = (-10.0, 10.0);
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if cdf(middle) < p {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// The new ratings of two players after a game `a` won, or drew if `draw`.
+pub fn trueskill(a: Skill, b: Skill, draw: bool) -> (Skill, Skill) {
+    let beta = TRUESKILL_SIGMA / 2.0;
+    let tau = TRUESKILL_SIGMA / 100.0;
+    let (var_a, var_b) = (a.sigma.powi(2) + tau * tau, b.sigma.powi(2) + tau * tau);
+    let c2 = 2.0 * beta * beta + var_a + var_b;
+    let c = c2.sqrt();
+    let margin = inverse_cdf((TRUESKILL_DRAW_PROBABILITY + 1.0) / 2.0) * 2f64.sqrt() * beta / c;
+    let t = (a.mu - b.mu) / c;
+
+    let (v, w) = if draw {
+        let (low, high) = (-margin - t, margin - t);
+        let p = (cdf(high) - cdf(low)).max(f64::MIN_POSITIVE);
+        let v = (pdf(low) - pdf(high)) / p;
+        (v, v * v + (high * pdf(high) - low * pdf(low)) / p)
+    } else {
+        let x = t - margin;
+        let p = cdf(x);
+        let v = if p > 1e-300 { pdf(x) / p } else { -x };
+        (v, v * (v + x))
+    };
+
+    let update = |skill: Skill, var: f64, sign: f64| Skill {
+        mu: skill.mu + sign * var / c * v,
+        sigma: (var * (1.0 - var / c2 * w).max(1e-4)).sqrt(),
+    };
+    (update(a, var_a, 1.0), update(b, var_b, -1.0))
+}
+
+/// The new Elo ratings after `a` scored `score` against `b`: 1 for a win,
+/// 0.5 for a draw, 0 for a loss.
+pub fn elo(a: f64, b: f64, score: f64) -> (f64, f64) {
+    let expected = 1.0 / (1.0 + 10f64.powf((b - a) / 400.0));
+    let change = ELO_K * (score - expected);
+    (a + change, b - change)
+}
+
+/// How one bot is doing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Rounds of a Swiss tournament it sat out, worth a win each.
+    pub byes: u32,
+    pub elo: f64,
+    pub skill: Skill,
+}
+
+impl Entry {
+    fn new(name: String) -> Self {
+        Entry { name, wins: 0, draws: 0, losses: 0, byes: 0, elo: ELO_START, skill: Skill::default() }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    pub fn points(&self) -> f64 {
+        (self.wins + self.byes) as f64 + self.draws as f64 / 2.0
+    }
+}
+
+/// The table of a tournament, in the order the bots were entered.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Standings {
+    pub entries: Vec<Entry>,
+}
+
+impl Standings {
+    pub fn new(names: &[String]) -> Self {
+        Standings { entries: names.iter().cloned().map(Entry::new).collect() }
+    }
+
+    /// Counts a game between entries `a` and `b`, won by `a` if `winner`
+    /// is 0, by `b` if it is 1 and drawn if it is `None`.
+    pub fn record(&mut self, a: usize, b: usize, winner: Option<usize>) {
+        let score = match winner {
+            Some(0) => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+        let (elo_a, elo_b) = elo(self.entries[a].elo, self.entries[b].elo, score);
+        self.entries[a].elo = elo_a;
+        self.entries[b].elo = elo_b;
+
+        let (first, second) = if winner == Some(1) { (b, a) } else { (a, b) };
+        let (skill_first, skill_second) = trueskill(self.entries[first].skill, self.entries[second].skill, winner.is_none());
+        self.entries[first].skill = skill_first;
+        self.entries[second].skill = skill_second;
+
+        match winner {
+            Some(_) => {
+                self.entries[first].wins += 1;
+                self.entries[second].losses += 1;
+            }
+            None => {
+                self.entries[a].draws += 1;
+                self.entries[b].draws += 1;
+            }
+        }
+    }
+
+    /// The entries best first: by points, then by rating.
+    pub fn ranking(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.entries.len()).collect();
+        order.sort_by(|a, b| {
+            let (a, b) = (&self.entries[*a], &self.entries[*b]);
+            b.points().total_cmp(&a.points())
+                      .then(b.skill.conservative().total_cmp(&a.skill.conservative()))
+                      .then(b.elo.total_cmp(&a.elo))
+        });
+        order
+    }
+}
+
+impl fmt::Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.entries.iter().map(|entry| entry.name.len()).max().unwrap_or(0).max(3);
+        writeln!(f, "{:>4}  {:<width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>6}  {:>6}  {:>6}  {:>6}  {:>6}",
+                 "rank", "bot", "games", "won", "drew", "lost", "points", "elo", "mu", "sigma", "skill", width = width)?;
+        for (rank, i) in self.ranking().into_iter().enumerate() {
+            let entry = &self.entries[i];
+            writeln!(f, "{:>4}  {:<width$}  {:>5}  {:>4}  {:>4}  {:>4}  {:>6.1}  {:>6.0}  {:>6.2}  {:>6.2}  {:>6.2}",
+                     rank + 1, entry.name, entry.games(), entry.wins, entry.draws, entry.losses, entry.points(),
+                     entry.elo, entry.skill.mu, entry.skill.sigma, entry.skill.conservative(), width = width)?;
+        }
+        Ok(())
+    }
+}
+
+/// Every pair of `count` entrants once.
+pub fn round_robin(count: usize) -> Vec<(usize, usize)> {
+    (0..count).flat_map(|a| (a + 1..count).map(move |b| (a, b))).collect()
+}
+
+/// The pairs of the next Swiss round and the entrant sitting it out, if
+/// there is an odd number of them. The bye goes to the lowest ranked one
+/// that has not had one yet, then the rest are taken best first, each paired
+/// with the next best it has not met.
+pub fn swiss(standings: &Standings, met: &HashSet<(usize, usize)>) -> (Vec<(usize, usize)>, Option<usize>) {
+    let mut order = standings.ranking();
+    let mut bye = None;
+    if order.len() % 2 == 1 {
+        let i = order.iter()
+                     .rposition(|i| standings.entries[*i].byes == 0)
+                     .unwrap_or(order.len() - 1);
+        bye = Some(order.remove(i));
+    }
+    let mut pairs = Vec::new();
+    while !order.is_empty() {
+        let a = order.remove(0);
+        let j = order.iter()
+                     .position(|b| !met.contains(&(a.min(*b), a.max(*b))))
+                     .unwrap_or(0);
+        let b = order.remove(j);
+        pairs.push((a, b));
+    }
+    (pairs, bye)
+}
+
+/// A tournament between bots. Every pairing is played twice on the same
+/// seed, once from each side, and the seeds count up from `seed` one
+/// pairing at a time, so the same entrants always play the same games.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tournament {
+    pub format: Format,
+    /// Times round a round robin, or rounds of a Swiss tournament.
+    pub rounds: u32,
+    pub seed: u64,
+    pub config: DuelConfig,
+}
+
+impl Tournament {
+    /// Plays the tournament between bots called `names`, made fresh for
+    /// every game by `bot`, and hands every finished game to `on_game`.
+    pub fn run<F, G>(&self, names: &[String], mut bot: F, mut on_game: G) -> Standings
+        where F: FnMut(usize) -> Box<dyn Bot>,
+              G: FnMut(&Record) {
+        let mut standings = Standings::new(names);
+        let mut met = HashSet::new();
+        let mut seed = self.seed;
+        for _ in 0..self.rounds {
+            let pairs = match self.format {
+                Format::RoundRobin => round_robin(names.len()),
+                Format::Swiss => {
+                    let (pairs, bye) = swiss(&standings, &met);
+                    if let Some(i) = bye {
+                        standings.entries[i].byes += 1;
+                    }
+                    pairs
+                }
+            };
+            for (a, b) in pairs {
+                met.insert((a.min(b), a.max(b)));
+                for &(first, second) in [(a, b), (b, a)].iter() {
+                    let mut bots = vec![bot(first), bot(second)];
+                    let record = duel::play(self.config, seed, &mut bots,
+                                            vec![names[first].clone(), names[second].clone()]);
+                    standings.record(first, second, record.winner);
+                    on_game(&record);
+                }
+                seed = seed.wrapping_add(1);
+            }
+        }
+        standings
+    }
+}
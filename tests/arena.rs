@@ -0,0 +1,135 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::arena::{ArenaGenerator, ArenaKind, Obstacles, Plain, Symmetric};
+use snake_rust::consts::*;
+use snake_rust::elements::*;
+use snake_rust::grid::{CellKind, Occupancy};
+use snake_rust::replay::Replay;
+use snake_rust::controls::ControlScheme;
+use snake_rust::settings::Settings;
+use snake_rust::topology::Topology;
+
+fn snake() -> Snake {
+    Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into())
+}
+
+fn build(kind: ArenaKind, seed: u64) -> (Wall, Occupancy) {
+    let mut rng = Pcg32::seed_from_u64(seed);
+    let walls = kind.build(&mut rng, GRID_SIZE, &snake(), Topology::Portals);
+    let grid = Occupancy::build(GRID_SIZE, &walls, &snake(), &[]);
+    (walls, grid)
+}
+
+#[test]
+fn arenas_round_trip_through_their_names() {
+    for kind in ArenaKind::ALL.iter() {
+        assert_eq!(kind.to_string().parse::<ArenaKind>(), Ok(*kind));
+    }
+    assert!("dungeon".parse::<ArenaKind>().is_err());
+
+    let mut kind = ArenaKind::Plain;
+    for _ in 0..ArenaKind::ALL.len() {
+        kind = kind.next();
+    }
+    assert_eq!(kind, ArenaKind::Plain);
+}
+
+#[test]
+fn every_floor_cell_can_be_reached_from_the_head() {
+    let snake = snake();
+    for kind in ArenaKind::ALL.iter() {
+        for seed in 0..20 {
+            let (_, grid) = build(*kind, seed);
+            let open = grid.reachable(snake.head_pos());
+
+            let mut free = 0;
+            for y in 1..GRID_SIZE.1 {
+                for x in 0..GRID_SIZE.0 {
+                    let pos = GridPosition::new(x, y);
+                    if grid.get(pos) != CellKind::Wall {
+                        assert!(open.contains(&pos), "{} {}: {:?} cannot be reached", kind, seed, pos.coords());
+                        free += 1;
+                    }
+                }
+            }
+            let area = (GRID_SIZE.0 * (GRID_SIZE.1 - 1)) as f32;
+            assert!(free as f32 >= ARENA_MIN_FREE * area, "{} {}: only {} free cells", kind, seed, free);
+
+            let mut ahead = snake.head_pos();
+            for _ in 0..ARENA_START_LANE {
+                ahead = GridPosition::new_from_move_in(ahead, snake.dir, GRID_SIZE);
+                assert_eq!(grid.get(ahead), CellKind::Empty, "{} {}: the snake starts facing a wall", kind, seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn generators_draw_something_new() {
+    let (plain, _) = build(ArenaKind::Plain, 3);
+    for kind in ArenaKind::ALL.iter().skip(1) {
+        let (walls, _) = build(*kind, 3);
+        assert!(walls.positions().count() > plain.positions().count(), "{} adds no walls", kind);
+    }
+}
+
+#[test]
+fn the_same_seed_builds_the_same_arena() {
+    for kind in ArenaKind::ALL.iter() {
+        let (first, _) = build(*kind, 42);
+        let (second, _) = build(*kind, 42);
+        assert!(first.positions().eq(second.positions()), "{} is not deterministic", kind);
+    }
+    let (first, _) = build(ArenaKind::Caves, 1);
+    let (second, _) = build(ArenaKind::Caves, 2);
+    assert!(!first.positions().eq(second.positions()));
+}
+
+#[test]
+fn symmetric_arenas_are_mirrored_both_ways() {
+    for seed in 0..10 {
+        let (_, grid) = build(ArenaKind::Symmetric, seed);
+        for y in 1..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                let cell = grid.get(GridPosition::new(x, y)) == CellKind::Wall;
+                let across = grid.get(GridPosition::new(GRID_SIZE.0 - 1 - x, y)) == CellKind::Wall;
+                assert_eq!(cell, across, "seed {}: ({}, {}) is not mirrored across", seed, x, y);
+                let below = grid.get(GridPosition::new(x, GRID_SIZE.1 - y)) == CellKind::Wall;
+                assert_eq!(cell, below, "seed {}: ({}, {}) is not mirrored below", seed, x, y);
+            }
+        }
+    }
+}
+
+#[test]
+fn small_boards_get_the_plain_border() {
+    let plain = |seed: u64, size: (i16, i16)| Plain.carve(&mut Pcg32::seed_from_u64(seed), size);
+    for seed in 0..20 {
+        for (generator, min) in [(&Obstacles as &dyn ArenaGenerator, Obstacles::MIN_SIZE),
+                                 (&Symmetric, Symmetric::MIN_SIZE)].iter() {
+            generator.carve(&mut Pcg32::seed_from_u64(seed), *min);
+            for size in [(min.0 - 1, min.1), (min.0, min.1 - 1), (4, 4)].iter() {
+                assert_eq!(generator.carve(&mut Pcg32::seed_from_u64(seed), *size), plain(seed, *size));
+            }
+        }
+        for kind in ArenaKind::ALL.iter() {
+            let snake = Snake::new((2, 3).into());
+            kind.build(&mut Pcg32::seed_from_u64(seed), (6, 6), &snake, Topology::Portals);
+        }
+    }
+}
+
+#[test]
+fn the_arena_is_remembered() {
+    let mut replay = Replay::new(5, ControlScheme::Relative, None);
+    replay.arena = ArenaKind::Maze;
+    let text = replay.to_string();
+    assert!(text.contains("arena maze\n"));
+    assert_eq!(text.parse::<Replay>(), Ok(replay));
+    assert!("seed 1\ncontrols absolute\narena rooms\n".parse::<Replay>().is_err());
+
+    let settings: Settings = "arena = caves".parse().unwrap();
+    assert_eq!(settings.arena, ArenaKind::Caves);
+    assert_eq!(settings.to_string().parse::<Settings>(), Ok(settings));
+}
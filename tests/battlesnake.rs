@@ -0,0 +1,180 @@
+mod common;
+
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::arena::ArenaKind;
+use snake_rust::battlesnake::*;
+use snake_rust::bots::{Bot, BotTier, View};
+use snake_rust::duel::{self, DuelConfig};
+use snake_rust::elements::*;
+use snake_rust::grid::Occupancy;
+use snake_rust::topology::Topology;
+
+use common::bot_board;
+
+type Requests = Arc<Mutex<Vec<(String, GameRequest)>>>;
+
+/// A Battlesnake server on a free local port that answers every request
+/// with `answer` after `delay`, and keeps the path and body of each.
+fn server(answer: &'static str, delay: Duration) -> (Url, Requests) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/", listener.local_addr().unwrap()).parse().unwrap();
+    let requests: Requests = Arc::new(Mutex::new(Vec::new()));
+    let seen = requests.clone();
+    thread::spawn(move || {
+        'requests: for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            let (head, body) = loop {
+                let n = stream.read(&mut buffer).unwrap_or(0);
+                if n == 0 {
+                    continue 'requests;
+                }
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(i) = text.find("\r\n\r\n") {
+                    let length: usize = text.lines()
+                        .find_map(|line| line.strip_prefix("Content-Length: "))
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    if text.len() >= i + 4 + length {
+                        break (text[..i].to_string(), text[i + 4..].to_string());
+                    }
+                }
+            };
+            let path = head.split_whitespace().nth(1).unwrap().to_string();
+            seen.lock().unwrap().push((path, serde_json::from_str(&body).unwrap()));
+            thread::sleep(delay);
+            let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\n\r\n{}", answer);
+        }
+    });
+    (url, requests)
+}
+
+#[test]
+fn urls_need_a_host_and_http() {
+    let url: Url = "http://localhost:8000/snakes/one/".parse().unwrap();
+    assert_eq!(url, Url { host: "localhost".to_string(), port: 8000, path: "/snakes/one".to_string() });
+    assert_eq!(url.to_string(), "http://localhost:8000/snakes/one");
+    assert_eq!("http://127.0.0.1".parse::<Url>().unwrap().port, 80);
+    assert!("https://localhost:8000".parse::<Url>().is_err());
+    assert!("localhost:8000".parse::<Url>().is_err());
+    assert!("http://:8000".parse::<Url>().is_err());
+    assert!("http://localhost:port".parse::<Url>().is_err());
+}
+
+#[test]
+fn the_board_is_turned_the_battlesnake_way_up() {
+    let board = bot_board();
+    let grid = board.grid();
+    let view = View { snake: &board.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 7 };
+    let request = GameRequest::new(&view, "game", Duration::from_millis(300));
+
+    assert_eq!((request.turn, request.game.timeout), (7, 300));
+    assert_eq!((request.game.ruleset.name.as_str(), request.game.map.as_str()), ("wrapped", "portals"));
+    assert_eq!((request.board.width, request.board.height), (10, 4));
+    let body: Vec<(i16, i16)> = request.you.body.iter().map(|c| (c.x, c.y)).collect();
+    assert_eq!(body, vec![(3, 2), (2, 2), (1, 2)]);
+    assert_eq!((request.you.head, request.you.length, request.you.health), (Coord { x: 3, y: 2 }, 3, 100));
+    assert_eq!(request.board.snakes, vec![request.you.clone()]);
+    assert_eq!(request.board.food, vec![Coord { x: 6, y: 1 }]);
+    assert!(request.board.hazards.contains(&Coord { x: 0, y: 0 }));
+    assert!(request.board.hazards.contains(&Coord { x: 0, y: 2 }));
+    assert!(!request.board.hazards.contains(&Coord { x: 4, y: 3 }));
+
+    let json = serde_json::to_value(&request).unwrap();
+    assert_eq!(json["game"]["ruleset"]["settings"]["hazardDamagePerTurn"], 100);
+    assert_eq!(json["you"]["head"], serde_json::json!({"x": 3, "y": 2}));
+}
+
+#[test]
+fn only_straight_wrapping_is_sent_as_wrapped() {
+    let board = bot_board();
+    for (topology, name) in [(Topology::Bounded, "standard"), (Topology::Portals, "wrapped"), (Topology::Torus, "wrapped"),
+                             (Topology::Klein, "wrapped_mirrored"), (Topology::Projective, "wrapped_mirrored")].iter() {
+        let grid = board.grid().with_topology(*topology);
+        let view = View { snake: &board.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 0 };
+        let request = GameRequest::new(&view, "game", Duration::from_millis(300));
+        assert_eq!(request.game.ruleset.name, *name);
+        assert_eq!(request.game.map, topology.to_string());
+    }
+}
+
+#[test]
+fn moves_wrap_the_way_the_wrapped_rules_have_them() {
+    let (width, height) = (7, 6);
+    for topology in [Topology::Portals, Topology::Torus].iter() {
+        let grid = Occupancy::new((width, height)).with_topology(*topology);
+        for y in 1..height {
+            for x in 0..width {
+                let from = Coord::new(GridPosition::new(x, y), height);
+                assert!(from.x < width && from.y >= 0 && from.y < height - 1);
+                for (dir, (dx, dy)) in [(Direction::Up, (0, 1)), (Direction::Down, (0, -1)),
+                                        (Direction::Left, (-1, 0)), (Direction::Right, (1, 0))].iter() {
+                    let to = Coord::new(grid.step(GridPosition::new(x, y), *dir).unwrap(), height);
+                    let wrapped = Coord { x: (from.x + dx).rem_euclid(width), y: (from.y + dy).rem_euclid(height - 1) };
+                    assert_eq!(to, wrapped, "{:?} from ({}, {}) on {}", dir, x, y, topology);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn a_game_starts_moves_and_ends() {
+    let (url, requests) = server(r#"{"move": "up", "shout": "hi"}"#, Duration::from_millis(0));
+    let board = bot_board();
+    let grid = board.grid();
+    let view = View { snake: &board.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 0 };
+    let mut bot = BattlesnakeBot::new(url, Duration::from_secs(5));
+    bot.start(&view);
+    assert_eq!(bot.choose(&view, &mut Pcg32::seed_from_u64(0)), Direction::Up);
+    bot.end(&view);
+
+    let requests = requests.lock().unwrap();
+    let paths: Vec<&str> = requests.iter().map(|(path, _)| path.as_str()).collect();
+    assert_eq!(paths, vec!["/start", "/move", "/end"]);
+    assert!(requests.iter().all(|(_, request)| request.game.id == requests[0].1.game.id));
+}
+
+#[test]
+fn no_answer_in_time_keeps_the_snake_going_straight() {
+    let (url, _) = server(r#"{"move": "up"}"#, Duration::from_secs(2));
+    let board = bot_board();
+    let grid = board.grid();
+    let view = View { snake: &board.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 0 };
+    let mut bot = BattlesnakeBot::new(url, Duration::from_millis(50));
+    let start = Instant::now();
+    assert_eq!(bot.choose(&view, &mut Pcg32::seed_from_u64(0)), Direction::Right);
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    let (url, _) = server("not a move", Duration::from_millis(0));
+    let mut bot = BattlesnakeBot::new(url, Duration::from_secs(5));
+    assert_eq!(bot.choose(&view, &mut Pcg32::seed_from_u64(0)), Direction::Right);
+}
+
+#[test]
+fn battlesnakes_play_duels() {
+    let (url, requests) = server(r#"{"move": "left"}"#, Duration::from_millis(0));
+    let config = DuelConfig { size: (16, 12), speed: 10, seconds: 2, arena: ArenaKind::Plain, topology: Topology::Torus };
+    let mut bots: Vec<Box<dyn Bot>> = vec![Box::new(BattlesnakeBot::new(url, Duration::from_secs(5))), BotTier::Greedy.bot()];
+    let record = duel::play(config, 1, &mut bots, vec!["server".to_string(), "greedy".to_string()]);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.first().unwrap().0, "/start");
+    assert_eq!(requests.last().unwrap().0, "/end");
+    assert_eq!(requests[1].1.board.snakes.len(), 2);
+    // A snake that is out is not asked for moves any more.
+    let moves = requests.iter().filter(|(path, _)| path == "/move").count();
+    assert_eq!(moves, record.moves.iter().filter(|tick| tick[0] != Direction::None).count());
+    assert!(record.moves.iter().all(|tick| tick[0] == Direction::Left || tick[0] == Direction::None));
+}
@@ -0,0 +1,122 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::board::Board;
+use snake_rust::consts::{FOOD_ITEMS, GRID_SIZE};
+use snake_rust::controls::Input;
+use snake_rust::elements::*;
+use snake_rust::food::FoodManager;
+use snake_rust::grid::Occupancy;
+
+/// Plays `inputs` one per tick on `before` and checks the board it ends on.
+fn play(before: &str, inputs: &[Option<Input>], after: &str) {
+    let mut board: Board = before.parse().unwrap();
+    let mut grid = board.grid();
+    for input in inputs {
+        if let Some(input) = input {
+            board.snake.apply_input(*input);
+        }
+        board.snake.update(&mut grid);
+    }
+    assert_eq!(board.to_string(), after.parse::<Board>().unwrap().to_string());
+}
+
+#[test]
+fn game_boards_survive_a_round_trip() {
+    for seed in 0..20 {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let walls = Wall::new(&mut rng);
+        let snake = Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into());
+        let mut foods = FoodManager::new(FOOD_ITEMS, None);
+        foods.fill(&mut rng, &mut Occupancy::build(GRID_SIZE, &walls, &snake, &[]));
+        let board = Board::new(GRID_SIZE, walls, snake, foods.items().to_vec());
+
+        let text = board.to_string();
+        let parsed: Board = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.grid(), board.grid());
+    }
+}
+
+#[test]
+fn a_coiled_snake_survives_a_round_trip() {
+    let text = "\
+.......
+#######
+:..*..:
+:Rrrd.:
+:<lll.:
+#######
+";
+    let board: Board = text.parse().unwrap();
+    assert_eq!(board.snake.length(), 8);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(1, 4));
+    assert_eq!(board.to_string(), text);
+}
+
+#[test]
+fn food_under_the_snake_is_kept() {
+    let text = "\
+.......
+#######
+:$..!.:
+:Rr>.+:
+:.....:
+#######
+mouse 2 3
+";
+    let board: Board = text.parse().unwrap();
+    let foods: Vec<(FoodKind, (i16, i16))> = board.foods.iter().map(|f| (f.kind, f.pos.coords())).collect();
+    assert_eq!(foods, vec![(FoodKind::Golden, (1, 2)), (FoodKind::Poisoned, (4, 2)),
+                           (FoodKind::Bonus, (5, 3)), (FoodKind::Mouse, (2, 3))]);
+    assert_eq!(board.to_string(), text);
+}
+
+#[test]
+fn bad_boards_are_rejected() {
+    assert!("".parse::<Board>().is_err());
+    assert!("..\n...\n".parse::<Board>().is_err());
+    assert!(".R>.\nlizard 1 1\n".parse::<Board>().is_err(), "unknown food");
+    assert!(".R>*?\n".parse::<Board>().is_err(), "unknown cell");
+    assert!("R.>*\n".parse::<Board>().is_err(), "broken snake");
+    assert!("Rr>*r\n".parse::<Board>().is_err(), "loose body piece");
+}
+
+#[test]
+fn snake_eats_the_mouse_in_front() {
+    play("\
+.......
+#######
+:.....:
+:Rr>*.:
+:.....:
+#######
+", &[None], "\
+.......
+#######
+:.....:
+:Rrr>.:
+:.....:
+#######
+mouse 4 3
+");
+}
+
+#[test]
+fn snake_wraps_through_a_hole_and_turns() {
+    play("\
+.......
+#######
+:.....:
+:....R>
+:....*:
+#######
+", &[None, Some(Input::Absolute(Direction::Down))], "\
+.......
+#######
+:.....:
+D.....:
+v....*:
+#######
+");
+}
@@ -0,0 +1,223 @@
+mod common;
+
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::board::Board;
+use snake_rust::bots::{self, BotTier, Rival, View};
+use snake_rust::consts::*;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::grid::{CellKind, Occupancy};
+use snake_rust::modes::ModeKind;
+use snake_rust::replay::Replay;
+use snake_rust::settings::Settings;
+
+use common::board;
+
+fn choose(tier: BotTier, board: &Board, grid: &Occupancy, seed: u64) -> Direction {
+    let view = View { snake: &board.snake, others: Vec::new(), grid, foods: &board.foods, tick: 0 };
+    tier.bot().choose(&view, &mut Pcg32::seed_from_u64(seed))
+}
+
+fn rival_at(x: i16, y: i16, number: u8) -> Snake {
+    let mut snake = Snake::new(GridPosition::new(x, y));
+    snake.rival = Some(number);
+    snake
+}
+
+#[test]
+fn tiers_round_trip_through_their_names() {
+    for tier in BotTier::ALL.iter() {
+        assert_eq!(tier.to_string().parse::<BotTier>(), Ok(*tier));
+    }
+    assert_eq!(BotTier::LookAhead.next(), BotTier::RandomSafe);
+    assert!("smart".parse::<BotTier>().is_err());
+}
+
+#[test]
+fn rivals_keep_their_cells_apart() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "#R>......#",
+        "#........#",
+        "##########",
+    ]);
+    let mut grid = board.grid();
+    let rival = rival_at(5, 3, 1);
+    grid.place_snake(&rival);
+    assert_eq!(grid.get(GridPosition::new(5, 3)), CellKind::Rival(1));
+    assert_eq!(grid.get(GridPosition::new(4, 3)), CellKind::Rival(1));
+    assert_eq!(grid.get(GridPosition::new(2, 2)), CellKind::Snake);
+
+    grid.clear_snake(&rival);
+    assert_eq!(grid.get(GridPosition::new(5, 3)), CellKind::Empty);
+    assert_eq!(grid.get(GridPosition::new(2, 2)), CellKind::Snake);
+}
+
+#[test]
+fn running_into_another_snake_is_fatal() {
+    let mut board = board(&[
+        "..........",
+        "##########",
+        "#R>......#",
+        "#........#",
+        "##########",
+    ]);
+    let mut grid = board.grid();
+    let mut rival = rival_at(4, 3, 0);
+    grid.place_snake(&rival);
+
+    rival.steer(Direction::Up);
+    rival.update(&mut grid);
+    assert_eq!(rival.ate, None);
+    assert_eq!(grid.get(GridPosition::new(4, 2)), CellKind::Rival(0));
+
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, None);
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, Some(Ate::OtherSnake));
+    assert!(ModeKind::Classic.rules().is_over(board.snake.ate, 1.0));
+
+    // The rival's head-on move into the player is just as deadly for it.
+    rival.steer(Direction::Left);
+    rival.update(&mut grid);
+    assert_eq!(rival.ate, Some(Ate::OtherSnake));
+}
+
+#[test]
+fn zen_games_have_no_rivals_to_run_into() {
+    for mode in [ModeKind::Classic, ModeKind::Zen].iter() {
+        let rules = mode.rules();
+        let mut snake = rules.snake();
+        let walls = Wall::from_positions(Vec::new());
+        let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]);
+        let rivals = bots::spawn(rules.rivals(MAX_RIVALS), BotTier::Greedy, &mut grid);
+
+        // Straight along the middle row, where the third rival starts.
+        let mut crashed = false;
+        for _ in 0..GRID_SIZE.0 {
+            rules.prepare(&mut snake);
+            snake.update(&mut grid);
+            crashed |= snake.ate == Some(Ate::OtherSnake);
+            if rules.is_over(snake.ate, 1.0) {
+                break;
+            }
+        }
+        if *mode == ModeKind::Zen {
+            assert!(rivals.is_empty());
+            assert!(!crashed);
+            assert!(snake.positions().all(|pos| grid.get(pos) == CellKind::Snake));
+        } else {
+            assert_eq!(rivals.len(), MAX_RIVALS as usize);
+            assert!(crashed && rules.is_over(snake.ate, 1.0));
+        }
+    }
+}
+
+#[test]
+fn every_tier_stays_alive_in_a_corridor() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "#Rr>.....#",
+        "##########",
+    ]);
+    let grid = board.grid();
+    for tier in BotTier::ALL.iter() {
+        for seed in 0..20 {
+            assert_eq!(choose(*tier, &board, &grid, seed), Direction::Right, "{} with seed {}", tier, seed);
+        }
+    }
+}
+
+#[test]
+fn a_loaded_rival_gets_a_bot_of_its_tier() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "#Rr>.....#",
+        "##########",
+    ]);
+    let grid = board.grid();
+    let json = serde_json::to_value(Rival::new(board.snake.clone(), BotTier::LookAhead)).unwrap();
+    assert!(json.get("bot").is_none());
+
+    let mut rival: Rival = serde_json::from_value(json).unwrap();
+    assert_eq!(rival.tier, BotTier::LookAhead);
+    let view = View { snake: &rival.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 0 };
+    assert_eq!(rival.bot.choose(&view, &mut Pcg32::seed_from_u64(0)), Direction::Right);
+}
+
+#[test]
+fn greedy_heads_for_the_nearest_mouse() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "#........#",
+        "#Rr>.....#",
+        "#......*.#",
+        "##########",
+    ]);
+    let grid = board.grid();
+    assert_eq!(choose(BotTier::Greedy, &board, &grid, 0), Direction::Down);
+}
+
+#[test]
+fn look_ahead_keeps_out_of_dead_ends() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "####*#####",
+        "#Rrr>....#",
+        "#........#",
+        "##########",
+    ]);
+    let grid = board.grid();
+    assert_eq!(choose(BotTier::Greedy, &board, &grid, 0), Direction::Up);
+    for seed in 0..20 {
+        assert_ne!(choose(BotTier::LookAhead, &board, &grid, seed), Direction::Up);
+    }
+}
+
+#[test]
+fn rivals_spawn_on_free_cells() {
+    let snake = ModeKind::Classic.rules().snake();
+    let walls = Wall::new(&mut Pcg32::seed_from_u64(3));
+    let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]);
+    let before = grid.clone();
+
+    let rivals = bots::spawn(5, BotTier::Greedy, &mut grid);
+    assert_eq!(rivals.len(), MAX_RIVALS as usize);
+    for (i, rival) in rivals.iter().enumerate() {
+        assert_eq!(rival.snake.rival, Some(i as u8));
+        for pos in rival.snake.positions() {
+            assert_eq!(before.get(pos), CellKind::Empty);
+            assert_eq!(grid.get(pos), CellKind::Rival(i as u8));
+        }
+    }
+
+    let mut again = before.clone();
+    let positions: Vec<GridPosition> = bots::spawn(5, BotTier::Greedy, &mut again)
+        .iter()
+        .map(|rival| rival.snake.head_pos())
+        .collect();
+    assert_eq!(positions, rivals.iter().map(|rival| rival.snake.head_pos()).collect::<Vec<_>>());
+}
+
+#[test]
+fn the_rivals_are_remembered() {
+    let mut replay = Replay::new(5, ControlScheme::Absolute, None);
+    replay.rivals = 2;
+    replay.rival_tier = BotTier::LookAhead;
+    let text = replay.to_string();
+    assert!(text.contains("rivals 2 lookahead\n"));
+    assert_eq!(text.parse::<Replay>(), Ok(replay));
+    assert!("seed 1\ncontrols absolute\nrivals two greedy\n".parse::<Replay>().is_err());
+
+    let settings: Settings = "rivals = 9\nrival_tier = random".parse().unwrap();
+    assert_eq!(settings.rivals, MAX_RIVALS);
+    assert_eq!(settings.rival_tier, BotTier::RandomSafe);
+    assert_eq!(settings.to_string().parse::<Settings>(), Ok(settings));
+}
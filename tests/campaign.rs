@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::Path;
+
+use snake_rust::campaign::{Campaign, Clear, Level, Progress, Target};
+use snake_rust::consts::GRID_SIZE;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::grid::CellKind;
+use snake_rust::modes::GameMode;
+use snake_rust::replay::Replay;
+
+fn shipped_levels() -> Campaign {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/levels");
+    let mut levels = Vec::new();
+    loop {
+        let path = dir.join(format!("{:02}.txt", levels.len() + 1));
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => break,
+        };
+        let level: Level = text.parse().unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        levels.push(level);
+    }
+    Campaign::new(levels)
+}
+
+#[test]
+fn the_campaign_has_twenty_playable_levels() {
+    let campaign = shipped_levels();
+    assert!(campaign.levels().len() >= 20, "only {} levels", campaign.levels().len());
+
+    for (i, level) in campaign.levels().iter().enumerate() {
+        let name = format!("level {} ({})", i + 1, level.name);
+        assert!(level.stars.0 <= level.stars.1 && level.stars.1 <= level.time, "{}: stars after the time limit", name);
+
+        let grid = level.board.grid();
+        for x in 0..GRID_SIZE.0 {
+            for y in [1, GRID_SIZE.1 - 1].iter() {
+                assert_eq!(grid.get(GridPosition::new(x, *y)), CellKind::Wall, "{}: a hole in the top or bottom row", name);
+            }
+        }
+        let head = level.board.snake.head_pos();
+        for step in 1..=2 {
+            let mut pos = head;
+            for _ in 0..step {
+                pos = GridPosition::new_from_move_in(pos, level.board.snake.dir, GRID_SIZE);
+            }
+            assert_eq!(grid.get(pos), CellKind::Empty, "{}: the snake starts facing a wall", name);
+        }
+
+        // Every door opened, as a player who found the keys and switches
+        // would see the board.
+        let mut grid = grid;
+        for door in level.board.walls.doors() {
+            grid.set(door.pos, CellKind::Empty);
+        }
+        let open = grid.reachable(head);
+        for y in 1..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                let pos = GridPosition::new(x, y);
+                if grid.get(pos) != CellKind::Wall {
+                    assert!(open.contains(&pos), "{}: {:?} cannot be reached", name, pos.coords());
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn levels_survive_a_round_trip() {
+    for level in shipped_levels().levels() {
+        let text = level.to_string();
+        let parsed: Level = text.parse().unwrap();
+        assert_eq!(parsed.to_string(), text);
+        assert_eq!(parsed.board.grid(), level.board.grid());
+    }
+}
+
+#[test]
+fn bad_levels_are_refused() {
+    let board = shipped_levels().levels()[0].board.to_string();
+    let without = |key: &str| {
+        let header = ["name Test", "target points 3", "time 60", "stars 20 40"];
+        let kept: Vec<&str> = header.iter().cloned().filter(|line| !line.starts_with(key)).collect();
+        format!("{}\n{}", kept.join("\n"), board)
+    };
+    assert!(without("none").parse::<Level>().is_ok());
+    assert!(without("name").parse::<Level>().is_err());
+    assert!(without("target").parse::<Level>().is_err());
+    assert!(without("time").parse::<Level>().is_err());
+    assert_eq!(without("stars").parse::<Level>().unwrap().stars, (30, 60));
+
+    assert!("name Small\ntarget points 3\ntime 60\n....\n#Rv#\n#..#\n####".parse::<Level>().is_err());
+    assert!(format!("name Test\ntarget score 3\ntime 60\n{}", board).parse::<Level>().is_err());
+    assert!(format!("name Test\ntarget points 3\ntime 60\nstars 50 20\n{}", board).parse::<Level>().is_err());
+
+    // A misspelled key is named, not read as a row of the board.
+    let typo = format!("name Test\ntarget points 3\ntme 60\n{}", board).parse::<Level>();
+    assert_eq!(typo.unwrap_err(), "unknown key: tme");
+    let late = format!("name Test\ntarget points 3\n{}\ntime 60", board).parse::<Level>();
+    assert!(late.is_err());
+}
+
+#[test]
+fn targets_stars_and_time_limits() {
+    let mut level = shipped_levels().levels()[0].clone();
+    level.target = Target::Length(6);
+    level.time = 90;
+    level.stars = (30, 60);
+
+    assert!(!level.is_won(100, 5));
+    assert!(level.is_won(0, 6));
+    assert_eq!("points 12".parse::<Target>(), Ok(Target::Points(12)));
+    assert!(Target::Points(12).reached(12, 2));
+
+    assert_eq!(level.stars(30.0), 3);
+    assert_eq!(level.stars(30.1), 2);
+    assert_eq!(level.stars(89.0), 1);
+    assert!(!level.is_over(None, 89.9));
+    assert!(level.is_over(None, 90.0));
+    assert!(level.is_over(Some(Ate::Itself), 1.0));
+
+    let head = level.board.snake.head_pos();
+    assert_eq!(level.snake().head_pos(), head);
+}
+
+#[test]
+fn clearing_a_level_unlocks_the_next() {
+    let mut progress = Progress::default();
+    assert!(progress.is_unlocked(0));
+    assert!(!progress.is_unlocked(1));
+
+    assert!(progress.record(0, Clear { stars: 2, points: 10, seconds: 50.0 }));
+    assert!(progress.is_unlocked(1));
+    assert!(!progress.is_unlocked(2));
+
+    assert!(!progress.record(0, Clear { stars: 1, points: 30, seconds: 20.0 }));
+    assert!(progress.record(0, Clear { stars: 2, points: 10, seconds: 45.5 }));
+    assert!(progress.record(1, Clear { stars: 3, points: 12, seconds: 30.0 }));
+    assert_eq!(progress.best(0).unwrap().seconds, 45.5);
+    assert_eq!(progress.stars(), 5);
+
+    let text = progress.to_string();
+    assert_eq!(text, "1 2 10 45.5\n2 3 12 30\n");
+    assert_eq!(text.parse::<Progress>(), Ok(progress));
+    assert!("0 3 10 4".parse::<Progress>().is_err());
+    assert!("1 3 10".parse::<Progress>().is_err());
+}
+
+#[test]
+fn replays_remember_the_level() {
+    let mut replay = Replay::new(5, ControlScheme::Relative, None);
+    replay.level = Some(6);
+    let text = replay.to_string();
+    assert!(text.contains("level 7\n"));
+    assert_eq!(text.parse::<Replay>(), Ok(replay));
+    assert!("seed 1\ncontrols absolute\nlevel 0\n".parse::<Replay>().is_err());
+}
@@ -0,0 +1,21 @@
+//! Helpers shared by the integration tests. Not every test uses all of them.
+#![allow(dead_code)]
+
+use snake_rust::board::Board;
+
+/// A board drawn row by row, the way levels are written.
+pub fn board(rows: &[&str]) -> Board {
+    rows.join("\n").parse().unwrap()
+}
+
+/// The board the bot tests send out: a hole in the top wall, the snake
+/// heading right and a mouse on the open row under it.
+pub fn bot_board() -> Board {
+    board(&[
+        "..........",
+        "####.#####",
+        "#Rr>.....#",
+        "......*...",
+        "##########",
+    ])
+}
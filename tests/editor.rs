@@ -0,0 +1,120 @@
+use snake_rust::campaign::Level;
+use snake_rust::consts::GRID_SIZE;
+use snake_rust::editor::{Editor, Problem, Tool};
+use snake_rust::elements::*;
+
+fn walls(editor: &Editor) -> usize {
+    editor.level().board.walls.positions().count()
+}
+
+fn stroke(editor: &mut Editor, tool: Tool, cells: &[(i16, i16)]) {
+    editor.begin_stroke();
+    for cell in cells {
+        editor.paint((*cell).into(), tool);
+    }
+    editor.end_stroke();
+}
+
+#[test]
+fn a_blank_level_is_playable() {
+    let editor = Editor::blank();
+    assert!(editor.problems().is_empty());
+    let text = editor.level().to_string();
+    assert_eq!(text.parse::<Level>().unwrap().to_string(), text);
+}
+
+#[test]
+fn strokes_are_undone_and_redone_whole() {
+    let mut editor = Editor::blank();
+    let before = walls(&editor);
+    stroke(&mut editor, Tool::Wall, &[(12, 4), (13, 4), (14, 4), (14, 4)]);
+    assert_eq!(walls(&editor), before + 3);
+
+    stroke(&mut editor, Tool::Hole, &[(13, 4), (0, 8)]);
+    assert_eq!(walls(&editor), before + 1);
+
+    editor.undo();
+    assert_eq!(walls(&editor), before + 3);
+    editor.undo();
+    assert_eq!(walls(&editor), before);
+    editor.undo();
+    assert_eq!(walls(&editor), before);
+
+    editor.redo();
+    editor.redo();
+    assert_eq!(walls(&editor), before + 1);
+
+    editor.undo();
+    stroke(&mut editor, Tool::Mouse, &[(20, 10)]);
+    editor.redo();
+    assert_eq!(walls(&editor), before + 3, "a new stroke drops what could be redone");
+    assert_eq!(editor.level().board.foods.len(), 1);
+}
+
+#[test]
+fn strokes_that_change_nothing_are_not_kept() {
+    let mut editor = Editor::blank();
+    stroke(&mut editor, Tool::Hole, &[(10, 10)]);
+    stroke(&mut editor, Tool::Wall, &[(10, 0)]);
+    stroke(&mut editor, Tool::Wall, &[(12, 4)]);
+    editor.undo();
+    editor.undo();
+    assert_eq!(walls(&editor), Editor::blank().level().board.walls.positions().count());
+    editor.redo();
+    assert!(editor.level().board.walls.positions().any(|pos| pos == GridPosition::new(12, 4)));
+}
+
+#[test]
+fn walls_and_mice_keep_off_the_snake() {
+    let mut editor = Editor::blank();
+    let head = editor.level().board.snake.head_pos();
+    stroke(&mut editor, Tool::Wall, &[head.coords()]);
+    stroke(&mut editor, Tool::Mouse, &[head.coords(), (20, 10)]);
+    assert!(!editor.level().board.walls.positions().any(|pos| pos == head));
+    assert_eq!(editor.level().board.foods.len(), 1);
+
+    stroke(&mut editor, Tool::Wall, &[(20, 10)]);
+    assert!(editor.level().board.foods.is_empty(), "a wall replaces the mouse under it");
+}
+
+#[test]
+fn the_snake_moves_and_turns() {
+    let mut editor = Editor::blank();
+    stroke(&mut editor, Tool::Snake, &[(15, 12)]);
+    let snake = &editor.level().board.snake;
+    assert_eq!(snake.head_pos(), GridPosition::new(15, 12));
+    assert_eq!(snake.positions().nth(1), Some(GridPosition::new(14, 12)));
+
+    stroke(&mut editor, Tool::Snake, &[(15, 12)]);
+    let snake = &editor.level().board.snake;
+    assert_eq!(snake.dir, Direction::Down);
+    assert_eq!(snake.positions().nth(1), Some(GridPosition::new(15, 11)));
+
+    // Placed on the wall, the snake knocks a hole in it.
+    stroke(&mut editor, Tool::Snake, &[(GRID_SIZE.0 - 1, 8)]);
+    assert!(!editor.level().board.walls.positions().any(|pos| pos == GridPosition::new(GRID_SIZE.0 - 1, 8)));
+}
+
+#[test]
+fn closed_off_cells_and_a_deadly_start_are_flagged() {
+    let mut editor = Editor::blank();
+    // A box around (20, 5) cuts it off.
+    stroke(&mut editor, Tool::Wall, &[(19, 4), (20, 4), (21, 4), (19, 5), (21, 5), (19, 6), (20, 6), (21, 6)]);
+    assert_eq!(editor.problems(), vec![Problem::Unreachable(vec![GridPosition::new(20, 5)])]);
+
+    // Knocking a hole in the box opens it up again, once the stroke is done.
+    editor.begin_stroke();
+    editor.paint((20, 6).into(), Tool::Hole);
+    assert_eq!(editor.problems().len(), 1);
+    editor.end_stroke();
+    assert!(editor.problems().is_empty());
+
+    let head = editor.level().board.snake.head_pos();
+    let ahead = GridPosition::new_from_move(head, editor.level().board.snake.dir);
+    stroke(&mut editor, Tool::Wall, &[ahead.coords()]);
+    assert_eq!(editor.problems(), vec![Problem::FatalStart]);
+    assert!(editor.fatal_start());
+
+    editor.undo();
+    assert!(!editor.fatal_start());
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use std::time::Duration;
+
+use snake_rust::board::Board;
+use snake_rust::bots::View;
+use snake_rust::elements::*;
+use snake_rust::external::{parse_move, ExternalBot, TickState};
+
+use common::bot_board;
+
+fn view(board: &Board, grid: &snake_rust::grid::Occupancy) -> TickState {
+    TickState::new(&View { snake: &board.snake, others: Vec::new(), grid, foods: &board.foods, tick: 7 })
+}
+
+#[test]
+fn moves_are_read_plain_or_as_json() {
+    assert_eq!(parse_move("up\n"), Some(Direction::Up));
+    assert_eq!(parse_move(" LEFT "), Some(Direction::Left));
+    assert_eq!(parse_move("\"down\""), Some(Direction::Down));
+    assert_eq!(parse_move(r#"{"move": "right", "shout": "hi"}"#), Some(Direction::Right));
+    assert_eq!(parse_move("north"), None);
+    assert_eq!(parse_move(r#"{"move": 3}"#), None);
+    assert_eq!(parse_move("{not json"), None);
+    assert_eq!(parse_move(""), None);
+}
+
+#[test]
+fn the_state_has_the_whole_board() {
+    let board = bot_board();
+    let grid = board.grid();
+    let state = view(&board, &grid);
+    assert_eq!((state.tick, state.width, state.height), (7, 10, 5));
+    assert_eq!(state.edges, "portals");
+    assert_eq!(state.you.segments, vec![(3, 2), (2, 2), (1, 2)]);
+    assert_eq!(state.you.dir, "right");
+    assert!(state.snakes.is_empty());
+    assert!(state.walls.contains(&(0, 2)) && !state.walls.contains(&(4, 1)));
+    assert_eq!(state.holes, vec![(4, 1), (0, 3), (9, 3)]);
+    assert_eq!(state.food.len(), 1);
+    assert_eq!((state.food[0].x, state.food[0].y, state.food[0].kind.as_str()), (6, 3, "mouse"));
+
+    let line = serde_json::to_string(&state).unwrap();
+    assert!(!line.contains('\n'));
+    assert!(line.contains(r#""you":{"segments":[[3,2],[2,2],[1,2]],"dir":"right"}"#));
+    assert_eq!(serde_json::from_str::<TickState>(&line).unwrap(), state);
+}
+
+#[test]
+fn food_kinds_keep_their_names() {
+    let mut board = bot_board();
+    let grid = board.grid();
+    for (kind, name) in [(FoodKind::Mouse, "mouse"), (FoodKind::Golden, "golden"),
+                         (FoodKind::Poisoned, "poisoned"), (FoodKind::Bonus, "bonus")].iter() {
+        board.foods[0].kind = *kind;
+        assert_eq!(view(&board, &grid).food[0].kind, *name);
+    }
+}
+
+#[cfg(unix)]
+fn ask(bot: &mut ExternalBot) -> Direction {
+    let board = bot_board();
+    let grid = board.grid();
+    let view = View { snake: &board.snake, others: Vec::new(), grid: &grid, foods: &board.foods, tick: 0 };
+    snake_rust::bots::Bot::choose(bot, &view, &mut <rand_pcg::Pcg32 as rand::SeedableRng>::seed_from_u64(0))
+}
+
+#[cfg(unix)]
+#[test]
+fn a_bot_answers_every_tick() {
+    let mut bot = ExternalBot::spawn("sh -c 'while read state; do echo up; done'", Duration::from_secs(5)).unwrap();
+    assert_eq!(ask(&mut bot), Direction::Up);
+    assert_eq!(ask(&mut bot), Direction::Up);
+}
+
+#[cfg(unix)]
+#[test]
+fn nonsense_keeps_the_snake_going_straight() {
+    // `cat` sends the state straight back, which is no move at all.
+    let mut bot = ExternalBot::spawn("cat", Duration::from_secs(5)).unwrap();
+    assert_eq!(ask(&mut bot), Direction::Right);
+}
+
+#[cfg(unix)]
+#[test]
+fn a_slow_bot_is_not_waited_for() {
+    let mut bot = ExternalBot::spawn("sleep 10", Duration::from_millis(50)).unwrap();
+    let start = std::time::Instant::now();
+    assert_eq!(ask(&mut bot), Direction::Right);
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[cfg(unix)]
+#[test]
+fn late_answers_are_thrown_away() {
+    let script = "sh -c 'read state; sleep 0.3; echo down; while read state; do echo up; done'";
+    let mut bot = ExternalBot::spawn(script, Duration::from_millis(100)).unwrap();
+    assert_eq!(ask(&mut bot), Direction::Right);
+    std::thread::sleep(Duration::from_millis(400));
+    assert_eq!(ask(&mut bot), Direction::Up);
+}
+
+#[test]
+fn a_missing_program_is_an_error() {
+    assert!(ExternalBot::spawn("no-such-snake-bot-anywhere", Duration::from_millis(10)).is_err());
+    assert!(ExternalBot::spawn("  ", Duration::from_millis(10)).is_err());
+}
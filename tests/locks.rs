@@ -0,0 +1,115 @@
+mod common;
+
+use snake_rust::board::Board;
+use snake_rust::elements::*;
+use snake_rust::grid::CellKind;
+use snake_rust::locks;
+use snake_rust::tiles::Tile;
+
+use common::board;
+
+/// Moves the snake one tick and works the keys and switches after it.
+fn tick(board: &mut Board, grid: &mut snake_rust::grid::Occupancy) -> Option<u8> {
+    board.snake.update(grid);
+    locks::update(&board.snake, &mut board.walls, grid)
+}
+
+#[test]
+fn doors_and_their_groups_round_trip() {
+    let text = [
+        "..........",
+        "##########",
+        "#R>k.K...#",
+        "#...o.G..#",
+        "#..k..g..#",
+        "##########",
+        "group 2 3 4",
+        "group 2 5 2",
+        "",
+    ].join("\n");
+    let board: Board = text.parse().unwrap();
+    assert_eq!(board.tiles.get(GridPosition::new(3, 2)), Some(Tile::Key(0)));
+    assert_eq!(board.tiles.get(GridPosition::new(3, 4)), Some(Tile::Key(2)));
+    assert_eq!(board.tiles.get(GridPosition::new(4, 3)), Some(Tile::Switch(0)));
+
+    let doors: Vec<&Door> = board.walls.doors().collect();
+    assert_eq!(doors.len(), 3);
+    assert!(doors.iter().any(|door| door.pos == GridPosition::new(5, 2) && door.group == 2 && !door.open));
+    assert!(doors.iter().any(|door| door.pos == GridPosition::new(6, 4) && door.open));
+
+    let grid = board.grid();
+    assert_eq!(grid.get(GridPosition::new(5, 2)), CellKind::Wall);
+    assert_eq!(grid.get(GridPosition::new(6, 3)), CellKind::Wall);
+    assert_eq!(grid.get(GridPosition::new(6, 4)), CellKind::Empty);
+    assert_eq!(board.to_string(), text);
+
+    assert!("#R>Kx#".parse::<Board>().is_err());
+    assert!("..\n#R>\ngroup two 1 1".parse::<Board>().is_err());
+}
+
+#[test]
+fn a_key_opens_the_doors_of_its_group() {
+    let mut board = board(&[
+        "..........",
+        "##########",
+        "#R>k..K..#",
+        "#.....K..#",
+        "##########",
+        "group 1 6 3",
+    ]);
+    let mut grid = board.grid();
+    assert_eq!(tick(&mut board, &mut grid), Some(0));
+    assert_eq!(grid.tile(GridPosition::new(3, 2)), None);
+    assert_eq!(grid.get(GridPosition::new(6, 2)), CellKind::Empty);
+    assert_eq!(grid.get(GridPosition::new(6, 3)), CellKind::Wall, "another group's door opened");
+    assert_eq!(board.walls.doors().count(), 1);
+
+    for _ in 0..4 {
+        assert_eq!(tick(&mut board, &mut grid), None);
+        assert_eq!(board.snake.ate, None);
+    }
+    assert_eq!(board.snake.head_pos(), GridPosition::new(7, 2));
+}
+
+#[test]
+fn shut_doors_are_walls() {
+    let mut board = board(&[
+        "..........",
+        "##########",
+        "#R>K.....#",
+        "##########",
+    ]);
+    let mut grid = board.grid();
+    tick(&mut board, &mut grid);
+    assert_eq!(board.snake.ate, Some(Ate::Wall));
+}
+
+#[test]
+fn gates_stand_open_while_a_switch_is_held() {
+    let mut board = board(&[
+        "..........",
+        "##########",
+        "#R>o.G.g.#",
+        "##########",
+    ]);
+    let gate = GridPosition::new(5, 2);
+    let open_gate = GridPosition::new(7, 2);
+    let mut grid = board.grid();
+
+    tick(&mut board, &mut grid);
+    assert_eq!(grid.get(gate), CellKind::Empty);
+    assert_eq!(grid.get(open_gate), CellKind::Wall, "a gate open at rest shuts while held");
+
+    // The tail leaves the switch as the head goes through the gate, which
+    // waits for the snake to pass before it shuts.
+    tick(&mut board, &mut grid);
+    tick(&mut board, &mut grid);
+    assert_eq!(board.snake.ate, None);
+    assert_eq!(board.snake.head_pos(), gate);
+    assert_eq!(grid.get(open_gate), CellKind::Empty);
+    tick(&mut board, &mut grid);
+    assert_eq!(grid.get(gate), CellKind::Snake);
+    tick(&mut board, &mut grid);
+    assert_eq!(board.snake.head_pos(), open_gate);
+    assert_eq!(grid.get(gate), CellKind::Wall);
+}
@@ -0,0 +1,121 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::board::Board;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::food::{FoodManager, MouseMoves};
+use snake_rust::replay::Replay;
+
+const STEP_EVERY_TICK: MouseMoves = MouseMoves { every: 1, breed_every: 0 };
+
+fn distance(a: GridPosition, b: GridPosition) -> i16 {
+    let (ax, ay) = a.coords();
+    let (bx, by) = b.coords();
+    (ax - bx).abs() + (ay - by).abs()
+}
+
+/// Runs one tick of mouse moves on `board` and returns where the mice are.
+fn tick(board: &str, moves: MouseMoves, seed: u64) -> Vec<GridPosition> {
+    let board: Board = board.parse().unwrap();
+    let mut grid = board.grid();
+    let mut foods = FoodManager::new(0, Some(moves));
+    for food in board.foods.iter() {
+        foods.place(food.clone(), &mut grid);
+    }
+    let mut rng = Pcg32::seed_from_u64(seed);
+    foods.update(1, board.snake.head_pos(), &mut rng, &mut grid);
+    foods.items().iter().map(|food| food.pos).collect()
+}
+
+#[test]
+fn mice_run_from_a_close_snake() {
+    let board = "\
+.........
+#########
+:.......:
+:Rr>.*..:
+:.......:
+#########
+";
+    let head = GridPosition::new(3, 3);
+    for seed in 0..50 {
+        let mice = tick(board, STEP_EVERY_TICK, seed);
+        assert_eq!(distance(mice[0], head), 3, "seed {}", seed);
+    }
+}
+
+#[test]
+fn mice_do_not_walk_into_walls_or_the_snake() {
+    let board = "\
+.........
+#########
+:.#.....:
+:#*Rrr>.:
+:.#.....:
+#########
+";
+    for seed in 0..50 {
+        assert_eq!(tick(board, STEP_EVERY_TICK, seed), vec![GridPosition::new(2, 3)]);
+    }
+}
+
+#[test]
+fn mice_use_holes_in_the_wall() {
+    let board = "\
+.........
+#########
+#.......#
+*#R>....:
+#.......#
+#########
+";
+    let moved: Vec<GridPosition> = (0..50).flat_map(|seed| tick(board, STEP_EVERY_TICK, seed)).collect();
+    assert!(moved.iter().all(|pos| *pos == GridPosition::new(0, 3) || *pos == GridPosition::new(8, 3)));
+    assert!(moved.contains(&GridPosition::new(8, 3)));
+}
+
+#[test]
+fn mice_see_the_snake_across_the_edge() {
+    // Three moves away through the holes, though the whole board away
+    // straight across.
+    let board = "\
+.........
+#########
+#.......#
+:*....R>:
+#.......#
+#########
+";
+    let away = [GridPosition::new(2, 3), GridPosition::new(1, 2), GridPosition::new(1, 4)];
+    for seed in 0..50 {
+        let mice = tick(board, STEP_EVERY_TICK, seed);
+        assert!(away.contains(&mice[0]), "seed {}: {:?}", seed, mice[0].coords());
+    }
+}
+
+#[test]
+fn mice_breed_next_to_a_parent() {
+    let board = "\
+.........
+#########
+:.......:
+:.....*.:
+:R>.....:
+#########
+";
+    let mice = tick(board, MouseMoves { every: 1000, breed_every: 1 }, 7);
+    assert_eq!(mice.len(), 2);
+    assert_eq!(distance(mice[0], mice[1]), 1);
+}
+
+#[test]
+fn replays_remember_how_mice_move() {
+    let mut replay = Replay::new(42, ControlScheme::Relative, Some(MouseMoves { every: 3, breed_every: 50 }));
+    replay.record(5, "turn-left".parse().unwrap());
+    let text = replay.to_string();
+    assert_eq!(text.parse::<Replay>().unwrap(), replay);
+
+    let still = Replay::new(42, ControlScheme::Absolute, None);
+    assert_eq!(still.to_string().parse::<Replay>().unwrap(), still);
+}
@@ -0,0 +1,157 @@
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg32;
+
+use snake_rust::arena::ArenaKind;
+use snake_rust::topology::Topology;
+use snake_rust::consts::*;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::grid::{CellKind, Occupancy};
+use snake_rust::modes::{GameMode, ModeKind};
+use snake_rust::replay::Replay;
+use snake_rust::scores::HighScores;
+
+fn start(mode: ModeKind, seed: u64) -> (Box<dyn GameMode>, Wall, Snake, Occupancy) {
+    let rules = mode.rules();
+    let mut rng = Pcg32::seed_from_u64(seed);
+    let walls = rules.walls(&mut rng, ArenaKind::Plain, Topology::Portals);
+    let snake = Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into());
+    let grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]);
+    (rules, walls, snake, grid)
+}
+
+#[test]
+fn modes_round_trip_through_their_names() {
+    for mode in ModeKind::ALL.iter() {
+        assert_eq!(mode.to_string().parse::<ModeKind>(), Ok(*mode));
+    }
+    assert!("arcade".parse::<ModeKind>().is_err());
+
+    let mut mode = ModeKind::Classic;
+    for _ in 0..ModeKind::ALL.len() {
+        mode = mode.next();
+    }
+    assert_eq!(mode, ModeKind::Classic);
+}
+
+#[test]
+fn classic_ends_on_a_crash_only() {
+    let rules = ModeKind::Classic.rules();
+    assert!(rules.is_over(Some(Ate::Wall), 1.0));
+    assert!(rules.is_over(Some(Ate::Itself), 1.0));
+    assert!(!rules.is_over(Some(Ate::BrokenWall), 1.0));
+    assert!(!rules.is_over(None, 10_000.0));
+    assert_eq!(rules.points(FoodKind::Golden), FoodKind::Golden.points());
+}
+
+#[test]
+fn time_attack_counts_mice_until_the_time_is_up() {
+    let rules = ModeKind::TimeAttack.rules();
+    assert_eq!(rules.points(FoodKind::Golden), 1);
+    assert_eq!(rules.points(FoodKind::Poisoned), 0);
+    assert!(!rules.is_over(None, TIME_ATTACK_SECONDS - 0.1));
+    assert!(rules.is_over(None, TIME_ATTACK_SECONDS));
+    assert!(rules.is_over(Some(Ate::Wall), 1.0));
+    assert_eq!(rules.hud(TIME_ATTACK_SECONDS - 30.5).as_deref(), Some("TIME 31s"));
+    assert_eq!(rules.hud(TIME_ATTACK_SECONDS + 3.0).as_deref(), Some("TIME 0s"));
+}
+
+#[test]
+fn survival_walls_close_in_around_the_snake() {
+    let (rules, mut walls, snake, mut grid) = start(ModeKind::Survival, 3);
+    let body: Vec<GridPosition> = snake.positions().collect();
+    let border = walls.positions().count();
+
+    for tick in 1..SURVIVAL_RING_TICKS {
+        rules.update(tick, &mut walls, &mut grid);
+    }
+    assert_eq!(walls.positions().count(), border, "walls moved before the first ring");
+
+    for tick in SURVIVAL_RING_TICKS..2 * SURVIVAL_RING_TICKS {
+        rules.update(tick, &mut walls, &mut grid);
+    }
+    let (width, height) = GRID_SIZE;
+    for x in 1..width - 1 {
+        for y in [2, height - 2].iter() {
+            let pos = GridPosition::new(x, *y);
+            let expected = if body.contains(&pos) { CellKind::Snake } else { CellKind::Wall };
+            assert_eq!(grid.get(pos), expected, "{:?}", pos);
+        }
+    }
+    assert!(walls.positions().any(|pos| pos == GridPosition::new(1, height / 2)));
+
+    let (min_x, min_y, max_x, max_y) = rules.spawn_area(2 * SURVIVAL_RING_TICKS, GRID_SIZE);
+    assert!(min_x > 2 && min_y > 3 && max_x <= width - 3 && max_y <= height - 3);
+    assert_eq!(grid, Occupancy::build(GRID_SIZE, &walls, &snake, &[]));
+}
+
+#[test]
+fn zen_has_no_walls_and_no_death() {
+    let (rules, walls, _, _) = start(ModeKind::Zen, 5);
+    assert_eq!(walls.positions().count(), 0);
+    assert!(!rules.is_over(Some(Ate::Itself), 1.0));
+    assert!(rules.endless());
+    assert!(!ModeKind::Classic.rules().endless());
+
+    // Straight up through the top edge, which runs under the score bar.
+    let mut snake = Snake::from_segments(&[Segment::new((4, 1).into(), Direction::Up),
+                                          Segment::new((4, 2).into(), Direction::Up)],
+                                        Direction::Up);
+    let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]);
+    rules.prepare(&mut snake);
+    snake.update(&mut grid);
+    assert_eq!(snake.ate, None);
+    assert_eq!(snake.head_pos(), GridPosition::new(4, GRID_SIZE.1 - 1));
+    assert!(snake.ghost);
+}
+
+#[test]
+fn zen_keeps_the_snake_out_of_the_score_bar() {
+    for topology in Topology::ALL.iter().filter(|topology| topology.wraps()) {
+        for seed in 0..5 {
+            let rules = ModeKind::Zen.rules();
+            let mut rng = Pcg32::seed_from_u64(seed);
+            let walls = rules.walls(&mut rng, ArenaKind::Plain, *topology);
+            let mut snake = rules.snake();
+            let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]).with_topology(*topology);
+            for tick in 0..500 {
+                if tick % 7 == 0 {
+                    snake.steer(Direction::MOVES[rng.gen_range(0, Direction::MOVES.len())]);
+                }
+                rules.prepare(&mut snake);
+                snake.update(&mut grid);
+                assert!(snake.positions().all(|pos| pos.coords().1 != 0),
+                        "{} with seed {}: the snake went under the score bar on tick {}", topology, seed, tick);
+            }
+        }
+    }
+}
+
+#[test]
+fn high_scores_keep_the_best_of_each_mode() {
+    let mut scores = HighScores::default();
+    for points in [3, 10, 1, 7, 7, 2].iter() {
+        scores.submit(ModeKind::Classic, *points);
+    }
+    assert_eq!(scores.table(ModeKind::Classic), &[10, 7, 7, 3, 2][..]);
+    assert_eq!(scores.submit(ModeKind::Classic, 1), None);
+    assert_eq!(scores.submit(ModeKind::Classic, 8), Some(1));
+    assert_eq!(scores.table(ModeKind::Classic), &[10, 8, 7, 7, 3][..]);
+
+    assert!(scores.table(ModeKind::Zen).is_empty());
+    assert_eq!(scores.submit(ModeKind::Zen, 0), Some(0));
+
+    let text = scores.to_string();
+    assert_eq!(text, "classic 10 8 7 7 3\nzen 0\n");
+    assert_eq!(text.parse::<HighScores>(), Ok(scores));
+    assert!("survival ten".parse::<HighScores>().is_err());
+}
+
+#[test]
+fn replays_remember_the_mode() {
+    let mut replay = Replay::new(9, ControlScheme::Absolute, None);
+    assert!(!replay.to_string().contains("mode"));
+    replay.mode = ModeKind::Survival;
+    let parsed: Replay = replay.to_string().parse().unwrap();
+    assert_eq!(parsed, replay);
+}
@@ -0,0 +1,87 @@
+use snake_rust::board::Board;
+use snake_rust::controls::Input;
+use snake_rust::elements::*;
+use snake_rust::powerups::{Effects, PowerUpKind};
+
+/// A snake coiled so that turning up and then left runs the head along its
+/// own body.
+const COIL: &str = "\
+.........
+#########
+:.......:
+:Rrrrd..:
+:...<l..:
+:.......:
+#########
+";
+
+fn turn_into_body(board: &mut Board) -> Vec<Option<Ate>> {
+    let mut grid = board.grid();
+    let mut ate = Vec::new();
+    for dir in [Direction::Up, Direction::Left, Direction::Left, Direction::Left, Direction::Left].iter() {
+        board.snake.apply_input(Input::Absolute(*dir));
+        board.snake.update(&mut grid);
+        ate.push(board.snake.ate);
+        if let Some(Ate::Itself) = board.snake.ate {
+            break;
+        }
+    }
+    assert_eq!(grid, board.grid(), "the occupancy grid is out of date");
+    ate
+}
+
+#[test]
+fn a_snake_dies_in_its_own_body() {
+    let mut board: Board = COIL.parse().unwrap();
+    assert_eq!(turn_into_body(&mut board), vec![Some(Ate::Itself)]);
+}
+
+#[test]
+fn a_ghost_passes_through_its_own_body() {
+    let mut board: Board = COIL.parse().unwrap();
+    board.snake.ghost = true;
+    assert_eq!(turn_into_body(&mut board), vec![None; 5]);
+    assert_eq!(board.snake.length(), 7);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(0, 3));
+}
+
+#[test]
+fn a_wall_breaker_goes_through_one_wall() {
+    let mut board: Board = "\
+.......
+#######
+:.....:
+:.R>..:
+:.....:
+#######
+".parse().unwrap();
+    let mut grid = board.grid();
+    board.snake.breaks_walls = true;
+    board.snake.apply_input(Input::Absolute(Direction::Up));
+
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, None);
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, Some(Ate::BrokenWall));
+    board.walls.remove(board.snake.head_pos());
+    assert!(board.walls.positions().all(|pos| pos != GridPosition::new(3, 1)));
+    assert_eq!(grid, board.grid());
+}
+
+#[test]
+fn effects_stack_and_run_out() {
+    let mut effects = Effects::default();
+    effects.pick_up(PowerUpKind::SlowTime);
+    effects.pick_up(PowerUpKind::SlowTime);
+    effects.pick_up(PowerUpKind::Ghost);
+    effects.pick_up(PowerUpKind::WallBreaker);
+    effects.pick_up(PowerUpKind::WallBreaker);
+    assert_eq!(effects.ticks_per_second(10), 5);
+    assert_eq!(effects.hud(10), vec!["SLOW 12.0s", "GHOST 5.0s", "BREAKER x2"]);
+
+    for _ in 0..200 {
+        effects.tick();
+    }
+    assert_eq!(effects.ticks_per_second(10), 10);
+    assert_eq!(effects.hud(10), vec!["BREAKER x2"]);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::fs;
+use std::path::Path;
+
+use snake_rust::board::Board;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::modes::GameMode;
+use snake_rust::puzzle::Puzzle;
+use snake_rust::replay::Replay;
+use snake_rust::solver::{self, SolveError, MAX_STATES};
+
+use common::board;
+
+fn shipped_puzzles() -> Vec<Puzzle> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("resources/puzzles");
+    let mut puzzles = Vec::new();
+    loop {
+        let path = dir.join(format!("{:02}.txt", puzzles.len() + 1));
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => break,
+        };
+        puzzles.push(text.parse::<Puzzle>().unwrap_or_else(|e| panic!("{}: {}", path.display(), e)));
+    }
+    puzzles
+}
+
+/// Plays `moves` with the game's own snake and checks it ends with every
+/// mouse eaten and the snake alive.
+fn assert_solves(board: &Board, moves: &[Direction]) {
+    let boards = solver::play(board, moves);
+    assert_eq!(boards.len(), moves.len(), "the snake died on the way");
+    assert!(boards.last().unwrap().foods.is_empty(), "mice left over");
+}
+
+#[test]
+fn the_solver_finds_the_shortest_way() {
+    let board = board(&[
+        "........",
+        "########",
+        "#R>..*.#",
+        "#......#",
+        "#*.....#",
+        "########",
+    ]);
+    let moves = solver::solve(&board, 20, MAX_STATES).unwrap();
+    // Three moves to the first mouse, then down two rows and back four columns.
+    assert_eq!(moves.len(), 9);
+    assert_solves(&board, &moves);
+
+    assert_eq!(solver::solve(&board, 8, MAX_STATES), Err(SolveError::Unsolvable));
+    assert_eq!(solver::solve(&board, 20, 10), Err(SolveError::TooManyStates(10)));
+}
+
+#[test]
+fn the_body_gets_in_the_way() {
+    // The mouse is behind the tail, so the snake has to turn round along the
+    // row below, and without one it cannot turn at all.
+    let board = board(&[
+        "........",
+        "########",
+        "#*Rrr>.#",
+        "#......#",
+        "########",
+    ]);
+    let moves = solver::solve(&board, 30, MAX_STATES).unwrap();
+    assert_eq!(moves.len(), 6);
+    assert_solves(&board, &moves);
+
+    let boxed = self::board(&[
+        "........",
+        "########",
+        "#*Rrr>##",
+        "########",
+    ]);
+    assert_eq!(solver::solve(&boxed, 30, MAX_STATES), Err(SolveError::Unsolvable));
+}
+
+#[test]
+fn the_solver_follows_the_tiles() {
+    let board = board(&[
+        "..........",
+        "##########",
+        "#R>1#...*#",
+        "#...#....#",
+        "#...#.1..#",
+        "##########",
+    ]);
+    let moves = solver::solve(&board, 20, MAX_STATES).unwrap();
+    assert_eq!(moves.len(), 5);
+    assert_solves(&board, &moves);
+
+    let locked = self::board(&[
+        "........",
+        "########",
+        "#R>k.*.#",
+        "########",
+    ]);
+    assert!(matches!(solver::solve(&locked, 20, MAX_STATES), Err(SolveError::Unsupported(_))));
+}
+
+#[test]
+fn shipped_puzzles_are_solved_in_par() {
+    let puzzles = shipped_puzzles();
+    assert!(!puzzles.is_empty());
+    for puzzle in puzzles.iter() {
+        let moves = solver::solve(&puzzle.board, puzzle.moves, MAX_STATES)
+            .unwrap_or_else(|e| panic!("{}: {}", puzzle.name, e));
+        assert_eq!(moves.len() as u32, puzzle.par, "{}: par is off", puzzle.name);
+        assert_solves(&puzzle.board, &moves);
+
+        let text = puzzle.to_string();
+        assert_eq!(text.parse::<Puzzle>().unwrap().to_string(), text);
+    }
+}
+
+#[test]
+fn puzzles_keep_to_their_rules() {
+    let puzzle = &shipped_puzzles()[0];
+    assert_eq!(puzzle.move_limit(), Some(puzzle.moves as u64));
+    assert!(puzzle.turn_based() && !puzzle.power_ups());
+    assert_eq!(puzzle.food_items(), 0);
+    assert!(!puzzle.is_won(puzzle.board.foods.len() as u32 - 1, 0));
+    assert!(puzzle.is_won(puzzle.board.foods.len() as u32, 0));
+    assert_eq!(puzzle.stars(puzzle.par as u64), 3);
+    assert_eq!(puzzle.stars(puzzle.moves as u64), 1);
+
+    let board = puzzle.board.to_string();
+    assert!(format!("name X\nmoves 5\npar 6\n{}", board).parse::<Puzzle>().is_err());
+    assert!(format!("name X\n{}", board).parse::<Puzzle>().is_err());
+    assert!(format!("name X\nmoves 5\n{}", board.replacen('*', "$", 1)).parse::<Puzzle>().is_err());
+    assert_eq!(format!("name X\nmoves 50\n{}", board).parse::<Puzzle>().unwrap().par, 50);
+    assert_eq!(format!("name X\nmovs 50\n{}", board).parse::<Puzzle>().unwrap_err(), "unknown key: movs");
+}
+
+#[test]
+fn solutions_come_out_as_replays() {
+    let replay = solver::replay(&[Direction::Right, Direction::Down], Some(2));
+    let text = replay.to_string();
+    assert_eq!(text, "seed 0\ncontrols absolute\npuzzle 3\n0 right\n1 down\n");
+    assert_eq!(text.parse::<Replay>(), Ok(replay));
+    assert!("seed 1\ncontrols absolute\npuzzle 0\n".parse::<Replay>().is_err());
+    assert_eq!(Replay::new(1, ControlScheme::Absolute, None).puzzle, None);
+}
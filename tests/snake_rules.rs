@@ -0,0 +1,237 @@
+use proptest::prelude::*;
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::consts::GRID_SIZE;
+use snake_rust::controls::Input;
+use snake_rust::elements::*;
+use snake_rust::food::{FoodManager, MouseMoves};
+use snake_rust::grid::{CellKind, Occupancy};
+use snake_rust::powerups::{Effects, PowerUp, PowerUpKind, PowerUps};
+use snake_rust::topology::Topology;
+
+/// Something dropped right in front of the snake, so games grow and use
+/// power-ups often enough to be interesting.
+#[derive(Clone, Copy, Debug)]
+enum Drop {
+    Food(FoodKind),
+    PowerUp(PowerUpKind),
+}
+
+/// One tick of play: an optional key press and maybe something to pick up.
+#[derive(Clone, Debug)]
+struct Step {
+    input: Option<Input>,
+    drop: Option<Drop>,
+}
+
+fn direction() -> impl Strategy<Value = Direction> {
+    prop_oneof![Just(Direction::Up), Just(Direction::Down), Just(Direction::Left), Just(Direction::Right)]
+}
+
+fn turn() -> impl Strategy<Value = Turn> {
+    prop_oneof![Just(Turn::Left), Just(Turn::Straight), Just(Turn::Right)]
+}
+
+fn input() -> impl Strategy<Value = Option<Input>> {
+    prop_oneof![
+        4 => Just(None),
+        1 => direction().prop_map(|d| Some(Input::Absolute(d))),
+        1 => turn().prop_map(|t| Some(Input::Relative(t))),
+    ]
+}
+
+fn food_kind() -> impl Strategy<Value = FoodKind> {
+    prop_oneof![Just(FoodKind::Mouse), Just(FoodKind::Golden), Just(FoodKind::Poisoned), Just(FoodKind::Bonus)]
+}
+
+fn power_up_kind() -> impl Strategy<Value = PowerUpKind> {
+    prop_oneof![Just(PowerUpKind::SlowTime), Just(PowerUpKind::Ghost),
+                Just(PowerUpKind::WallBreaker), Just(PowerUpKind::Scissors)]
+}
+
+fn drop() -> impl Strategy<Value = Drop> {
+    prop_oneof![
+        3 => food_kind().prop_map(Drop::Food),
+        1 => power_up_kind().prop_map(Drop::PowerUp),
+    ]
+}
+
+fn steps() -> impl Strategy<Value = Vec<Step>> {
+    let drop = prop::option::weighted(0.2, drop());
+    prop::collection::vec((input(), drop).prop_map(|(input, drop)| Step { input, drop }), 1..400)
+}
+
+struct Game {
+    snake: Snake,
+    foods: FoodManager,
+    powerups: PowerUps,
+    effects: Effects,
+    walls: Wall,
+    grid: Occupancy,
+    rng: Pcg32,
+}
+
+impl Game {
+    fn new(seed: u64) -> Self {
+        let mut rng = Pcg32::seed_from_u64(seed);
+        let walls = Wall::new(&mut rng);
+        let snake = Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into());
+        let mut foods = FoodManager::new(3, Some(MouseMoves { every: 2, breed_every: 7 }));
+        let mut grid = Occupancy::build(GRID_SIZE, &walls, &snake, foods.items());
+        foods.fill(&mut rng, &mut grid);
+        Game { snake, foods, powerups: PowerUps::new(), effects: Effects::default(), walls, grid, rng }
+    }
+
+    /// Moves the snake and applies what it ran into the way the game does.
+    fn step(&mut self) {
+        self.snake.ghost = self.effects.ghost > 0;
+        self.snake.breaks_walls = self.effects.wall_breakers > 0;
+        self.snake.update(&mut self.grid);
+        self.effects.tick();
+
+        let head = self.snake.head_pos();
+        match self.snake.ate {
+            Some(Ate::Food(_)) => {
+                self.foods.take(head, &mut self.grid);
+            }
+            Some(Ate::PowerUp(kind)) => {
+                self.powerups.take(head);
+                self.effects.pick_up(kind);
+                if kind == PowerUpKind::Scissors {
+                    self.snake.shrink(self.snake.length() / 2, &mut self.grid);
+                }
+            }
+            Some(Ate::BrokenWall) => {
+                self.walls.remove(head);
+                self.effects.wall_breakers -= 1;
+            }
+            _ => {},
+        }
+    }
+
+    /// Clears spoiled food and refills the board the way the game does.
+    fn restock(&mut self, tick: u64) {
+        self.foods.update(tick, self.snake.head_pos(), &mut self.rng, &mut self.grid);
+        self.foods.fill(&mut self.rng, &mut self.grid);
+        self.powerups.update(tick, &mut self.rng, &mut self.grid);
+    }
+
+    fn drop(&mut self, drop: Drop) {
+        let dir = self.snake.next_dir.unwrap_or(self.snake.dir);
+        let pos = match self.grid.step(self.snake.head_pos(), dir) {
+            Some(pos) => pos,
+            None => return,
+        };
+        if self.grid.get(pos) != CellKind::Empty {
+            return;
+        }
+        match drop {
+            Drop::Food(kind) => self.foods.place(Food::of_kind(pos, kind), &mut self.grid),
+            Drop::PowerUp(kind) => self.powerups.place(PowerUp { pos, kind, ticks_left: 10 }, &mut self.grid),
+        }
+    }
+}
+
+/// Distance across the board's edges. Rows wrap under the score bar, so
+/// there is one row fewer to go round than there are columns of the grid.
+fn wrapped_distance(a: GridPosition, b: GridPosition) -> i16 {
+    let (ax, ay) = a.coords();
+    let (bx, by) = b.coords();
+    let rows = GRID_SIZE.1 - 1;
+    let dx = (ax - bx).rem_euclid(GRID_SIZE.0);
+    let dy = (ay - by).rem_euclid(rows);
+    dx.min(GRID_SIZE.0 - dx) + dy.min(rows - dy)
+}
+
+fn check_body(snake: &Snake) -> Result<(), TestCaseError> {
+    let segments: Vec<&Segment> = snake.segments().collect();
+    for pair in segments.windows(2) {
+        let (front, back) = (pair[0], pair[1]);
+        prop_assert_eq!(wrapped_distance(front.pos, back.pos), 1,
+                        "segments {:?} and {:?} are not neighbours", front.pos, back.pos);
+        prop_assert_eq!(Topology::Portals.step(back.pos, front.dir, GRID_SIZE), Some(front.pos),
+                        "segment {:?} does not follow {:?}", front.pos, back.pos);
+    }
+
+    let tail = segments[segments.len() - 1];
+    let before_tail = segments[segments.len() - 2];
+    prop_assert_eq!(tail.dir, before_tail.dir, "tail does not point at the next segment");
+    Ok(())
+}
+
+proptest! {
+    #[test]
+    fn update_keeps_the_snake_in_one_piece(seed in any::<u64>(), steps in steps()) {
+        let mut game = Game::new(seed);
+        // Cells of growth owed to the snake: it grows one cell per tick
+        // until the food it ate is used up.
+        let mut owed = 0;
+        let mut ghosted = false;
+
+        for (tick, step) in steps.into_iter().enumerate() {
+            if let Some(input) = step.input {
+                game.snake.apply_input(input);
+            }
+            if let Some(drop) = step.drop {
+                game.drop(drop);
+            }
+
+            let length = game.snake.length();
+            let previous_dir = game.snake.last_update_dir;
+            let neck = game.snake.positions().nth(1).unwrap();
+
+            ghosted |= game.effects.ghost > 0;
+            game.step();
+            check_body(&game.snake)?;
+
+            prop_assert_ne!(game.snake.dir, previous_dir.inverse(), "the snake reversed");
+            prop_assert_ne!(game.snake.head_pos(), neck, "the head went back into the neck");
+
+            let expected = match game.snake.ate {
+                Some(Ate::Food(kind)) if kind.growth() < 0 => {
+                    owed = 0;
+                    (length as i32 + kind.growth()).max(2) as usize
+                }
+                Some(Ate::Food(kind)) => {
+                    owed += kind.growth() as usize;
+                    length
+                }
+                None | Some(Ate::PowerUp(_)) | Some(Ate::BrokenWall) => length,
+                Some(Ate::Itself) | Some(Ate::Wall) | Some(Ate::OtherSnake) => break,
+            };
+            let grows = owed > 0;
+            if grows {
+                owed -= 1;
+            }
+            let mut expected = expected + grows as usize;
+            if let Some(Ate::PowerUp(PowerUpKind::Scissors)) = game.snake.ate {
+                expected -= (expected / 2).min(expected - 2);
+                owed = 0;
+            }
+            prop_assert_eq!(game.snake.length(), expected, "wrong length after {:?}", game.snake.ate);
+            game.restock(tick as u64 + 1);
+
+            // Only a ghost may pass through itself and cover a cell twice.
+            if !ghosted {
+                let mut cells: Vec<GridPosition> = game.snake.positions().collect();
+                let count = cells.len();
+                cells.sort_by_key(|p| p.coords());
+                cells.dedup();
+                prop_assert_eq!(cells.len(), count, "the snake overlaps itself");
+            }
+
+            let mut grid = Occupancy::build(GRID_SIZE, &game.walls, &game.snake, game.foods.items());
+            game.powerups.mark(&mut grid);
+            prop_assert_eq!(&game.grid, &grid, "the occupancy grid is out of date");
+        }
+    }
+
+    #[test]
+    fn turns_are_quarter_turns(dir in direction(), turn in turn()) {
+        let turned = dir.turn(turn);
+        prop_assert_ne!(turned, dir.inverse());
+        prop_assert_eq!(dir.turn_left().turn_right(), dir);
+        prop_assert_eq!(dir.turn_left().turn_left(), dir.inverse());
+    }
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use snake_rust::board::Board;
+use snake_rust::elements::*;
+use snake_rust::tiles::{Tile, Tiles};
+
+use common::board;
+
+#[test]
+fn tiles_round_trip_through_their_characters() {
+    for c in "0123456789neswNESW".chars() {
+        assert_eq!(Tile::from_char(c).map(Tile::to_char), Some(c));
+    }
+    assert_eq!(Tile::from_char('s'), Some(Tile::OneWay(Direction::Down)));
+    assert_eq!(Tile::from_char('W'), Some(Tile::Conveyor(Direction::Left)));
+    assert_eq!(Tile::from_char('#'), None);
+
+    let three = vec![(GridPosition::new(1, 1), Tile::Teleporter(4)),
+                     (GridPosition::new(2, 1), Tile::Teleporter(4)),
+                     (GridPosition::new(3, 1), Tile::Teleporter(4))];
+    assert!(Tiles::new(three).is_err());
+}
+
+#[test]
+fn teleporters_keep_the_heading() {
+    let mut board = board(&[
+        "........",
+        "########",
+        "#R>1...#",
+        "#......#",
+        "#...1..#",
+        "########",
+    ]);
+    assert_eq!(board.tiles.partner(GridPosition::new(3, 2)), Some(GridPosition::new(4, 4)));
+
+    let mut grid = board.grid();
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, None);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(4, 4));
+    assert_eq!(board.snake.dir, Direction::Right);
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(5, 4));
+
+    // The body is followed through the teleporter when the board is read back.
+    let text = board.to_string();
+    assert!(text.contains("tile 1 4 4\n"), "{}", text);
+    let parsed: Board = text.parse().unwrap();
+    assert!(parsed.snake.positions().eq(board.snake.positions()));
+    assert_eq!(parsed.to_string(), text);
+}
+
+#[test]
+fn one_way_tiles_only_let_in_one_way() {
+    let rows = [
+        "........",
+        "########",
+        "#......#",
+        "#R>e...#",
+        "#..w...#",
+        "########",
+    ];
+    let mut board = board(&rows);
+    let mut grid = board.grid();
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, None);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(3, 3));
+
+    assert!(board.snake.steer(Direction::Down));
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.ate, Some(Ate::Wall), "entering a left arrow heading down");
+    assert_eq!(board.snake.head_pos(), GridPosition::new(3, 3));
+
+    let grid = self::board(&rows).grid();
+    assert_eq!(grid.step(GridPosition::new(3, 3), Direction::Down), None);
+    assert_eq!(grid.step(GridPosition::new(4, 4), Direction::Left), Some(GridPosition::new(3, 4)));
+    assert_eq!(grid.step(GridPosition::new(4, 3), Direction::Left), None);
+}
+
+#[test]
+fn conveyors_carry_the_snake() {
+    let mut board = board(&[
+        "........",
+        "########",
+        "#......#",
+        "#R>EE..#",
+        "#......#",
+        "########",
+    ]);
+    let mut grid = board.grid();
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(3, 3));
+
+    // Steering is ignored while on the belt.
+    assert!(board.snake.steer(Direction::Down));
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(4, 3));
+    board.snake.update(&mut grid);
+    assert_eq!(board.snake.head_pos(), GridPosition::new(5, 3));
+    assert_eq!(board.snake.dir, Direction::Right);
+
+    let mut against = self::board(&[
+        "........",
+        "########",
+        "#......#",
+        "#R>W...#",
+        "#......#",
+        "########",
+    ]);
+    let mut grid = against.grid();
+    against.snake.update(&mut grid);
+    assert_eq!(against.snake.ate, Some(Ate::Wall));
+}
@@ -0,0 +1,162 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+use snake_rust::arena::ArenaKind;
+use snake_rust::consts::*;
+use snake_rust::controls::ControlScheme;
+use snake_rust::elements::*;
+use snake_rust::grid::{CellKind, Occupancy};
+use snake_rust::replay::Replay;
+use snake_rust::settings::Settings;
+use snake_rust::topology::Topology;
+
+fn open_board(snake: &Snake, topology: Topology) -> Occupancy {
+    Occupancy::build(GRID_SIZE, &Wall::from_positions(Vec::new()), snake, &[]).with_topology(topology)
+}
+
+#[test]
+fn topologies_round_trip_through_their_names() {
+    for topology in Topology::ALL.iter() {
+        assert_eq!(topology.to_string().parse::<Topology>(), Ok(*topology));
+    }
+    assert!("sphere".parse::<Topology>().is_err());
+
+    let mut topology = Topology::Portals;
+    for _ in 0..Topology::ALL.len() {
+        topology = topology.next();
+    }
+    assert_eq!(topology, Topology::Portals);
+}
+
+#[test]
+fn edges_are_joined_as_promised() {
+    let (w, h) = GRID_SIZE;
+    let step = |topology: Topology, pos: (i16, i16), dir| topology.step(pos.into(), dir, GRID_SIZE);
+
+    for topology in Topology::ALL.iter() {
+        assert_eq!(step(*topology, (5, 5), Direction::Left), Some(GridPosition::new(4, 5)));
+    }
+
+    assert_eq!(step(Topology::Portals, (0, 5), Direction::Left), Some(GridPosition::new(w - 1, 5)));
+    assert_eq!(step(Topology::Torus, (3, h - 1), Direction::Down), Some(GridPosition::new(3, 1)));
+    assert_eq!(step(Topology::Torus, (3, 1), Direction::Up), Some(GridPosition::new(3, h - 1)));
+    assert_eq!(step(Topology::Bounded, (0, 5), Direction::Left), None);
+    assert_eq!(step(Topology::Bounded, (3, h - 1), Direction::Down), None);
+
+    assert_eq!(step(Topology::Klein, (0, 5), Direction::Left), Some(GridPosition::new(w - 1, 5)));
+    assert_eq!(step(Topology::Klein, (3, 1), Direction::Up), Some(GridPosition::new(w - 4, h - 1)));
+    assert_eq!(step(Topology::Projective, (w - 1, 5), Direction::Right), Some(GridPosition::new(0, h - 5)));
+    assert_eq!(step(Topology::Projective, (w - 1, 1), Direction::Right), Some(GridPosition::new(0, h - 1)));
+    assert_eq!(step(Topology::Projective, (3, h - 1), Direction::Down), Some(GridPosition::new(w - 4, 1)));
+}
+
+#[test]
+fn every_step_can_be_walked_back() {
+    for topology in Topology::ALL.iter() {
+        for y in 1..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                let pos = GridPosition::new(x, y);
+                for dir in Direction::MOVES.iter() {
+                    if let Some(next) = topology.step(pos, *dir, GRID_SIZE) {
+                        assert_eq!(topology.step(next, dir.inverse(), GRID_SIZE), Some(pos),
+                                   "{}: {:?} {:?}", topology, pos.coords(), dir);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn nothing_steps_into_the_score_bar() {
+    for topology in Topology::ALL.iter() {
+        for y in 1..GRID_SIZE.1 {
+            for x in 0..GRID_SIZE.0 {
+                for dir in Direction::MOVES.iter() {
+                    if let Some(next) = topology.step(GridPosition::new(x, y), *dir, GRID_SIZE) {
+                        assert_ne!(next.coords().1, 0, "{}: {:?} from {:?}", topology, dir, (x, y));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn the_border_suits_the_topology() {
+    let mut rng = Pcg32::seed_from_u64(9);
+    let walls = Wall::new(&mut rng);
+    let mut inner = walls.clone();
+    inner.add(GridPosition::new(10, 10));
+    let border = |walls: &Wall| walls.positions().filter(|pos| pos.coords().0 == 0).count();
+
+    assert!(Topology::Portals.shape(walls.clone(), GRID_SIZE).positions().eq(walls.positions()));
+    assert_eq!(border(&Topology::Bounded.shape(walls.clone(), GRID_SIZE)), (GRID_SIZE.1 - 1) as usize);
+
+    let torus = Topology::Torus.shape(inner, GRID_SIZE);
+    assert!(torus.positions().eq(std::iter::once(GridPosition::new(10, 10))));
+}
+
+#[test]
+fn arenas_stay_in_reach_on_every_topology() {
+    let snake = Snake::new((GRID_SIZE.0 / 4, GRID_SIZE.1 / 2).into());
+    for topology in Topology::ALL.iter() {
+        for kind in ArenaKind::ALL.iter() {
+            for seed in 0..5 {
+                let walls = kind.build(&mut Pcg32::seed_from_u64(seed), GRID_SIZE, &snake, *topology);
+                let grid = Occupancy::build(GRID_SIZE, &walls, &snake, &[]).with_topology(*topology);
+                let open = grid.reachable(snake.head_pos());
+                for y in 1..GRID_SIZE.1 {
+                    for x in 0..GRID_SIZE.0 {
+                        let pos = GridPosition::new(x, y);
+                        assert!(grid.get(pos) == CellKind::Wall || open.contains(&pos),
+                                "{} {} {}: {:?} cannot be reached", topology, kind, seed, pos.coords());
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn the_snake_crashes_into_the_edge_of_a_box() {
+    let mut snake = Snake::new((GRID_SIZE.0 - 2, 10).into());
+    let mut grid = open_board(&snake, Topology::Bounded);
+    snake.update(&mut grid);
+    assert_eq!(snake.ate, None);
+    assert_eq!(snake.head_pos(), GridPosition::new(GRID_SIZE.0 - 1, 10));
+
+    snake.update(&mut grid);
+    assert_eq!(snake.ate, Some(Ate::Wall));
+    assert_eq!(snake.head_pos(), GridPosition::new(GRID_SIZE.0 - 1, 10));
+}
+
+#[test]
+fn the_snake_comes_back_mirrored_on_a_klein_bottle() {
+    let mut snake = Snake::new((3, 1).into());
+    let mut grid = open_board(&snake, Topology::Klein);
+    assert!(snake.steer(Direction::Up));
+    snake.update(&mut grid);
+    assert_eq!(snake.ate, None);
+    assert!(snake.wrapped);
+    assert_eq!(snake.head_pos(), GridPosition::new(GRID_SIZE.0 - 4, GRID_SIZE.1 - 1));
+    assert_eq!(snake.dir, Direction::Up);
+
+    // Every free cell under the score bar is still in reach, across the
+    // mirrored edges too.
+    assert_eq!(grid.reachable(snake.head_pos()).len(), (GRID_SIZE.0 * (GRID_SIZE.1 - 1)) as usize);
+}
+
+#[test]
+fn the_topology_is_remembered() {
+    let mut replay = Replay::new(5, ControlScheme::Absolute, None);
+    replay.topology = Topology::Projective;
+    let text = replay.to_string();
+    assert!(text.contains("topology projective\n"));
+    assert_eq!(text.parse::<Replay>(), Ok(replay));
+    assert!("seed 1\ncontrols absolute\ntopology sphere\n".parse::<Replay>().is_err());
+
+    let settings: Settings = "topology = klein".parse().unwrap();
+    assert_eq!(settings.topology, Topology::Klein);
+    assert_eq!(settings.to_string().parse::<Settings>(), Ok(settings));
+}
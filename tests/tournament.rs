@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+
+use snake_rust::arena::ArenaKind;
+use snake_rust::bots::{Bot, BotTier};
+use snake_rust::consts::DUEL_MIN_SIZE;
+use snake_rust::duel::{self, Duel, DuelConfig, Record};
+use snake_rust::grid::CellKind;
+use snake_rust::topology::Topology;
+use snake_rust::tournament::*;
+
+fn config() -> DuelConfig {
+    DuelConfig { size: (16, 12), speed: 10, seconds: 20, arena: ArenaKind::Obstacles, topology: Topology::Torus }
+}
+
+fn duel(a: BotTier, b: BotTier, seed: u64) -> Record {
+    let mut bots: Vec<Box<dyn Bot>> = vec![a.bot(), b.bot()];
+    duel::play(config(), seed, &mut bots, vec![a.to_string(), b.to_string()])
+}
+
+#[test]
+fn duels_start_mirrored_on_open_cells() {
+    for arena in ArenaKind::ALL.iter() {
+        for seed in 0..5 {
+            let config = DuelConfig { arena: *arena, ..config() };
+            let duel = Duel::new(config, seed);
+            let (first, second) = (&duel.snakes[0], &duel.snakes[1]);
+            assert_eq!(first.length(), second.length());
+            assert_eq!(first.dir.inverse(), second.dir);
+            for (side, snake) in duel.snakes.iter().enumerate() {
+                for pos in snake.positions() {
+                    assert_eq!(duel.grid.get(pos), CellKind::Rival(side as u8), "{} with seed {}", arena, seed);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn every_arena_fits_the_smallest_duel() {
+    for arena in ArenaKind::ALL.iter() {
+        for topology in Topology::ALL.iter() {
+            for seed in 0..20 {
+                let config = DuelConfig { size: DUEL_MIN_SIZE, arena: *arena, topology: *topology, ..config() };
+                let duel = Duel::new(config, seed);
+                assert!(duel.alive.iter().all(|alive| *alive), "{} on {} with seed {}", arena, topology, seed);
+            }
+        }
+    }
+}
+
+#[test]
+fn duels_play_back_the_same_every_time() {
+    let record = duel(BotTier::Greedy, BotTier::RandomSafe, 3);
+    assert_eq!(duel(BotTier::Greedy, BotTier::RandomSafe, 3), record);
+    assert!(record.ticks <= config().max_ticks());
+    assert_eq!(record.moves.len() as u64, record.ticks);
+
+    let text = record.to_string();
+    assert!(text.starts_with("seed 3\nsize 16 12\nspeed 10\nseconds 20\narena obstacles\ntopology torus\nsnake greedy\nsnake random\n"));
+    let parsed: Record = text.parse().unwrap();
+    assert_eq!(parsed, record);
+
+    let again = parsed.replay();
+    assert!(again.is_over());
+    assert_eq!(again.tick, record.ticks);
+    assert_eq!(again.winner(), record.winner);
+
+    assert!("seed 1\nsize 16\n".parse::<Record>().is_err());
+    assert!(text.replace("winner", "loser").parse::<Record>().is_err());
+    // Too small for the symmetric arena a duel may be played on.
+    assert!(text.replace("size 16 12", "size 6 6").parse::<Record>().is_err());
+}
+
+#[test]
+fn elo_moves_points_from_the_loser_to_the_winner() {
+    let (a, b) = elo(1500.0, 1500.0, 1.0);
+    assert_eq!((a, b), (1516.0, 1484.0));
+    let (a, b) = elo(1600.0, 1400.0, 0.5);
+    assert!(a < 1600.0 && b > 1400.0);
+    assert!((a + b - 3000.0).abs() < 1e-9);
+}
+
+#[test]
+fn trueskill_grows_surer_with_every_game() {
+    let fresh = Skill::default();
+    let (winner, loser) = trueskill(fresh, fresh, false);
+    assert!(winner.mu > fresh.mu && loser.mu < fresh.mu);
+    assert!((winner.mu - fresh.mu - (fresh.mu - loser.mu)).abs() < 1e-9);
+    assert!(winner.sigma < fresh.sigma && loser.sigma < fresh.sigma);
+
+    let (a, b) = trueskill(fresh, fresh, true);
+    assert!((a.mu - fresh.mu).abs() < 1e-9 && (b.mu - fresh.mu).abs() < 1e-9);
+    assert!(a.sigma < fresh.sigma);
+
+    // An upset moves the ratings further than the expected result does.
+    let strong = Skill { mu: 35.0, sigma: 3.0 };
+    let weak = Skill { mu: 15.0, sigma: 3.0 };
+    let (expected, _) = trueskill(strong, weak, false);
+    let (upset, _) = trueskill(weak, strong, false);
+    assert!(upset.mu - weak.mu > expected.mu - strong.mu);
+}
+
+#[test]
+fn swiss_rounds_avoid_rematches() {
+    let names: Vec<String> = ["a", "b", "c", "d", "e"].iter().map(|name| name.to_string()).collect();
+    let mut standings = Standings::new(&names);
+    standings.record(0, 1, Some(0));
+    standings.record(2, 3, Some(1));
+    standings.entries[4].byes = 1;
+    let met: HashSet<(usize, usize)> = [(0, 1), (2, 3)].iter().cloned().collect();
+
+    let (pairs, bye) = swiss(&standings, &met);
+    assert_eq!(pairs.len(), 2);
+    assert!(bye.is_some() && bye != Some(4));
+    for (a, b) in pairs.iter() {
+        assert!(!met.contains(&(*a.min(b), *a.max(b))));
+    }
+    assert_eq!(round_robin(4).len(), 6);
+}
+
+#[test]
+fn every_pairing_is_played_from_both_sides() {
+    let tiers = [BotTier::RandomSafe, BotTier::Greedy, BotTier::LookAhead];
+    let names: Vec<String> = tiers.iter().map(|tier| tier.to_string()).collect();
+    let tournament = Tournament { format: Format::RoundRobin, rounds: 1, seed: 10, config: config() };
+    let mut records = Vec::new();
+    let standings = tournament.run(&names, |i| tiers[i].bot(), |record| records.push(record.clone()));
+
+    assert_eq!(records.len(), 6);
+    for pair in records.chunks(2) {
+        assert_eq!(pair[0].seed, pair[1].seed);
+        assert_eq!(pair[0].names[0], pair[1].names[1]);
+    }
+    assert!(standings.entries.iter().all(|entry| entry.games() == 4));
+    let decided = records.iter().filter(|record| record.winner.is_some()).count() as u32;
+    assert_eq!(standings.entries.iter().map(|entry| entry.wins).sum::<u32>(), decided);
+    assert_eq!(standings.ranking().len(), 3);
+    assert!(standings.to_string().starts_with("rank  bot"));
+}
@@ -0,0 +1,110 @@
+use snake_rust::consts::GRID_SIZE;
+use snake_rust::elements::*;
+use snake_rust::grid::Occupancy;
+
+const LAST_X: i16 = GRID_SIZE.0 - 1;
+const LAST_Y: i16 = GRID_SIZE.1 - 1;
+
+#[test]
+fn new_from_move_wraps_at_every_edge() {
+    let cases = [
+        ((5, 5), Direction::Up, (5, 4)),
+        ((5, 5), Direction::Down, (5, 6)),
+        ((5, 5), Direction::Left, (4, 5)),
+        ((5, 5), Direction::Right, (6, 5)),
+        ((5, 5), Direction::None, (5, 5)),
+        ((0, 7), Direction::Left, (LAST_X, 7)),
+        ((LAST_X, 7), Direction::Right, (0, 7)),
+        ((9, 1), Direction::Up, (9, LAST_Y)),
+        ((9, LAST_Y), Direction::Down, (9, 1)),
+        ((0, 0), Direction::Left, (LAST_X, 0)),
+        ((LAST_X, LAST_Y), Direction::Down, (LAST_X, 1)),
+    ];
+
+    for (from, dir, to) in cases.iter() {
+        let pos = GridPosition::new_from_move((*from).into(), *dir);
+        assert_eq!(pos, (*to).into(), "moving {:?} from {:?}", dir, from);
+    }
+}
+
+/// The frame `Wall::new` draws: full top and bottom rows under the score bar
+/// and side columns, with holes only where asked for.
+fn frame(holes: &[i16]) -> Wall {
+    let mut cells = Vec::new();
+    for i in 0..GRID_SIZE.0 {
+        for j in 1..GRID_SIZE.1 {
+            let side = (i == 0 || i == LAST_X) && !holes.contains(&j);
+            if j == 1 || j == LAST_Y || side {
+                cells.push(GridPosition::new(i, j));
+            }
+        }
+    }
+    Wall::from_positions(cells)
+}
+
+/// Runs a snake from `start` for `ticks` ticks in `dir` and returns every
+/// head position and what it ran into.
+fn run(walls: &Wall, start: (i16, i16), dir: Direction, ticks: usize) -> Vec<((i16, i16), Option<Ate>, bool)> {
+    let mut snake = Snake::new(start.into());
+    let mut grid = Occupancy::build(GRID_SIZE, walls, &snake, &[]);
+    snake.steer(dir);
+
+    let mut trace = Vec::new();
+    for _ in 0..ticks {
+        snake.update(&mut grid);
+        trace.push((snake.head_pos().coords(), snake.ate, snake.wrapped));
+        if let Some(Ate::Wall) | Some(Ate::Itself) = snake.ate {
+            break;
+        }
+    }
+    trace
+}
+
+#[test]
+fn snake_wraps_through_matching_holes() {
+    let walls = frame(&[10]);
+    let trace = run(&walls, (LAST_X - 2, 10), Direction::Right, 4);
+
+    assert_eq!(trace, vec![
+        ((LAST_X - 1, 10), None, false),
+        ((LAST_X, 10), None, false),
+        ((0, 10), None, true),
+        ((1, 10), None, false),
+    ]);
+}
+
+#[test]
+fn snake_dies_in_a_wall_without_a_hole() {
+    let walls = frame(&[]);
+    let trace = run(&walls, (LAST_X - 2, 10), Direction::Right, 4);
+
+    assert_eq!(trace, vec![
+        ((LAST_X - 1, 10), None, false),
+        ((LAST_X, 10), Some(Ate::Wall), false),
+    ]);
+}
+
+#[test]
+fn snake_dies_when_the_hole_on_the_other_side_is_closed() {
+    let mut walls_cells: Vec<GridPosition> = frame(&[10]).positions().collect();
+    walls_cells.push(GridPosition::new(0, 10));
+    let walls = Wall::from_positions(walls_cells);
+    let trace = run(&walls, (LAST_X - 2, 10), Direction::Right, 4);
+
+    assert_eq!(trace, vec![
+        ((LAST_X - 1, 10), None, false),
+        ((LAST_X, 10), None, false),
+        ((0, 10), Some(Ate::Wall), true),
+    ]);
+}
+
+#[test]
+fn snake_cannot_wrap_through_the_top_and_bottom_rows() {
+    let walls = frame(&[10]);
+    let trace = run(&walls, (8, 3), Direction::Up, 4);
+
+    assert_eq!(trace, vec![
+        ((8, 2), None, false),
+        ((8, 1), Some(Ate::Wall), false),
+    ]);
+}